@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract Wallet {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function setOwner(address newOwner) public {
+        owner = newOwner;
+    }
+
+    function withdraw(uint256 amount) public {
+        require(msg.sender == owner);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
+
+contract WalletFixed {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function setOwner(address newOwner) public {
+        require(msg.sender == owner);
+        owner = newOwner;
+    }
+
+    function withdraw(uint256 amount) public {
+        require(msg.sender == owner);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
@@ -0,0 +1,72 @@
+; runtime code of the vulnerable contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x8da5cb5b
+EQ
+PUSH2 @owner
+JUMPI
+DUP1
+PUSH4 0x13af4035
+EQ
+PUSH2 @setOwner
+JUMPI
+DUP1
+PUSH4 0x2e1a7d4d
+EQ
+PUSH2 @withdraw
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+owner:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+setOwner:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+SSTORE
+STOP
+withdraw:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SLOAD
+CALLER
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x04
+CALLDATALOAD
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
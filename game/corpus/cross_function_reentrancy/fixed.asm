@@ -0,0 +1,228 @@
+; runtime code of the fixed contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x27e235e3
+EQ
+PUSH2 @balances
+JUMPI
+DUP1
+PUSH4 0x2ecd4e7d
+EQ
+PUSH2 @debts
+JUMPI
+DUP1
+PUSH4 0xd0e30db0
+EQ
+PUSH2 @deposit
+JUMPI
+DUP1
+PUSH4 0x3ccfd60b
+EQ
+PUSH2 @withdraw
+JUMPI
+DUP1
+PUSH4 0xe68d3569
+EQ
+PUSH2 @borrow
+JUMPI
+DUP1
+PUSH4 0x402d8883
+EQ
+PUSH2 @repay
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+balances:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+debts:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x01
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+deposit:
+JUMPDEST
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+CALLVALUE
+ADD
+SWAP1
+SSTORE
+STOP
+withdraw:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x02
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x01
+PUSH1 0x02
+SSTORE
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x01
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+DUP1
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+DUP3
+SSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+DUP5
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+POP
+POP
+PUSH1 0x00
+PUSH1 0x02
+SSTORE
+STOP
+borrow:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x01
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+DUP1
+ISZERO
+PUSH2 @revert
+JUMPI
+DUP1
+DUP3
+SSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+DUP5
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
+repay:
+JUMPDEST
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x01
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+CALLVALUE
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SWAP1
+SSTORE
+STOP
@@ -0,0 +1,79 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract Lender {
+    mapping(address => uint256) public balances;
+    mapping(address => uint256) public debts;
+    bool locked;
+
+    constructor() payable {}
+
+    function deposit() public payable {
+        balances[msg.sender] += msg.value;
+    }
+
+    function withdraw() public {
+        require(!locked);
+        locked = true;
+        require(debts[msg.sender] == 0);
+        uint256 amount = balances[msg.sender];
+        require(amount > 0);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+        balances[msg.sender] = 0;
+        locked = false;
+    }
+
+    /// Lends the balance of the caller, which stays as collateral until repaid.
+    function borrow() public {
+        require(debts[msg.sender] == 0);
+        uint256 amount = balances[msg.sender];
+        require(amount > 0);
+        debts[msg.sender] = amount;
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+
+    function repay() public payable {
+        require(msg.value == debts[msg.sender]);
+        debts[msg.sender] = 0;
+    }
+}
+
+contract LenderFixed {
+    mapping(address => uint256) public balances;
+    mapping(address => uint256) public debts;
+    bool locked;
+
+    constructor() payable {}
+
+    function deposit() public payable {
+        balances[msg.sender] += msg.value;
+    }
+
+    function withdraw() public {
+        require(!locked);
+        locked = true;
+        require(debts[msg.sender] == 0);
+        uint256 amount = balances[msg.sender];
+        require(amount > 0);
+        balances[msg.sender] = 0;
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+        locked = false;
+    }
+
+    function borrow() public {
+        require(debts[msg.sender] == 0);
+        uint256 amount = balances[msg.sender];
+        require(amount > 0);
+        debts[msg.sender] = amount;
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+
+    function repay() public payable {
+        require(msg.value == debts[msg.sender]);
+        debts[msg.sender] = 0;
+    }
+}
@@ -0,0 +1,99 @@
+; runtime code of the fixed contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x8da5cb5b
+EQ
+PUSH2 @owner
+JUMPI
+DUP1
+PUSH4 0x6704fe9f
+EQ
+PUSH2 @verify
+JUMPI
+DUP1
+PUSH4 0xf3fef3a3
+EQ
+PUSH2 @withdraw
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+owner:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+verify:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+SLOAD
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
+withdraw:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+ADDRESS
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH4 0x6704fe9f
+PUSH1 0xe0
+SHL
+PUSH1 0x00
+MSTORE
+CALLER
+PUSH1 0x04
+MSTORE
+PUSH1 0x24
+CALLDATALOAD
+PUSH1 0x24
+MSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x44
+PUSH1 0x00
+PUSH1 0x04
+CALLDATALOAD
+GAS
+DELEGATECALL
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x24
+CALLDATALOAD
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
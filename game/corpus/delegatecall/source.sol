@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract VerifiedVault {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function verify(address account, uint256) public view {
+        require(account == owner);
+    }
+
+    /// Pays `amount` if `verifier`, run in the context of the vault, accepts it.
+    function withdraw(address verifier, uint256 amount) public {
+        (bool ok, ) = verifier.delegatecall(abi.encodeWithSignature("verify(address,uint256)", msg.sender, amount));
+        require(ok);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
+
+contract VerifiedVaultFixed {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function verify(address account, uint256) public view {
+        require(account == owner);
+    }
+
+    function withdraw(address verifier, uint256 amount) public {
+        require(verifier == address(this));
+        (bool ok, ) = verifier.delegatecall(abi.encodeWithSignature("verify(address,uint256)", msg.sender, amount));
+        require(ok);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
@@ -0,0 +1,118 @@
+; runtime code of the vulnerable contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x70a08231
+EQ
+PUSH2 @balanceOf
+JUMPI
+DUP1
+PUSH4 0xd96a094a
+EQ
+PUSH2 @buy
+JUMPI
+DUP1
+PUSH4 0xe4849b32
+EQ
+PUSH2 @sell
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+balanceOf:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+buy:
+JUMPDEST
+PUSH1 0x04
+CALLDATALOAD
+DUP1
+PUSH8 0x0de0b6b3a7640000
+MUL
+CALLVALUE
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+DUP3
+ADD
+SWAP1
+SSTORE
+POP
+STOP
+sell:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+DUP3
+DUP2
+LT
+PUSH2 @revert
+JUMPI
+DUP3
+SWAP1
+SUB
+SWAP1
+SSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+DUP5
+PUSH8 0x0de0b6b3a7640000
+MUL
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+POP
+STOP
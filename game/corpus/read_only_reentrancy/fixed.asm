@@ -0,0 +1,375 @@
+; runtime code of the fixed contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0xce7c2ac2
+EQ
+PUSH2 @shares
+JUMPI
+DUP1
+PUSH4 0x3a98ef39
+EQ
+PUSH2 @totalShares
+JUMPI
+DUP1
+PUSH4 0x01e1d114
+EQ
+PUSH2 @totalAssets
+JUMPI
+DUP1
+PUSH4 0x2ecd4e7d
+EQ
+PUSH2 @debts
+JUMPI
+DUP1
+PUSH4 0x2c62fa10
+EQ
+PUSH2 @assetsOf
+JUMPI
+DUP1
+PUSH4 0xd0e30db0
+EQ
+PUSH2 @deposit
+JUMPI
+DUP1
+PUSH4 0x2e1a7d4d
+EQ
+PUSH2 @withdraw
+JUMPI
+DUP1
+PUSH4 0xe68d3569
+EQ
+PUSH2 @borrow
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+shares:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+totalShares:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x01
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+totalAssets:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x02
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+debts:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x04
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+assetsOf:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x03
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x01
+SLOAD
+DUP1
+ISZERO
+PUSH2 @emptyview
+JUMPI
+SWAP1
+PUSH1 0x02
+SLOAD
+MUL
+DIV
+PUSH2 @assetsview
+JUMP
+emptyview:
+JUMPDEST
+POP
+POP
+PUSH1 0x00
+assetsview:
+JUMPDEST
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+deposit:
+JUMPDEST
+PUSH1 0x03
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x01
+PUSH1 0x03
+SSTORE
+PUSH1 0x01
+SLOAD
+DUP1
+ISZERO
+PUSH2 @first
+JUMPI
+CALLVALUE
+MUL
+PUSH1 0x02
+SLOAD
+SWAP1
+DIV
+PUSH2 @minted
+JUMP
+first:
+JUMPDEST
+POP
+CALLVALUE
+minted:
+JUMPDEST
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+DUP3
+ADD
+SWAP1
+SSTORE
+PUSH1 0x01
+SLOAD
+ADD
+PUSH1 0x01
+SSTORE
+PUSH1 0x02
+SLOAD
+CALLVALUE
+ADD
+PUSH1 0x02
+SSTORE
+PUSH1 0x00
+PUSH1 0x03
+SSTORE
+STOP
+withdraw:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x03
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x01
+PUSH1 0x03
+SSTORE
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x04
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+PUSH1 0x04
+CALLDATALOAD
+DUP1
+DUP3
+LT
+PUSH2 @revert
+JUMPI
+DUP1
+PUSH1 0x02
+SLOAD
+MUL
+PUSH1 0x01
+SLOAD
+SWAP1
+DIV
+DUP2
+DUP4
+SUB
+DUP5
+SSTORE
+DUP2
+PUSH1 0x01
+SLOAD
+SUB
+PUSH1 0x01
+SSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+DUP5
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x02
+SLOAD
+SUB
+PUSH1 0x02
+SSTORE
+POP
+POP
+POP
+PUSH1 0x00
+PUSH1 0x03
+SSTORE
+STOP
+borrow:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x04
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x03
+SLOAD
+PUSH2 @revert
+JUMPI
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x01
+SLOAD
+DUP1
+ISZERO
+PUSH2 @emptyborrow
+JUMPI
+SWAP1
+PUSH1 0x02
+SLOAD
+MUL
+DIV
+PUSH2 @assetsborrow
+JUMP
+emptyborrow:
+JUMPDEST
+POP
+POP
+PUSH1 0x00
+assetsborrow:
+JUMPDEST
+DUP1
+ISZERO
+PUSH2 @revert
+JUMPI
+DUP1
+DUP3
+SSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+DUP5
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract SharesVault {
+    mapping(address => uint256) public shares;
+    uint256 public totalShares;
+    uint256 public totalAssets;
+    bool locked;
+    mapping(address => uint256) public debts;
+
+    modifier nonReentrant() {
+        require(!locked);
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    constructor() payable {}
+
+    function assetsOf(address account) public view returns (uint256) {
+        if (totalShares == 0) return 0;
+        return shares[account] * totalAssets / totalShares;
+    }
+
+    function deposit() public payable nonReentrant {
+        uint256 minted = totalShares == 0 ? msg.value : msg.value * totalShares / totalAssets;
+        shares[msg.sender] += minted;
+        totalShares += minted;
+        totalAssets += msg.value;
+    }
+
+    function withdraw(uint256 amount) public nonReentrant {
+        require(debts[msg.sender] == 0);
+        require(shares[msg.sender] >= amount);
+        uint256 assets = amount * totalAssets / totalShares;
+        shares[msg.sender] -= amount;
+        totalShares -= amount;
+        (bool sent, ) = msg.sender.call{value: assets}("");
+        require(sent);
+        totalAssets -= assets;
+    }
+
+    /// Lends the value of the shares of the caller, which stay as collateral.
+    function borrow() public {
+        require(debts[msg.sender] == 0);
+        uint256 amount = assetsOf(msg.sender);
+        require(amount > 0);
+        debts[msg.sender] = amount;
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
+
+/// Same as SharesVault, but the view refuses to answer in the middle of a withdrawal.
+contract SharesVaultFixed {
+    mapping(address => uint256) public shares;
+    uint256 public totalShares;
+    uint256 public totalAssets;
+    bool locked;
+    mapping(address => uint256) public debts;
+
+    modifier nonReentrant() {
+        require(!locked);
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    constructor() payable {}
+
+    function assetsOf(address account) public view returns (uint256) {
+        require(!locked);
+        if (totalShares == 0) return 0;
+        return shares[account] * totalAssets / totalShares;
+    }
+
+    function deposit() public payable nonReentrant {
+        uint256 minted = totalShares == 0 ? msg.value : msg.value * totalShares / totalAssets;
+        shares[msg.sender] += minted;
+        totalShares += minted;
+        totalAssets += msg.value;
+    }
+
+    function withdraw(uint256 amount) public nonReentrant {
+        require(debts[msg.sender] == 0);
+        require(shares[msg.sender] >= amount);
+        uint256 assets = amount * totalAssets / totalShares;
+        shares[msg.sender] -= amount;
+        totalShares -= amount;
+        (bool sent, ) = msg.sender.call{value: assets}("");
+        require(sent);
+        totalAssets -= assets;
+    }
+
+    function borrow() public {
+        require(debts[msg.sender] == 0);
+        uint256 amount = assetsOf(msg.sender);
+        require(amount > 0);
+        debts[msg.sender] = amount;
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
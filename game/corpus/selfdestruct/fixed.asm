@@ -0,0 +1,46 @@
+; runtime code of the fixed contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x8da5cb5b
+EQ
+PUSH2 @owner
+JUMPI
+DUP1
+PUSH4 0x43d726d6
+EQ
+PUSH2 @close
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+owner:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+close:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+SLOAD
+CALLER
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+CALLER
+SELFDESTRUCT
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract Closable {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function close() public {
+        selfdestruct(payable(msg.sender));
+    }
+}
+
+contract ClosableFixed {
+    address public owner;
+
+    constructor() payable {
+        owner = msg.sender;
+    }
+
+    function close() public {
+        require(msg.sender == owner);
+        selfdestruct(payable(msg.sender));
+    }
+}
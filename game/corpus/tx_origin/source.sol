@@ -0,0 +1,43 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+contract OriginWallet {
+    address public constant owner = address(uint160(0xa11ce));
+    bool private locked;
+
+    constructor() payable {}
+
+    modifier nonReentrant() {
+        require(!locked);
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    /// Only the owner moves the funds of the wallet.
+    function transfer(address payable to, uint256 amount) public nonReentrant {
+        require(tx.origin == owner);
+        (bool sent, ) = to.call{value: amount}("");
+        require(sent);
+    }
+}
+
+contract OriginWalletFixed {
+    address public constant owner = address(uint160(0xa11ce));
+    bool private locked;
+
+    constructor() payable {}
+
+    modifier nonReentrant() {
+        require(!locked);
+        locked = true;
+        _;
+        locked = false;
+    }
+
+    function transfer(address payable to, uint256 amount) public nonReentrant {
+        require(msg.sender == owner);
+        (bool sent, ) = to.call{value: amount}("");
+        require(sent);
+    }
+}
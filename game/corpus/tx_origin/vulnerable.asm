@@ -0,0 +1,69 @@
+; runtime code of the vulnerable contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x8da5cb5b
+EQ
+PUSH2 @owner
+JUMPI
+DUP1
+PUSH4 0xa9059cbb
+EQ
+PUSH2 @transfer
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+owner:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH20 0x00000000000000000000000000000000000a11ce
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+transfer:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+; nonReentrant, locked in slot 0
+PUSH1 0x00
+SLOAD
+PUSH2 @revert
+JUMPI
+PUSH1 0x01
+PUSH1 0x00
+SSTORE
+; require(tx.origin == owner)
+PUSH20 0x00000000000000000000000000000000000a11ce
+ORIGIN
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+; to.call{value: amount}("")
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x24
+CALLDATALOAD
+PUSH1 0x04
+CALLDATALOAD
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+PUSH1 0x00
+SSTORE
+STOP
@@ -0,0 +1,145 @@
+; runtime code of the fixed contract, see source.sol
+PUSH1 0x00
+CALLDATALOAD
+PUSH1 0xe0
+SHR
+DUP1
+PUSH4 0x70a08231
+EQ
+PUSH2 @balanceOf
+JUMPI
+DUP1
+PUSH4 0xa6f2ae3a
+EQ
+PUSH2 @buy
+JUMPI
+DUP1
+PUSH4 0x9dc29fac
+EQ
+PUSH2 @burn
+JUMPI
+DUP1
+PUSH4 0xe4849b32
+EQ
+PUSH2 @sell
+JUMPI
+revert:
+JUMPDEST
+PUSH1 0x00
+DUP1
+REVERT
+balanceOf:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+SLOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x20
+PUSH1 0x00
+RETURN
+buy:
+JUMPDEST
+CALLER
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+CALLVALUE
+ADD
+SWAP1
+SSTORE
+STOP
+burn:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+ADDRESS
+CALLER
+EQ
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x00
+MSTORE
+PUSH1 0x00
+PUSH1 0x20
+MSTORE
+PUSH1 0x40
+PUSH1 0x00
+KECCAK256
+DUP1
+SLOAD
+PUSH1 0x24
+CALLDATALOAD
+DUP1
+DUP3
+LT
+PUSH2 @revert
+JUMPI
+SWAP1
+SUB
+SWAP1
+SSTORE
+STOP
+sell:
+JUMPDEST
+CALLVALUE
+PUSH2 @revert
+JUMPI
+PUSH4 0x9dc29fac
+PUSH1 0xe0
+SHL
+PUSH1 0x00
+MSTORE
+CALLER
+PUSH1 0x04
+MSTORE
+PUSH1 0x04
+CALLDATALOAD
+PUSH1 0x24
+MSTORE
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x44
+PUSH1 0x00
+PUSH1 0x00
+ADDRESS
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x00
+PUSH1 0x04
+CALLDATALOAD
+CALLER
+GAS
+CALL
+ISZERO
+PUSH2 @revert
+JUMPI
+STOP
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.18;
+
+/// Exchange of ether for tokens 1:1, the token ledger is reached through external calls.
+contract Exchange {
+    mapping(address => uint256) public balanceOf;
+
+    constructor() payable {}
+
+    function buy() public payable {
+        balanceOf[msg.sender] += msg.value;
+    }
+
+    function burn(address from, uint256 amount) public {
+        require(msg.sender == address(this));
+        require(balanceOf[from] >= amount);
+        balanceOf[from] -= amount;
+    }
+
+    function sell(uint256 amount) public {
+        address(this).call(abi.encodeWithSignature("burn(address,uint256)", msg.sender, amount));
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
+
+contract ExchangeFixed {
+    mapping(address => uint256) public balanceOf;
+
+    constructor() payable {}
+
+    function buy() public payable {
+        balanceOf[msg.sender] += msg.value;
+    }
+
+    function burn(address from, uint256 amount) public {
+        require(msg.sender == address(this));
+        require(balanceOf[from] >= amount);
+        balanceOf[from] -= amount;
+    }
+
+    function sell(uint256 amount) public {
+        (bool burnt, ) = address(this).call(abi.encodeWithSignature("burn(address,uint256)", msg.sender, amount));
+        require(burnt);
+        (bool sent, ) = msg.sender.call{value: amount}("");
+        require(sent);
+    }
+}
@@ -0,0 +1,50 @@
+//! Terminal debugger over a recorded episode: `debugger <episode.json>`.
+
+use game::debugger::Debugger;
+use game::episode::Episode;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+fn main() {
+    let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("usage: debugger <episode.json>");
+        std::process::exit(2);
+    };
+    let debugger = Episode::load(&path).and_then(|episode| Debugger::new(&episode));
+    let mut debugger = match debugger {
+        Ok(debugger) => debugger,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "{} turns, {} instructions, type help for commands",
+        debugger.turns.len(),
+        debugger.trace.len()
+    );
+    print!("{}", debugger.execute("where").unwrap_or_default());
+    // an empty line repeats the last command, like gdb
+    let mut last = String::from("where");
+    let stdin = std::io::stdin();
+    loop {
+        print!("(debugger) ");
+        let _ = std::io::stdout().flush();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+        let line = match line.trim() {
+            "" => last.clone(),
+            line => line.to_string(),
+        };
+        if matches!(line.as_str(), "quit" | "q") {
+            break;
+        }
+        match debugger.execute(&line) {
+            Ok(out) => print!("{out}"),
+            Err(e) => println!("error: {e}"),
+        }
+        last = line;
+    }
+}
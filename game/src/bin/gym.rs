@@ -0,0 +1,66 @@
+//! Gym-style server over the game: `gym <config.json> [--socket <path>]`.
+//!
+//! Serves stdin and stdout, or every connection to a Unix socket with a game of its own. See
+//! [`game::gym`] for the protocol.
+
+use game::env::NoChecks;
+use game::gym::{serve, GymConfig, GymEnv};
+use std::io::BufReader;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+
+fn main() {
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+    let (config, socket) = match args.as_slice() {
+        [config] => (PathBuf::from(config), None),
+        [config, flag, socket] if flag == "--socket" => {
+            (PathBuf::from(config), Some(PathBuf::from(socket)))
+        }
+        _ => {
+            eprintln!("usage: gym <config.json> [--socket <path>]");
+            std::process::exit(2);
+        }
+    };
+    let config = GymConfig::load(&config).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        std::process::exit(1);
+    });
+    let new_env = || GymEnv::new(config.clone(), NoChecks);
+    if let Err(e) = new_env() {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+
+    let Some(socket) = socket else {
+        let stdin = std::io::stdin();
+        if let Err(e) = serve(&mut new_env().unwrap(), stdin.lock(), std::io::stdout()) {
+            eprintln!("{e}");
+        }
+        return;
+    };
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket).unwrap_or_else(|e| {
+        eprintln!("{}: {e}", socket.display());
+        std::process::exit(1);
+    });
+    eprintln!("listening on {}", socket.display());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("{e}");
+                continue;
+            }
+        };
+        let mut env = new_env().unwrap();
+        std::thread::spawn(move || {
+            let reader = match stream.try_clone() {
+                Ok(reader) => BufReader::new(reader),
+                Err(e) => return eprintln!("{e}"),
+            };
+            if let Err(e) = serve(&mut env, reader, stream) {
+                eprintln!("{e}");
+            }
+        });
+    }
+}
@@ -0,0 +1,268 @@
+//! Benchmark contracts with a known vulnerability, a known exploit and a known fix.
+//!
+//! Every benchmark is a directory of `game/corpus` built into the crate:
+//!
+//! - `vulnerable.bin` and `fixed.bin`, deployment code in hex,
+//! - `abi.json`, the ABI both versions share,
+//! - `source.sol`, the source of both versions,
+//! - `benchmark.json`, the name, class and description of the vulnerability, the fix, and the
+//!   actions of an attacker exploiting it from [`ATTACKER`], answering the transaction of a
+//!   victim into the attacker if the benchmark has one.
+//!
+//! Apart from the vulnerable SillyBank, compiled by solc, the bytecode is assembled by hand after
+//! `source.sol`: it behaves like the source, it is not the output of solc. Its runtime code is
+//! kept in `vulnerable.asm` and `fixed.asm`, in the format of [`revm::interpreter::asm`], the
+//! deployment code only adds a constructor copying it.
+
+use crate::env::{GameEnvironment, NoChecks};
+use crate::episode::{serde_hex, Episode, Victim};
+use crate::mcts::Action;
+use crate::vec_env::OwnedGame;
+use ethers::abi::Abi;
+use revm::primitives::{hex, Bytes, Env, B160, U256};
+use revm::InMemoryDB;
+use serde::{Deserialize, Serialize};
+
+/// Attacker account of the exploits, which pass it as argument where needed.
+pub const ATTACKER: B160 = B160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x0a, 0x77, 0xac,
+]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VulnerabilityClass {
+    /// A function is reentered before it updates its state.
+    Reentrancy,
+    /// Another function is entered while one is halfway through updating the state they share.
+    CrossFunctionReentrancy,
+    /// A view is read while a function is halfway through updating the state behind it.
+    ReadOnlyReentrancy,
+    AccessControl,
+    IntegerOverflow,
+    /// Authentication through `tx.origin`.
+    TxOrigin,
+    /// The success of an external call is not checked.
+    UncheckedCall,
+    /// Delegatecall to an address the caller chooses.
+    Delegatecall,
+    /// Selfdestruct anyone can trigger.
+    Selfdestruct,
+}
+
+/// A version of a benchmark contract.
+#[derive(Debug, Clone)]
+pub struct Contract {
+    pub deployment_code: Bytes,
+    pub abi: Abi,
+}
+
+impl Contract {
+    /// Deploys the contract into a new game.
+    pub fn game<'a>(
+        &self,
+        env: &'a mut Env,
+        db: &'a mut InMemoryDB,
+        attacker: B160,
+        attacker_balance: U256,
+    ) -> GameEnvironment<'a> {
+        GameEnvironment::new(
+            env,
+            db,
+            attacker,
+            attacker_balance,
+            self.deployment_code.clone(),
+            self.abi.clone().into(),
+        )
+    }
+
+    pub fn owned_game(&self, attacker: B160, attacker_balance: U256) -> OwnedGame {
+        OwnedGame::new(
+            attacker,
+            attacker_balance,
+            self.deployment_code.clone(),
+            self.abi.clone().into(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Benchmark {
+    /// Directory of the benchmark.
+    pub id: &'static str,
+    /// Name of the vulnerable contract in the source.
+    pub name: String,
+    pub class: VulnerabilityClass,
+    pub description: String,
+    pub fix: String,
+    pub source: &'static str,
+    pub attacker_balance: U256,
+    /// Transaction into the attacker the exploit starts with, e.g. of the phished owner of the
+    /// defender.
+    pub victim: Option<Victim>,
+    pub exploit: Vec<Action>,
+    pub vulnerable: Contract,
+    pub fixed: Contract,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    name: String,
+    class: VulnerabilityClass,
+    description: String,
+    fix: String,
+    #[serde(with = "serde_hex::word")]
+    attacker_balance: U256,
+    #[serde(default)]
+    victim: Option<Victim>,
+    exploit: Vec<Action>,
+}
+
+/// Files of a benchmark: id, `benchmark.json`, `abi.json`, `vulnerable.bin`, `fixed.bin` and
+/// `source.sol`.
+type Files = (
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+    &'static str,
+);
+
+macro_rules! corpus {
+    ($($id:literal),* $(,)?) => {
+        [$((
+            $id,
+            include_str!(concat!("../corpus/", $id, "/benchmark.json")),
+            include_str!(concat!("../corpus/", $id, "/abi.json")),
+            include_str!(concat!("../corpus/", $id, "/vulnerable.bin")),
+            include_str!(concat!("../corpus/", $id, "/fixed.bin")),
+            include_str!(concat!("../corpus/", $id, "/source.sol")),
+        )),*]
+    };
+}
+
+const CORPUS: [Files; 9] = corpus![
+    "reentrancy",
+    "cross_function_reentrancy",
+    "read_only_reentrancy",
+    "access_control",
+    "integer_overflow",
+    "tx_origin",
+    "unchecked_call",
+    "delegatecall",
+    "selfdestruct",
+];
+
+/// Every benchmark of the corpus.
+pub fn benchmarks() -> Vec<Benchmark> {
+    CORPUS.iter().map(load).collect()
+}
+
+pub fn benchmark(id: &str) -> Option<Benchmark> {
+    CORPUS.iter().find(|files| files.0 == id).map(load)
+}
+
+fn load(&(id, metadata, abi, vulnerable, fixed, source): &Files) -> Benchmark {
+    let parse = || -> Result<Benchmark, String> {
+        let metadata: Metadata = serde_json::from_str(metadata).map_err(|e| e.to_string())?;
+        let abi: Abi = serde_json::from_str(abi).map_err(|e| e.to_string())?;
+        let contract = |code: &str| -> Result<Contract, String> {
+            let deployment_code = hex::decode(code.trim()).map_err(|e| e.to_string())?;
+            Ok(Contract {
+                deployment_code: deployment_code.into(),
+                abi: abi.clone(),
+            })
+        };
+        Ok(Benchmark {
+            id,
+            name: metadata.name,
+            class: metadata.class,
+            description: metadata.description,
+            fix: metadata.fix,
+            source,
+            attacker_balance: metadata.attacker_balance,
+            victim: metadata.victim,
+            exploit: metadata.exploit,
+            vulnerable: contract(vulnerable)?,
+            fixed: contract(fixed)?,
+        })
+    };
+    parse().unwrap_or_else(|e| panic!("corpus/{id}: {e}"))
+}
+
+impl Benchmark {
+    /// Records the exploit against `contract`, the defender lets every call through.
+    pub fn exploit_episode(&self, contract: &Contract) -> Result<Episode, String> {
+        let episode = Episode {
+            deployment_code: contract.deployment_code.clone(),
+            abi: contract.abi.clone(),
+            attacker: ATTACKER,
+            attacker_balance: self.attacker_balance,
+            victim: self.victim.clone(),
+            moves: vec![],
+        };
+        episode.recorded(&self.exploit, NoChecks)
+    }
+
+    /// Balance of the attacker after the exploit against `contract`. Fails if the exploit
+    /// makes a move the game does not allow, e.g. answers a call that never came.
+    pub fn play_exploit(&self, contract: &Contract) -> Result<U256, String> {
+        let episode = self.exploit_episode(contract)?;
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        episode.replay(&mut game)?;
+        Ok(game.balance(ATTACKER))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn exploits_work_until_fixed() {
+        let benchmarks = benchmarks();
+        let classes: HashSet<_> = benchmarks.iter().map(|benchmark| benchmark.class).collect();
+        assert_eq!(classes.len(), benchmarks.len());
+        for benchmark in &benchmarks {
+            let id = benchmark.id;
+            let balance = benchmark.play_exploit(&benchmark.vulnerable);
+            assert!(
+                matches!(balance, Ok(balance) if balance > benchmark.attacker_balance),
+                "{id}: {balance:?}"
+            );
+            let balance = benchmark.play_exploit(&benchmark.fixed);
+            assert!(
+                !matches!(balance, Ok(balance) if balance > benchmark.attacker_balance),
+                "{id} fixed: {balance:?}"
+            );
+        }
+        assert_eq!(benchmark("reentrancy").unwrap().name, "SillyBank");
+    }
+
+    #[test]
+    fn bytecode_matches_assembly() {
+        let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+        for benchmark in benchmarks() {
+            let versions = [
+                ("vulnerable", &benchmark.vulnerable),
+                ("fixed", &benchmark.fixed),
+            ];
+            for (version, contract) in versions {
+                let path = corpus.join(benchmark.id).join(format!("{version}.asm"));
+                let Ok(text) = std::fs::read_to_string(&path) else {
+                    continue;
+                };
+                let runtime = revm::interpreter::asm::assemble(&text).unwrap();
+                assert!(
+                    contract.deployment_code.ends_with(&runtime),
+                    "{}",
+                    path.display()
+                );
+            }
+        }
+    }
+}
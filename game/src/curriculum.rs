@@ -0,0 +1,249 @@
+//! Curriculum over the targets of the corpus, from easy to hard.
+//!
+//! Targets are ordered by a difficulty estimated from the runtime code of the defender. The
+//! attacker trains on the target of its stage and moves to the next one once it succeeds often
+//! enough on it. Earlier targets come back now and then so it does not forget them.
+
+use crate::corpus::{Benchmark, Contract, ATTACKER};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use revm::interpreter::analysis::cfg::{without_metadata, Cfg};
+use revm::interpreter::opcode;
+use revm::primitives::{Env, U256};
+use revm::InMemoryDB;
+use std::collections::VecDeque;
+
+/// Static features of the runtime code of a defender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Features {
+    /// Basic blocks of the control-flow graph.
+    pub blocks: usize,
+    /// Functions of the selector dispatcher.
+    pub functions: usize,
+    /// `CALL`, `CALLCODE`, `DELEGATECALL` and `STATICCALL` instructions.
+    pub external_calls: usize,
+}
+
+impl Features {
+    pub fn of_runtime(code: &[u8]) -> Self {
+        let cfg = Cfg::from_slice(without_metadata(code));
+        let external_calls = cfg
+            .instructions
+            .iter()
+            .filter(|instruction| {
+                matches!(
+                    instruction.opcode,
+                    opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL
+                )
+            })
+            .count();
+        Self {
+            blocks: cfg.blocks.len(),
+            functions: cfg.function_entries().len(),
+            external_calls,
+        }
+    }
+
+    /// Features of the runtime code `contract` deploys.
+    pub fn of_contract(contract: &Contract) -> Self {
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = contract.game(&mut env, &mut db, ATTACKER, U256::ZERO);
+        let defender = game.defender_account;
+        Self::of_runtime(&game.code(defender))
+    }
+
+    /// Higher is harder. Every function widens the action space and every external call is a
+    /// place where the attacker has to answer, so they weigh more than a block.
+    pub fn difficulty(&self) -> f64 {
+        self.blocks as f64 + 4.0 * self.functions as f64 + 8.0 * self.external_calls as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CurriculumConfig {
+    /// Success rate on the current target that promotes to the next one.
+    pub promotion_threshold: f64,
+    /// Episodes on the current target the success rate is measured over. No promotion before
+    /// that many.
+    pub window: usize,
+    /// Probability of training on an earlier target instead of the current one.
+    pub review_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for CurriculumConfig {
+    fn default() -> Self {
+        Self {
+            promotion_threshold: 0.8,
+            window: 20,
+            review_rate: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Task {
+    pub benchmark: Benchmark,
+    pub features: Features,
+    pub difficulty: f64,
+    pub episodes: usize,
+    pub successes: usize,
+}
+
+pub struct Curriculum {
+    /// Ordered by difficulty.
+    tasks: Vec<Task>,
+    stage: usize,
+    /// Outcomes of the last episodes on the current target.
+    recent: VecDeque<bool>,
+    config: CurriculumConfig,
+    rng: StdRng,
+}
+
+impl Curriculum {
+    /// Orders the vulnerable contracts of `benchmarks` by difficulty.
+    pub fn new(benchmarks: Vec<Benchmark>, config: CurriculumConfig) -> Self {
+        let mut tasks: Vec<Task> = benchmarks
+            .into_iter()
+            .map(|benchmark| {
+                let features = Features::of_contract(&benchmark.vulnerable);
+                Task {
+                    benchmark,
+                    features,
+                    difficulty: features.difficulty(),
+                    episodes: 0,
+                    successes: 0,
+                }
+            })
+            .collect();
+        assert!(!tasks.is_empty(), "a curriculum needs a target");
+        tasks.sort_by(|a, b| {
+            a.difficulty
+                .total_cmp(&b.difficulty)
+                .then(a.benchmark.id.cmp(b.benchmark.id))
+        });
+        Self {
+            tasks,
+            stage: 0,
+            recent: VecDeque::new(),
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+        }
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// Index of the current target.
+    pub fn stage(&self) -> usize {
+        self.stage
+    }
+
+    pub fn current(&self) -> &Task {
+        &self.tasks[self.stage]
+    }
+
+    /// Success rate over the window of the current target, `None` before a full window.
+    pub fn success_rate(&self) -> Option<f64> {
+        if self.recent.len() < self.config.window.max(1) {
+            return None;
+        }
+        let successes = self.recent.iter().filter(|success| **success).count();
+        Some(successes as f64 / self.recent.len() as f64)
+    }
+
+    /// The last target is mastered.
+    pub fn is_complete(&self) -> bool {
+        self.stage == self.tasks.len() - 1 && self.mastered()
+    }
+
+    /// The success rate on the current target reaches the threshold.
+    fn mastered(&self) -> bool {
+        self.success_rate()
+            .is_some_and(|rate| rate >= self.config.promotion_threshold)
+    }
+
+    /// Index of the target of the next episode.
+    pub fn next_task(&mut self) -> usize {
+        if self.stage > 0 && self.rng.gen_bool(self.config.review_rate.clamp(0.0, 1.0)) {
+            self.rng.gen_range(0..self.stage)
+        } else {
+            self.stage
+        }
+    }
+
+    /// Records the outcome of an episode on target `index`, returns whether it promoted to the
+    /// next target. Only episodes on the current target count towards promotion.
+    pub fn record(&mut self, index: usize, success: bool) -> bool {
+        let task = &mut self.tasks[index];
+        task.episodes += 1;
+        task.successes += success as usize;
+        if index != self.stage {
+            return false;
+        }
+        self.recent.push_back(success);
+        if self.recent.len() > self.config.window.max(1) {
+            self.recent.pop_front();
+        }
+        let promote = self.stage + 1 < self.tasks.len() && self.mastered();
+        if promote {
+            self.stage += 1;
+            self.recent.clear();
+        }
+        promote
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::corpus::{benchmark, benchmarks};
+
+    #[test]
+    fn promotes_from_easy_to_hard_targets() {
+        let features = Features::of_contract(&benchmark("reentrancy").unwrap().vulnerable);
+        assert_eq!(features.functions, 3);
+        assert_eq!(features.external_calls, 1);
+
+        let config = CurriculumConfig {
+            window: 4,
+            review_rate: 0.5,
+            ..Default::default()
+        };
+        let mut curriculum = Curriculum::new(benchmarks(), config);
+        let tasks = curriculum.tasks();
+        assert!(tasks
+            .windows(2)
+            .all(|pair| pair[0].difficulty <= pair[1].difficulty));
+        assert_eq!(tasks[0].benchmark.id, "selfdestruct");
+        assert_eq!(tasks.last().unwrap().benchmark.id, "reentrancy");
+
+        // 3 of the last 4 is below the threshold, 4 of the last 4 is not
+        for success in [true, false, true, true, true] {
+            assert_eq!(curriculum.next_task(), 0);
+            assert!(!curriculum.record(0, success));
+        }
+        assert_eq!(curriculum.success_rate(), Some(0.75));
+        assert!(curriculum.record(0, true));
+        assert_eq!(curriculum.stage(), 1);
+        assert_eq!(curriculum.success_rate(), None);
+
+        let picks: Vec<_> = (0..100).map(|_| curriculum.next_task()).collect();
+        assert!(picks.contains(&0) && picks.contains(&1));
+        assert!(!curriculum.record(0, false));
+        assert_eq!(curriculum.tasks()[0].episodes, 7);
+        assert_eq!(curriculum.stage(), 1);
+
+        while curriculum.stage() + 1 < curriculum.tasks().len() {
+            let stage = curriculum.stage();
+            curriculum.record(stage, true);
+        }
+        assert!(!curriculum.is_complete());
+        for _ in 0..4 {
+            assert!(!curriculum.record(curriculum.stage(), true));
+        }
+        assert!(curriculum.is_complete());
+    }
+}
@@ -0,0 +1,585 @@
+//! Step-through debugger over recorded episodes.
+//!
+//! The episode is replayed once with `env.cfg.trace_steps` set, keeping the instructions every
+//! turn ran and the state after every turn. The debugger then moves over the recording, forward
+//! and backward by instruction or by turn, or to the next breakpoint on a pc or a selector.
+//! Commands are the ones listed by `help`.
+
+use crate::episode::{Episode, Move, Player};
+use crate::mcts::Action;
+use ethers::prelude::BaseContract;
+use revm::interpreter::asm::{disassemble_slice, Instruction};
+use revm::interpreter::opcode;
+use revm::primitives::{hex, Bytes, Env, B160, U256};
+use revm::{InMemoryDB, TraceStep};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::ops::Range;
+
+const HELP: &str = "\
+step [n], s        run n instructions (1)
+back [n], bs       undo n instructions (1)
+next, n            go to the start of the next turn
+prev, p            go to the start of the previous turn
+continue, c        run to the next breakpoint
+reverse, rc        go back to the previous breakpoint
+break pc <pc>      break at a pc, decimal or 0x hex
+break <selector>   break when a frame starts with a selector, 0x hex or a function name
+delete [n]         delete breakpoint n, or all of them
+breakpoints        list the breakpoints
+where, w           show the current instruction
+dis [n]            disassemble n instructions around the current one (10)
+stack              show the stack, top first
+memory, mem        show the memory
+storage            show the storage of the defender
+return, ret        show the return data buffer
+turns              list the turns
+help, h            show this help
+quit, q            leave the debugger";
+
+#[derive(Debug, Clone)]
+pub struct Turn {
+    /// `None` for the deployment of the defender.
+    pub player: Option<Player>,
+    pub description: String,
+    /// Instructions run during the turn, as a range of the trace.
+    pub steps: Range<usize>,
+    /// Name of the state the game waits in after the turn.
+    pub waiting_for: &'static str,
+    /// Storage of the defender after the turn.
+    pub storage: BTreeMap<U256, U256>,
+    pub attacker_balance: U256,
+    pub defender_balance: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    Pc(usize),
+    /// First instruction of a frame whose call data starts with the selector.
+    Selector([u8; 4]),
+}
+
+impl Breakpoint {
+    fn hit(&self, step: &TraceStep) -> bool {
+        match self {
+            Self::Pc(pc) => step.pc == *pc,
+            Self::Selector(selector) => {
+                step.pc == 0 && !step.resumed && step.input.starts_with(selector)
+            }
+        }
+    }
+}
+
+pub struct Debugger {
+    pub turns: Vec<Turn>,
+    pub trace: Vec<TraceStep>,
+    pub breakpoints: Vec<Breakpoint>,
+    attacker: B160,
+    defender: B160,
+    abi: BaseContract,
+    /// Disassembled runtime code by address, and the init code run by the deployment.
+    codes: HashMap<B160, Vec<Instruction>>,
+    init_code: Vec<Instruction>,
+    turn: usize,
+    /// Index in the trace, equal to the start of the steps of the turn when it ran none.
+    step: usize,
+}
+
+impl Debugger {
+    /// Replays `episode` and starts at the first instruction of its first turn.
+    pub fn new(episode: &Episode) -> Result<Self, String> {
+        let mut env = Env::default();
+        env.cfg.trace_steps = true;
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        let attacker = game.attacker_account;
+        let defender = game.defender_account;
+        let abi: BaseContract = episode.abi.clone().into();
+        let mut trace = game.take_trace();
+        let mut turns = vec![];
+        let moves = std::iter::once(None).chain(episode.moves.iter().map(Some));
+        for (index, next) in moves.enumerate() {
+            let start = trace.len();
+            if let Some(next) = next {
+                next.play(&mut game)
+                    .map_err(|e| format!("move {}: {e}", index - 1))?;
+                trace.extend(game.take_trace());
+            }
+            turns.push(Turn {
+                player: next.map(Move::player),
+                description: next.map_or("deploy the defender".into(), |next| describe(&abi, next)),
+                steps: if index == 0 {
+                    0..trace.len()
+                } else {
+                    start..trace.len()
+                },
+                waiting_for: game.stuck_state().name(),
+                storage: game.storage_slots(defender),
+                attacker_balance: game.balance(attacker),
+                defender_balance: game.balance(defender),
+            });
+        }
+        let mut codes = HashMap::new();
+        for step in &trace {
+            codes
+                .entry(step.address)
+                .or_insert_with(|| disassemble_slice(&game.code(step.address)));
+        }
+        Ok(Self {
+            turns,
+            trace,
+            breakpoints: vec![],
+            attacker,
+            defender,
+            abi,
+            codes,
+            init_code: disassemble_slice(&episode.deployment_code),
+            turn: 0,
+            step: 0,
+        })
+    }
+
+    /// Runs a command line and returns what it prints.
+    pub fn execute(&mut self, line: &str) -> Result<String, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let count = || -> Result<usize, String> {
+            words
+                .get(1)
+                .map_or(Ok(1), |n| n.parse().map_err(|e| format!("{n}: {e}")))
+        };
+        match words.first().copied().unwrap_or("where") {
+            "step" | "s" => {
+                let count = count()?;
+                self.seek((self.step + count).min(self.trace.len().saturating_sub(1)));
+            }
+            "back" | "bs" => {
+                let count = count()?;
+                self.seek(self.step.saturating_sub(count));
+            }
+            "next" | "n" => self.seek_turn((self.turn + 1).min(self.turns.len() - 1)),
+            "prev" | "p" => self.seek_turn(self.turn.saturating_sub(1)),
+            "continue" | "c" => {
+                let hit = (self.step + 1..self.trace.len()).find(|index| self.hit(*index));
+                match hit {
+                    Some(index) => self.seek(index),
+                    None => {
+                        self.seek(self.trace.len().saturating_sub(1));
+                        return Ok(format!("no breakpoint hit\n{}", self.location()));
+                    }
+                }
+            }
+            "reverse" | "rc" => {
+                let hit = (0..self.step).rev().find(|index| self.hit(*index));
+                match hit {
+                    Some(index) => self.seek(index),
+                    None => {
+                        self.seek(0);
+                        return Ok(format!("no breakpoint hit\n{}", self.location()));
+                    }
+                }
+            }
+            "break" | "b" => {
+                let breakpoint = match words[1..] {
+                    ["pc", pc] => Breakpoint::Pc(parse_number(pc)?),
+                    [selector] => Breakpoint::Selector(self.selector(selector)?),
+                    _ => return Err("usage: break pc <pc> | break <selector>".into()),
+                };
+                self.breakpoints.push(breakpoint);
+                return Ok(format!(
+                    "breakpoint {}: {}\n",
+                    self.breakpoints.len() - 1,
+                    self.show_breakpoint(&breakpoint)
+                ));
+            }
+            "delete" | "d" => match words.get(1) {
+                None => self.breakpoints.clear(),
+                Some(index) => {
+                    let index: usize = index.parse().map_err(|e| format!("{index}: {e}"))?;
+                    if index >= self.breakpoints.len() {
+                        return Err(format!("no breakpoint {index}"));
+                    }
+                    self.breakpoints.remove(index);
+                }
+            },
+            "breakpoints" => {
+                let mut out = String::new();
+                for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+                    let _ = writeln!(out, "{index}: {}", self.show_breakpoint(breakpoint));
+                }
+                return Ok(out);
+            }
+            "where" | "w" => {}
+            "dis" => {
+                let count = words
+                    .get(1)
+                    .map_or(Ok(10), |n| n.parse().map_err(|e| format!("{n}: {e}")))?;
+                return Ok(self.disassembly(count));
+            }
+            "stack" => return Ok(self.stack()),
+            "memory" | "mem" => {
+                return Ok(self
+                    .current()
+                    .map_or(String::new(), |step| dump(&step.memory)))
+            }
+            "storage" => return Ok(self.storage()),
+            "return" | "ret" => {
+                return Ok(self.current().map_or(String::new(), |step| {
+                    format!("0x{}\n", hex::encode(&step.return_data))
+                }))
+            }
+            "turns" => return Ok(self.list_turns()),
+            "help" | "h" => return Ok(format!("{HELP}\n")),
+            command => return Err(format!("unknown command {command}, try help")),
+        }
+        Ok(self.location())
+    }
+
+    /// Instruction about to run, `None` if the current turn ran none.
+    pub fn current(&self) -> Option<&TraceStep> {
+        self.turns[self.turn]
+            .steps
+            .contains(&self.step)
+            .then(|| &self.trace[self.step])
+    }
+
+    pub fn turn(&self) -> &Turn {
+        &self.turns[self.turn]
+    }
+
+    fn seek(&mut self, step: usize) {
+        self.step = step;
+        if let Some(turn) = self
+            .turns
+            .iter()
+            .position(|turn| turn.steps.contains(&step))
+        {
+            self.turn = turn;
+        }
+    }
+
+    fn seek_turn(&mut self, turn: usize) {
+        self.turn = turn;
+        self.step = self.turns[turn].steps.start;
+    }
+
+    fn hit(&self, index: usize) -> bool {
+        self.breakpoints
+            .iter()
+            .any(|breakpoint| breakpoint.hit(&self.trace[index]))
+    }
+
+    fn selector(&self, word: &str) -> Result<[u8; 4], String> {
+        if let Some(digits) = word.strip_prefix("0x") {
+            let bytes = hex::decode(digits).map_err(|e| format!("{word}: {e}"))?;
+            return bytes
+                .try_into()
+                .map_err(|_| format!("{word}: a selector has 4 bytes"));
+        }
+        let function = self
+            .abi
+            .abi()
+            .function(word)
+            .map_err(|e| format!("{word}: {e}"))?;
+        Ok(function.short_signature())
+    }
+
+    fn function_name(&self, input: &[u8]) -> Option<String> {
+        function_name(&self.abi, input)
+    }
+
+    fn show_breakpoint(&self, breakpoint: &Breakpoint) -> String {
+        match breakpoint {
+            Breakpoint::Pc(pc) => format!("pc {pc:#x}"),
+            Breakpoint::Selector(selector) => match self.function_name(selector) {
+                Some(name) => format!("selector 0x{} ({name})", hex::encode(selector)),
+                None => format!("selector 0x{}", hex::encode(selector)),
+            },
+        }
+    }
+
+    fn account(&self, address: B160) -> String {
+        if address == self.attacker {
+            "attacker".into()
+        } else if address == self.defender {
+            "defender".into()
+        } else {
+            format!("0x{}", hex::encode(address))
+        }
+    }
+
+    fn instructions(&self, step: &TraceStep) -> &[Instruction] {
+        if self.turn == 0 {
+            &self.init_code
+        } else {
+            self.codes.get(&step.address).map_or(&[], Vec::as_slice)
+        }
+    }
+
+    fn location(&self) -> String {
+        let turn = self.turn();
+        let player = match turn.player {
+            None => "setup",
+            Some(Player::Attacker) => "attacker",
+            Some(Player::Defender) => "defender",
+        };
+        let mut out = format!(
+            "turn {}/{} {player}: {}\n",
+            self.turn,
+            self.turns.len() - 1,
+            turn.description
+        );
+        match self.current() {
+            None => {
+                let _ = writeln!(out, "no instructions ran, waiting for {}", turn.waiting_for);
+            }
+            Some(step) => {
+                let frame = match self.function_name(&step.input) {
+                    Some(name) if self.turn != 0 => format!(" in {name}"),
+                    _ => String::new(),
+                };
+                let _ = writeln!(
+                    out,
+                    "step {}/{} depth {} {}{frame} gas {}{}",
+                    self.step,
+                    self.trace.len() - 1,
+                    step.depth,
+                    self.account(step.address),
+                    step.gas_remaining,
+                    if step.resumed { " (resumed)" } else { "" }
+                );
+                out.push_str(&self.disassembly(5));
+            }
+        }
+        out
+    }
+
+    fn disassembly(&self, count: usize) -> String {
+        let Some(step) = self.current() else {
+            return String::new();
+        };
+        let instructions = self.instructions(step);
+        let at = instructions.partition_point(|instruction| instruction.pc < step.pc);
+        let start = at.saturating_sub(count / 2);
+        let mut out = String::new();
+        for instruction in instructions.iter().skip(start).take(count) {
+            let breakpoint = self.breakpoints.contains(&Breakpoint::Pc(instruction.pc));
+            let _ = writeln!(
+                out,
+                "{}{} {:#06x}  {instruction}",
+                if instruction.pc == step.pc {
+                    "=>"
+                } else {
+                    "  "
+                },
+                if breakpoint { "*" } else { " " },
+                instruction.pc
+            );
+        }
+        out
+    }
+
+    fn stack(&self) -> String {
+        let mut out = String::new();
+        if let Some(step) = self.current() {
+            for (depth, word) in step.stack.iter().rev().enumerate() {
+                let _ = writeln!(out, "{depth:3}: {}", word_hex(*word));
+            }
+        }
+        out
+    }
+
+    /// Storage after the previous turn with the writes of the defender so far, reverted writes
+    /// stay until the end of the turn.
+    fn storage(&self) -> String {
+        let mut storage = match self.turn {
+            0 => BTreeMap::new(),
+            turn => self.turns[turn - 1].storage.clone(),
+        };
+        let start = self.turns[self.turn].steps.start;
+        for step in &self.trace[start..self.step.max(start)] {
+            if step.opcode == opcode::SSTORE && step.address == self.defender {
+                let [.., value, index] = step.stack[..] else {
+                    continue;
+                };
+                storage.insert(index, value);
+            }
+        }
+        let mut out = String::new();
+        for (index, value) in storage {
+            let _ = writeln!(out, "{} => {}", word_hex(index), word_hex(value));
+        }
+        out
+    }
+
+    fn list_turns(&self) -> String {
+        let mut out = String::new();
+        for (index, turn) in self.turns.iter().enumerate() {
+            let player = match turn.player {
+                None => "setup",
+                Some(Player::Attacker) => "attacker",
+                Some(Player::Defender) => "defender",
+            };
+            let _ = writeln!(
+                out,
+                "{}{index:3} {player:8} {:32} {:5} steps, attacker balance {}",
+                if index == self.turn { "=>" } else { "  " },
+                turn.description,
+                turn.steps.len(),
+                turn.attacker_balance
+            );
+        }
+        out
+    }
+}
+
+fn describe(abi: &BaseContract, next: &Move) -> String {
+    let call = |data: &Bytes, value: U256| {
+        let name = function_name(abi, data).unwrap_or_else(|| format!("0x{}", hex::encode(data)));
+        if value == U256::ZERO {
+            name
+        } else {
+            format!("{name} with {value} wei")
+        }
+    };
+    match next {
+        Move::Attacker(Action::Transact(transact)) => {
+            format!("transact {}", call(&transact.data, transact.value))
+        }
+        Move::Attacker(Action::Stop) => "stop".into(),
+        Move::Attacker(Action::Backcall(backcall)) => {
+            format!("call back {}", call(&backcall.data, backcall.value))
+        }
+        Move::Attacker(Action::Return) => "return to the defender".into(),
+        Move::Attacker(Action::Pass(true)) => "let the call return".into(),
+        Move::Attacker(Action::Pass(false)) => "revert the call".into(),
+        Move::Defender(true) => "let the call through".into(),
+        Move::Defender(false) => "ban the call".into(),
+    }
+}
+
+fn function_name(abi: &BaseContract, input: &[u8]) -> Option<String> {
+    let selector = input.get(..4)?;
+    abi.abi()
+        .functions()
+        .find(|function| function.short_signature() == selector)
+        .map(|function| function.signature())
+}
+
+fn parse_number(word: &str) -> Result<usize, String> {
+    match word.strip_prefix("0x") {
+        Some(digits) => usize::from_str_radix(digits, 16),
+        None => word.parse(),
+    }
+    .map_err(|e| format!("{word}: {e}"))
+}
+
+/// Hex without leading zeros.
+fn word_hex(word: U256) -> String {
+    let digits = hex::encode(word.to_be_bytes::<32>());
+    match digits.trim_start_matches('0') {
+        "" => "0x0".into(),
+        digits => format!("0x{digits}"),
+    }
+}
+
+/// Hex dump, 32 bytes per line.
+fn dump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(32).enumerate() {
+        let _ = writeln!(out, "{:#06x}: {}", line * 32, hex::encode(chunk));
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, NoChecks, SILLY_BANK};
+    use crate::mcts::Call;
+
+    #[test]
+    fn steps_through_reentrancy() {
+        let abi = silly_bank_abi();
+        let call = |function: &str, value: u64| Call {
+            data: abi.encode(function, ()).unwrap().0,
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let actions = [
+            Action::Transact(call("deposit", 500)),
+            Action::Transact(call("withdraw", 0)),
+            Action::Backcall(call("withdraw", 0)),
+            Action::Return,
+            Action::Pass(true),
+            Action::Pass(true),
+        ];
+        let episode = Episode::record(
+            SILLY_BANK.to_vec().into(),
+            abi.abi().clone(),
+            B160::from_low_u64_be(0xa77ac),
+            U256::from(1000),
+            &actions,
+            NoChecks,
+        )
+        .unwrap();
+        let mut debugger = Debugger::new(&episode).unwrap();
+        assert_eq!(debugger.turns.len(), episode.moves.len() + 1);
+        assert!(debugger
+            .execute("where")
+            .unwrap()
+            .starts_with("turn 0/9 setup: deploy the defender"));
+
+        // both withdraw frames, the second one entered from the attacker
+        debugger.execute("break withdraw").unwrap();
+        let first = debugger.execute("continue").unwrap();
+        assert!(first.contains("defender: let the call through"), "{first}");
+        assert!(first.contains("depth 1 defender in withdraw()"), "{first}");
+        let second = debugger.execute("c").unwrap();
+        assert!(
+            second.contains("depth 3 defender in withdraw()"),
+            "{second}"
+        );
+        assert_eq!(debugger.execute("rc").unwrap(), first);
+        assert!(debugger
+            .execute("c")
+            .unwrap()
+            .starts_with(&second[..second.find('\n').unwrap()]));
+
+        // the inner withdraw pays the attacker before the outer one zeroes its balance
+        assert_eq!(debugger.turn().attacker_balance, U256::from(1500));
+        debugger.execute("next").unwrap();
+        assert!(debugger
+            .execute("w")
+            .unwrap()
+            .contains("no instructions ran, waiting for attacker to return"));
+        while debugger.turn + 1 < debugger.turns.len() {
+            debugger.execute("n").unwrap();
+        }
+        assert_eq!(debugger.turn().attacker_balance, U256::from(1500));
+        assert_eq!(
+            debugger
+                .turn()
+                .storage
+                .values()
+                .filter(|value| **value != U256::ZERO)
+                .count(),
+            0
+        );
+
+        // a write shows up in the storage before the turn ends
+        debugger.execute("delete").unwrap();
+        debugger.execute("prev").unwrap();
+        let before = debugger.execute("storage").unwrap();
+        assert!(before.contains("=> 0x1f4"), "{before}");
+        debugger
+            .execute(&format!("step {}", debugger.turn().steps.len() - 1))
+            .unwrap();
+        assert!(!debugger.execute("storage").unwrap().contains("=> 0x1f4"));
+
+        debugger.execute("break pc 0").unwrap();
+        assert!(debugger.execute("dis").unwrap().lines().count() > 1);
+        assert!(debugger.execute("stack").is_ok());
+        assert!(debugger.execute("bogus").is_err());
+        assert!(debugger.execute("break 0x1234").is_err());
+    }
+}
@@ -1,23 +1,116 @@
-use revm::{primitives::{ShanghaiSpec, Bytes, B160, U256, AccountInfo}, interpreter::{Interpreter, CallInputs, Transfer, CallContext, StuckReason, InstructionResult, Gas, CreateInputs, return_ok}, CallResult, CreateResult, DatabaseCommit};
+use revm::{primitives::{LatestSpec, Bytes, B160, U256}, interpreter::{Interpreter, ParkedInterpreter, CallInputs, Transfer, CallContext, StuckReason, InstructionResult, Gas, CreateInputs, return_ok, return_revert}, CallResult};
 use ethers::prelude::BaseContract;
+use crate::fingerprint::{Fingerprint, FingerprintBuilder};
+use crate::invariant::Invariants;
 
 pub struct GameEnvironment<'a> {
-    executor: revm::EVMImpl<'a, ShanghaiSpec, revm::InMemoryDB>,
+    executor: revm::EVMImpl<'a, LatestSpec, revm::InMemoryDB>,
     interpreters: Vec<InterpreterSlot>,
     stuck_state: StuckState,
     pub attacker_account: revm::primitives::B160,
     pub defender_account: revm::primitives::B160,
     pub abi: BaseContract,
+    /// Result of the last transaction that returned to the attacker's turn.
+    pub last_result: Option<CallResult>,
+    /// Accounts of the database, to which the journaled state is applied when fingerprinting.
+    world: revm::primitives::StateRootCache,
+    /// Checkpoints taken when the defender called the attacker, innermost call last.
+    attacker_checkpoints: Vec<revm::JournalCheckpoint>,
+    /// Gas used so far by the calls into the attacker, innermost call last.
+    attacker_frames: Vec<u64>,
+    pub gas_model: GasModel,
+    gas_used: GasUsage,
+    /// Checked after every move and every return, see [`GameEnvironment::set_invariants`].
+    invariants: Option<Invariants>,
+    /// Name of the first invariant a position of the game violated.
+    broken: Option<String>,
 }
 
-#[derive(Debug)]
-pub enum InterpreterSlot {
+/// Gas of what the game runs outside of the EVM and budgets of the players. The default only
+/// counts the gas the EVM runs and the gas of the checks, without budgets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasModel {
+    /// Charged to the attacker for every call it answers, out of the gas of the call.
+    pub fake_frame: u64,
+    /// Charged to the attacker for every backcall, on top of the gas the backcall uses.
+    pub backcall: u64,
+    /// Charged to the defender for every call it checks, on top of the gas of the checks.
+    pub check: u64,
+    /// Gas the attacker may use in a game, its transactions get no more than what is left.
+    pub attacker_budget: Option<u64>,
+    /// Gas the defender may use in a game, calls it cannot afford to check are rejected.
+    pub defender_budget: Option<u64>,
+    /// Price of the gas of the attacker, see [`GameEnvironment::attacker_net_balance`].
+    pub gas_price: U256,
+}
+
+/// Gas the players used in a game.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasUsage {
+    /// Gas of the transactions of the attacker, with its answers and backcalls.
+    pub attacker: u64,
+    /// Gas of the checks of the defender.
+    pub defender: u64,
+}
+
+/// Checks run by the defender before a call into its contract goes on.
+pub trait DefenderChecks {
+    fn check(&mut self, call_inputs: &CallInputs) -> CheckResult;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckResult {
+    /// The call is allowed, otherwise it is reverted.
+    pub pass: bool,
+    /// Gas spent by the checks.
+    pub gas: u64,
+    /// Bytes of auxiliary state the checks keep.
+    pub memory: usize,
+}
+
+/// Defender that lets every call through for free.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoChecks;
+
+impl DefenderChecks for NoChecks {
+    fn check(&mut self, _call_inputs: &CallInputs) -> CheckResult {
+        CheckResult { pass: true, gas: 0, memory: 0 }
+    }
+}
+
+impl<F: FnMut(&CallInputs) -> CheckResult> DefenderChecks for F {
+    fn check(&mut self, call_inputs: &CallInputs) -> CheckResult {
+        self(call_inputs)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InterpreterSlot<I = Box<Interpreter>> {
     // the return len to return to when this is popped
     Fake{call_inputs: Box<CallInputs>, return_len: usize, return_offset: usize},
-    Interpreter{call_inputs: Box<CallInputs>, interpreter: Box<Interpreter>, return_len: usize, return_offset: usize},
+    Interpreter{call_inputs: Box<CallInputs>, interpreter: I, return_len: usize, return_offset: usize},
+}
+
+impl InterpreterSlot {
+    /// Snapshots keep the interpreters parked, which makes them `Send`.
+    fn park(self) -> InterpreterSlot<ParkedInterpreter> {
+        match self {
+            Self::Fake { call_inputs, return_len, return_offset } => InterpreterSlot::Fake { call_inputs, return_len, return_offset },
+            Self::Interpreter { call_inputs, interpreter, return_len, return_offset } => InterpreterSlot::Interpreter { call_inputs, interpreter: interpreter.park(), return_len, return_offset },
+        }
+    }
+}
+
+impl InterpreterSlot<ParkedInterpreter> {
+    fn unpark(self) -> InterpreterSlot {
+        match self {
+            Self::Fake { call_inputs, return_len, return_offset } => InterpreterSlot::Fake { call_inputs, return_len, return_offset },
+            Self::Interpreter { call_inputs, interpreter, return_len, return_offset } => InterpreterSlot::Interpreter { call_inputs, interpreter: Box::new(interpreter.unpark()), return_len, return_offset },
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StuckState {
     MoveAttacker,
     CallAttacker{call_inputs: Box<CallInputs>, return_len: usize, return_offset: usize},
@@ -27,6 +120,51 @@ pub enum StuckState {
     Noop,
 }
 
+impl StuckState {
+    /// What the game waits for.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::MoveAttacker => "attacker to move",
+            Self::CallAttacker { .. } => "attacker to answer a call",
+            Self::PrepareAttackerReturn { .. } => "attacker to return",
+            Self::CallDefender { .. } => "defender to check a call",
+            Self::SomeoneReturn { .. } => "return to be popped",
+            Self::Noop => "nothing",
+        }
+    }
+}
+
+/// Everything a move changes, to branch from a position and come back to it.
+#[derive(Debug, Clone)]
+pub struct GameSnapshot {
+    journaled_state: revm::JournaledState,
+    suspended: Vec<revm::JournalCheckpoint>,
+    attacker_checkpoints: Vec<revm::JournalCheckpoint>,
+    attacker_frames: Vec<u64>,
+    gas_used: GasUsage,
+    broken: Option<String>,
+    interpreters: Vec<InterpreterSlot<ParkedInterpreter>>,
+    stuck_state: StuckState,
+    tx: revm::primitives::TxEnv,
+    last_result: Option<CallResult>,
+}
+
+/// A game without the borrows of its `Env` and database, see [`GameEnvironment::into_parts`].
+#[derive(Debug, Clone)]
+pub struct GameParts {
+    snapshot: GameSnapshot,
+    attacker_account: B160,
+    defender_account: B160,
+    abi: BaseContract,
+    world: revm::primitives::StateRootCache,
+    gas_model: GasModel,
+    invariants: Option<Invariants>,
+}
+
+fn precompiles() -> revm::precompile::Precompiles {
+    revm::precompile::Precompiles::new(revm::precompile::SpecId::BERLIN).clone()
+}
+
 impl<'a> GameEnvironment<'a> {
     pub fn new(
         env: &'a mut revm::primitives::Env,
@@ -46,41 +184,289 @@ impl<'a> GameEnvironment<'a> {
             stuck_state: StuckState::MoveAttacker,
             attacker_account,
             defender_account: B160::zero(),
-            executor: EVMImpl::new(db, env, revm::precompile::Precompiles::new(revm::precompile::SpecId::BERLIN).clone()),
+            executor: EVMImpl::new(db, env, precompiles()),
             interpreters: vec![],
             abi,
+            last_result: None,
+            world: Default::default(),
+            attacker_checkpoints: vec![],
+            attacker_frames: vec![],
+            gas_model: GasModel::default(),
+            gas_used: GasUsage::default(),
+            invariants: None,
+            broken: None,
         };
         this.executor.data.db.insert_account_info(B160::zero(), AccountInfo{
             balance: U256::MAX, nonce: 1,
             code_hash: revm::primitives::KECCAK_EMPTY, code: None,
         });
-        let (create_result, _interpreter) = this.executor.create(&CreateInputs{
+        // the constructor runs to its end, there are no moves before the game starts
+        let create_result = this.executor.create_nested(&CreateInputs{
             caller: B160::zero(),
             scheme: revm::primitives::CreateScheme::Create,
             init_code: contract_deployment_code.clone(),
             value: U256::MAX / U256::from(2),
             gas_limit: 1000000,
-        }, None);
-        println!("{create_result:#?}");
+        });
         this.defender_account = create_result.created_address.unwrap();
         let code = Bytes::default();
         this.executor.data.db.insert_account_info(this.attacker_account, AccountInfo { balance: attacker_balance, nonce: 1, code_hash: revm::primitives::keccak256(&code), code: None });
-        return this;
+        for (address, account) in &this.executor.data.db.accounts {
+            if account.info().is_none() { continue }
+            this.world.set_account(*address, &account.info);
+            for (index, value) in &account.storage {
+                this.world.set_storage(*address, *index, *value);
+            }
+        }
+        this
+    }
+    /// Releases the `Env` and database, the game goes on with [`GameEnvironment::from_parts`]
+    /// on the same ones.
+    pub fn into_parts(mut self) -> GameParts {
+        let journaled_state = std::mem::replace(&mut self.executor.data.journaled_state, revm::JournaledState::new(0));
+        GameParts {
+            snapshot: GameSnapshot {
+                journaled_state,
+                suspended: self.executor.suspended().to_vec(),
+                attacker_checkpoints: self.attacker_checkpoints,
+                attacker_frames: self.attacker_frames,
+                gas_used: self.gas_used,
+                broken: self.broken,
+                interpreters: self.interpreters.into_iter().map(InterpreterSlot::park).collect(),
+                stuck_state: self.stuck_state,
+                tx: self.executor.data.env.tx.clone(),
+                last_result: self.last_result,
+            },
+            attacker_account: self.attacker_account,
+            defender_account: self.defender_account,
+            abi: self.abi,
+            world: self.world,
+            gas_model: self.gas_model,
+            invariants: self.invariants,
+        }
+    }
+    pub fn from_parts(env: &'a mut revm::primitives::Env, db: &'a mut revm::InMemoryDB, parts: GameParts) -> Self {
+        let GameParts { snapshot, attacker_account, defender_account, abi, world, gas_model, invariants } = parts;
+        let mut executor = revm::EVMImpl::new(db, env, precompiles());
+        executor.data.journaled_state = snapshot.journaled_state;
+        executor.set_suspended(snapshot.suspended);
+        executor.data.env.tx = snapshot.tx;
+        GameEnvironment {
+            executor,
+            interpreters: snapshot.interpreters.into_iter().map(InterpreterSlot::unpark).collect(),
+            stuck_state: snapshot.stuck_state,
+            attacker_account,
+            defender_account,
+            abi,
+            last_result: snapshot.last_result,
+            world,
+            attacker_checkpoints: snapshot.attacker_checkpoints,
+            attacker_frames: snapshot.attacker_frames,
+            gas_model,
+            gas_used: snapshot.gas_used,
+            invariants,
+            broken: snapshot.broken,
+        }
+    }
+    /// Frames the game keeps suspended, one per call between the players still running.
+    pub fn depth(&self) -> usize {
+        self.interpreters.len()
+    }
+    pub fn stuck_state(&self) -> &StuckState {
+        &self.stuck_state
+    }
+    pub fn snapshot(&self) -> GameSnapshot {
+        GameSnapshot {
+            journaled_state: self.executor.data.journaled_state.clone(),
+            suspended: self.executor.suspended().to_vec(),
+            attacker_checkpoints: self.attacker_checkpoints.clone(),
+            attacker_frames: self.attacker_frames.clone(),
+            gas_used: self.gas_used,
+            broken: self.broken.clone(),
+            interpreters: self.interpreters.iter().cloned().map(InterpreterSlot::park).collect(),
+            stuck_state: self.stuck_state.clone(),
+            tx: self.executor.data.env.tx.clone(),
+            last_result: self.last_result.clone(),
+        }
+    }
+    pub fn restore(&mut self, snapshot: &GameSnapshot) {
+        self.executor.data.journaled_state = snapshot.journaled_state.clone();
+        self.executor.set_suspended(snapshot.suspended.clone());
+        self.attacker_checkpoints = snapshot.attacker_checkpoints.clone();
+        self.attacker_frames = snapshot.attacker_frames.clone();
+        self.gas_used = snapshot.gas_used;
+        self.broken = snapshot.broken.clone();
+        self.interpreters = snapshot.interpreters.iter().cloned().map(InterpreterSlot::unpark).collect();
+        self.stuck_state = snapshot.stuck_state.clone();
+        self.executor.data.env.tx = snapshot.tx.clone();
+        self.last_result = snapshot.last_result.clone();
+    }
+    /// Stable hash of the position: world state, suspended frames and the pending move.
+    pub fn fingerprint(&self) -> Fingerprint {
+        let journaled_state = &self.executor.data.journaled_state;
+        let mut world = self.world.clone();
+        world.commit(&journaled_state.state);
+        let mut builder = FingerprintBuilder::new();
+        builder.hash(world.root());
+        let mut transient: Vec<_> = journaled_state.transient_storage.iter().filter(|(_, value)| **value != U256::ZERO).collect();
+        transient.sort_unstable();
+        builder.u64(transient.len() as u64);
+        for ((address, index), value) in transient {
+            builder.address(*address).word(*index).word(*value);
+        }
+        builder.u64(self.interpreters.len() as u64);
+        for slot in &self.interpreters {
+            match slot {
+                InterpreterSlot::Fake { call_inputs, return_len, return_offset } => {
+                    builder.tag(0).call_inputs(call_inputs).u64(*return_len as u64).u64(*return_offset as u64);
+                }
+                InterpreterSlot::Interpreter { call_inputs, interpreter, return_len, return_offset } => {
+                    builder.tag(1).call_inputs(call_inputs).interpreter(interpreter).u64(*return_len as u64).u64(*return_offset as u64);
+                }
+            }
+        }
+        match &self.stuck_state {
+            StuckState::MoveAttacker => { builder.tag(0); }
+            StuckState::CallAttacker { call_inputs, return_len, return_offset } => {
+                builder.tag(1).call_inputs(call_inputs).u64(*return_len as u64).u64(*return_offset as u64);
+            }
+            StuckState::PrepareAttackerReturn { call_inputs, return_len, return_offset } => {
+                builder.tag(2).call_inputs(call_inputs).u64(*return_len as u64).u64(*return_offset as u64);
+            }
+            StuckState::CallDefender { call_inputs, return_len, return_offset } => {
+                builder.tag(3).call_inputs(call_inputs).u64(*return_len as u64).u64(*return_offset as u64);
+            }
+            StuckState::SomeoneReturn { result, return_len, return_offset } => {
+                builder.tag(4).tag(result.result as u8).u64(result.gas.remaining()).bytes(&result.return_value).u64(*return_len as u64).u64(*return_offset as u64);
+            }
+            StuckState::Noop => { builder.tag(5); }
+        }
+        builder.u64(self.attacker_frames.len() as u64);
+        for frame in &self.attacker_frames {
+            builder.u64(*frame);
+        }
+        // gas used only changes what comes next under budgets or a price
+        let model = &self.gas_model;
+        if model.attacker_budget.is_some() || model.defender_budget.is_some() || model.gas_price != U256::ZERO {
+            builder.u64(self.gas_used.attacker).u64(self.gas_used.defender);
+        }
+        builder.finish()
+    }
+    pub fn balance(&mut self, address: B160) -> U256 {
+        let (account, _) = self.executor.data.journaled_state.load_account(address, self.executor.data.db).unwrap();
+        account.info.balance
+    }
+    pub fn set_balance(&mut self, address: B160, balance: U256) {
+        self.executor.data.journaled_state.load_account(address, self.executor.data.db).unwrap();
+        self.executor.data.journaled_state.set_balance(address, balance);
     }
-    fn pop_return(&mut self) {
-        let StuckState::SomeoneReturn { result, return_len, return_offset } = 
+    pub fn storage(&mut self, address: B160, index: U256) -> U256 {
+        self.executor.data.journaled_state.load_account(address, self.executor.data.db).unwrap();
+        self.executor.data.journaled_state.sload(address, index, self.executor.data.db).unwrap().0
+    }
+    /// Slots of `address` known to the game, i.e. stored before it started or touched since.
+    pub fn storage_slots(&self, address: B160) -> std::collections::BTreeMap<U256, U256> {
+        let mut slots = std::collections::BTreeMap::new();
+        if let Some(account) = self.executor.data.db.accounts.get(&address) {
+            slots.extend(account.storage.iter().map(|(index, value)| (*index, *value)));
+        }
+        if let Some(account) = self.executor.data.journaled_state.state.get(&address) {
+            slots.extend(account.storage.iter().map(|(index, slot)| (*index, slot.present_value)));
+        }
+        slots
+    }
+    pub fn code(&mut self, address: B160) -> Bytes {
+        let (account, _) = self.executor.data.journaled_state.load_code(address, self.executor.data.db).unwrap();
+        account.info.code.as_ref().map(|code| code.original_bytes()).unwrap_or_default()
+    }
+    pub fn gas_used(&self) -> GasUsage {
+        self.gas_used
+    }
+    /// Balance of the attacker minus the price of the gas it used, what its utility is measured on.
+    pub fn attacker_net_balance(&mut self) -> U256 {
+        let cost = self.gas_model.gas_price.saturating_mul(U256::from(self.gas_used.attacker));
+        self.balance(self.attacker_account).saturating_sub(cost)
+    }
+    /// Charges the defender for `check` and the check of the gas model, returns whether the call
+    /// goes on. A call the defender cannot afford to check is rejected, nothing gets past the
+    /// defender unchecked.
+    pub fn charge_check(&mut self, check: &CheckResult) -> bool {
+        let cost = check.gas.saturating_add(self.gas_model.check);
+        let used = self.gas_used.defender.saturating_add(cost);
+        if self.gas_model.defender_budget.is_some_and(|budget| used > budget) {
+            return false;
+        }
+        self.gas_used.defender = used;
+        check.pass
+    }
+    /// Instructions run since the last call, recorded when the game is created with
+    /// `env.cfg.trace_steps` set.
+    pub fn take_trace(&mut self) -> Vec<revm::TraceStep> {
+        self.executor.take_trace()
+    }
+    /// Light events of the instructions run since the last call, recorded when the game is
+    /// created with `env.cfg.trace_events` set.
+    pub fn take_events(&mut self) -> Vec<revm::StepEvent> {
+        self.executor.take_events()
+    }
+    /// Checks `invariants` after every move that runs code and after every return from now on,
+    /// including in the middle of transactions. Returns the ones checked before.
+    pub fn set_invariants(&mut self, invariants: Option<Invariants>) -> Option<Invariants> {
+        std::mem::replace(&mut self.invariants, invariants)
+    }
+    /// Name of the first invariant a position of the game violated, which ends the game.
+    pub fn broken(&self) -> Option<&str> {
+        self.broken.as_deref()
+    }
+    fn check_invariants(&mut self) {
+        if self.broken.is_some() { return }
+        let Some(invariants) = self.invariants.take() else { return };
+        self.broken = invariants.violated(self).map(|invariant| invariant.name.clone());
+        self.invariants = Some(invariants);
+    }
+    /// Static call from `caller` into the defender on the current state, e.g. of a view
+    /// function. The game is left as it was, steps of the call are not recorded.
+    pub fn static_call(&mut self, caller: B160, data: Bytes, gas_limit: u64) -> CallResult {
+        let snapshot = self.snapshot();
+        let defender = self.defender_account;
+        let call_inputs = CallInputs {
+            contract: defender,
+            transfer: Transfer { source: caller, target: defender, value: U256::ZERO },
+            input: data,
+            gas_limit,
+            context: CallContext { caller, address: defender, code_address: defender, apparent_value: U256::ZERO, scheme: revm::interpreter::CallScheme::StaticCall },
+            is_static: true,
+        };
+        let (result, _) = self.executor.untraced(|executor| executor.call(&call_inputs, None));
+        self.restore(&snapshot);
+        result
+    }
+    pub fn pop_return(&mut self) {
+        let StuckState::SomeoneReturn { result, .. } = 
             std::mem::replace(&mut self.stuck_state, StuckState::Noop) else { panic!() };
         let interpreter = self.interpreters.pop();
         match interpreter {
             None => {
+                if self.executor.data.env.tx.caller == self.attacker_account {
+                    // a transaction that halts uses all its gas
+                    let spent = if matches!(result.result, return_ok!() | return_revert!()) { result.gas.spend() } else { result.gas.limit() };
+                    self.gas_used.attacker = self.gas_used.attacker.saturating_add(spent);
+                }
+                // EIP-1153: transient storage lasts for one transaction
+                self.executor.data.journaled_state.transient_storage.clear();
+                self.last_result = Some(result);
                 self.stuck_state = StuckState::MoveAttacker;
             }
             Some(InterpreterSlot::Fake { call_inputs, return_len, return_offset }) => {
+                // the backcall runs on the gas of the call into the attacker
+                let frame = self.attacker_frames.last_mut().expect("the attacker was called");
+                *frame = frame.saturating_add(result.gas.spend());
                 self.stuck_state = StuckState::PrepareAttackerReturn { call_inputs, return_len, return_offset };
             }
             Some(InterpreterSlot::Interpreter{call_inputs, return_len, return_offset, mut interpreter}) => {
                 interpreter.stuck_reason = StuckReason::CallReturn(result.result, result.gas, result.return_value, return_len, return_offset);
                 let (result, interpreter) = self.executor.call(&call_inputs, Some(interpreter));
+                let (result, interpreter) = self.run_creates(&call_inputs, result, interpreter);
                 if let Some(interpreter) = interpreter {
                     match &interpreter.stuck_reason {
                         StuckReason::Call(call_inputs, return_len, return_offset) => {
@@ -88,30 +474,39 @@ impl<'a> GameEnvironment<'a> {
                             let return_len = *return_len;
                             let return_offset = *return_offset;
                             if _call_inputs.contract == self.attacker_account {
-                                self.stuck_state = StuckState::CallAttacker { call_inputs: _call_inputs, return_len, return_offset };
+                                self.call_attacker(_call_inputs, return_len, return_offset);
                             } else {
                                 self.stuck_state = StuckState::CallDefender { call_inputs: _call_inputs, return_len, return_offset };
                             }
                             self.interpreters.push(InterpreterSlot::Interpreter{call_inputs: call_inputs.clone(), interpreter, return_len, return_offset});
                         },
-                        _ => unimplemented!()
+                        reason => unreachable!("creates run to their end: {reason:?}")
                     }
                 } else {
                     self.stuck_state = StuckState::SomeoneReturn { result, return_len, return_offset }
                 }
             }
         }
+        self.check_invariants();
+    }
+    /// Transaction of the attacker, with no more gas than is left of its budget.
+    pub fn attacker_move(&mut self, data: revm::primitives::Bytes, value: revm::primitives::U256, gas_limit: u64) {
+        let left = self.gas_model.attacker_budget.map(|budget| budget.saturating_sub(self.gas_used.attacker));
+        let gas_limit = left.map_or(gas_limit, |left| gas_limit.min(left));
+        self.user_move(self.attacker_account, data, value, gas_limit)
     }
-    fn attacker_move(&mut self, data: revm::primitives::Bytes, value: revm::primitives::U256, gas_limit: u64) {
+    /// Transaction from any account into the defender, e.g. a test case of the defender.
+    pub fn user_move(&mut self, caller: B160, data: revm::primitives::Bytes, value: revm::primitives::U256, gas_limit: u64) {
         use revm::interpreter::CallScheme;
+        self.executor.data.env.tx.caller = caller;
         self.executor.data.env.tx.data = data.clone();
         let call_inputs = Box::new(CallInputs {
             contract: self.defender_account,
-            transfer: Transfer { source: self.attacker_account, target: self.defender_account, value },
+            transfer: Transfer { source: caller, target: self.defender_account, value },
             input: data,
             gas_limit,
             context: CallContext {
-                caller: self.attacker_account,
+                caller,
                 address: self.defender_account,
                 code_address: self.defender_account,
                 apparent_value: value,
@@ -121,16 +516,68 @@ impl<'a> GameEnvironment<'a> {
         });
         self.stuck_state = StuckState::CallDefender { call_inputs, return_len: 0, return_offset: 0 }
     }
-    fn attacker_pass(&mut self, pass: bool) {
-        let StuckState::PrepareAttackerReturn { return_len, return_offset, .. } = std::mem::replace(&mut self.stuck_state, StuckState::Noop) 
+    /// Transaction of `victim` into the attacker, e.g. of an owner of the defender lured by
+    /// phishing. The attacker answers it like a call of the defender, with `victim` as origin.
+    pub fn victim_move(&mut self, victim: B160, data: Bytes, value: U256, gas_limit: u64) {
+        self.executor.data.env.tx.caller = victim;
+        self.executor.data.env.tx.data = data.clone();
+        let attacker = self.attacker_account;
+        let call_inputs = Box::new(CallInputs {
+            contract: attacker,
+            transfer: Transfer { source: victim, target: attacker, value },
+            input: data,
+            gas_limit,
+            context: CallContext { caller: victim, address: attacker, code_address: attacker, apparent_value: value, scheme: revm::interpreter::CallScheme::Call },
+            is_static: false
+        });
+        self.call_attacker(call_inputs, 0, 0);
+        self.check_invariants();
+    }
+    /// Enters a call of the defender into the attacker. The value of the call moves under a
+    /// checkpoint that [`Self::attacker_pass`] commits or reverts.
+    fn call_attacker(&mut self, call_inputs: Box<CallInputs>, return_len: usize, return_offset: usize) {
+        let journaled_state = &mut self.executor.data.journaled_state;
+        let checkpoint = journaled_state.checkpoint();
+        let transfer = &call_inputs.transfer;
+        if let Err(result) = journaled_state.transfer(&transfer.source, &transfer.target, transfer.value, self.executor.data.db) {
+            journaled_state.checkpoint_revert(checkpoint);
+            let call_result = CallResult { result, gas: Gas::new(call_inputs.gas_limit), return_value: Bytes::default() };
+            self.stuck_state = StuckState::SomeoneReturn { result: call_result, return_len, return_offset };
+            return;
+        }
+        self.attacker_checkpoints.push(checkpoint);
+        self.attacker_frames.push(self.gas_model.fake_frame);
+        self.stuck_state = StuckState::CallAttacker { call_inputs, return_len, return_offset };
+    }
+    /// Returns from the call into the attacker, or reverts it. The call runs out of gas if the
+    /// answer of the attacker used more than the call had.
+    pub fn attacker_pass(&mut self, pass: bool) {
+        let StuckState::PrepareAttackerReturn { call_inputs, return_len, return_offset } = std::mem::replace(&mut self.stuck_state, StuckState::Noop) 
             else { panic!() };
-        let call_result = CallResult { result: if pass { InstructionResult::Return } else { InstructionResult::Revert }, gas: Gas::new(0), return_value: Bytes::default() };
-        self.stuck_state = StuckState::SomeoneReturn { result: call_result, return_len, return_offset }
+        let checkpoint = self.attacker_checkpoints.pop().expect("the attacker was called");
+        let used = self.attacker_frames.pop().expect("the attacker was called");
+        let mut gas = Gas::new(call_inputs.gas_limit);
+        let result = if !gas.record_cost(used) {
+            gas.record_cost(gas.remaining());
+            InstructionResult::OutOfGas
+        } else if pass { InstructionResult::Return } else { InstructionResult::Revert };
+        if matches!(result, InstructionResult::Return) {
+            self.executor.data.journaled_state.checkpoint_commit();
+        } else {
+            self.executor.data.journaled_state.checkpoint_revert(checkpoint);
+        }
+        let call_result = CallResult { result, gas, return_value: Bytes::default() };
+        self.stuck_state = StuckState::SomeoneReturn { result: call_result, return_len, return_offset };
+        self.check_invariants();
     }
-    fn attacker_answer(&mut self, backcall: Option<(Bytes, U256, u64)>) {
+    pub fn attacker_answer(&mut self, backcall: Option<(Bytes, U256, u64)>) {
         let StuckState::CallAttacker { call_inputs, return_len, return_offset } = 
             std::mem::replace(&mut self.stuck_state, StuckState::Noop) else { panic!() };
         if let Some((data, value, gas_limit)) = backcall {
+            // the backcall gets no more than what is left of the call into the attacker
+            let frame = self.attacker_frames.last_mut().expect("the attacker was called");
+            *frame = frame.saturating_add(self.gas_model.backcall);
+            let gas_limit = gas_limit.min(call_inputs.gas_limit.saturating_sub(*frame));
             let backcall_inputs = Box::new(CallInputs {
                 contract: self.defender_account,
                 transfer: Transfer { source: self.attacker_account, target: self.defender_account, value },
@@ -151,18 +598,24 @@ impl<'a> GameEnvironment<'a> {
             self.stuck_state = StuckState::PrepareAttackerReturn { call_inputs, return_len, return_offset };
         }
     }
-    fn defender_pass(&mut self, pass: bool) {
+    pub fn defender_pass(&mut self, pass: bool) {
+        self.enter_defender(pass);
+        self.check_invariants();
+    }
+    fn enter_defender(&mut self, pass: bool) {
         let StuckState::CallDefender { call_inputs, return_len, return_offset } = 
             std::mem::replace(&mut self.stuck_state, StuckState::Noop) else { panic!("{:?}", self.stuck_state) };
         if !pass {
+            // a banned call runs nothing, its gas goes back to the caller
             self.stuck_state = StuckState::SomeoneReturn{ result: CallResult {
                 result: InstructionResult::Revert, 
-                gas: Gas::new(0),
+                gas: Gas::new(call_inputs.gas_limit),
                 return_value: Bytes::default(),
             }, return_len, return_offset };
             return;
         }
         let (call_result, maybe_interpreter) = self.executor.call(&call_inputs, None);
+        let (call_result, maybe_interpreter) = self.run_creates(&call_inputs, call_result, maybe_interpreter);
         if !matches!(call_result.result, InstructionResult::Stuck) {
             self.stuck_state = StuckState::SomeoneReturn { result: call_result, return_len, return_offset };
             return;
@@ -174,30 +627,53 @@ impl<'a> GameEnvironment<'a> {
                 let return_len = *return_len;
                 let return_offset = *return_offset;
                 if _call_inputs.contract == self.attacker_account {
-                    self.stuck_state = StuckState::CallAttacker { call_inputs: _call_inputs, return_len, return_offset };
+                    self.call_attacker(_call_inputs, return_len, return_offset);
                 } else {
                     self.stuck_state = StuckState::CallDefender { call_inputs: _call_inputs, return_len, return_offset };
                 }
                 self.interpreters.push(InterpreterSlot::Interpreter{call_inputs: call_inputs.clone(), interpreter, return_len, return_offset});
             },
-            _ => unimplemented!()
+            reason => unreachable!("creates run to their end: {reason:?}")
+        }
+    }
+    /// Runs the creates the interpreter of `call_inputs` gets stuck on to their end and resumes
+    /// it, until it returns or gets stuck on a call. Calls of the init code into the attacker
+    /// are not its moves, they run on its empty code.
+    fn run_creates(&mut self, call_inputs: &CallInputs, mut result: CallResult, mut interpreter: Option<Box<Interpreter>>) -> (CallResult, Option<Box<Interpreter>>) {
+        while let Some(mut stuck) = interpreter {
+            let StuckReason::Create(create_inputs) = &stuck.stuck_reason else { return (result, Some(stuck)) };
+            let created = self.executor.create_nested(create_inputs);
+            stuck.stuck_reason = StuckReason::CreateReturn(created.result, created.created_address, created.gas, created.return_value);
+            (result, interpreter) = self.executor.call(call_inputs, Some(stuck));
         }
+        (result, None)
     }
 }
 
+/// Deployment code of SillyBank, a bank whose `withdraw` can be reentered.
+#[cfg(test)]
+pub(crate) const SILLY_BANK: &[u8] = &revm::primitives::hex_literal::hex!("608060405261046a806100136000396000f3fe6080604052600436106100345760003560e01c806327e235e3146100395780633ccfd60b14610076578063d0e30db01461008d575b600080fd5b34801561004557600080fd5b50610060600480360381019061005b91906102ad565b610097565b60405161006d91906102f3565b60405180910390f35b34801561008257600080fd5b5061008b6100af565b005b6100956101f3565b005b60006020528060005260406000206000915090505481565b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020549050600081116100ff57600080fd5b60003373ffffffffffffffffffffffffffffffffffffffff16826040516101259061033f565b60006040518083038185875af1925050503d8060008114610162576040519150601f19603f3d011682016040523d82523d6000602084013e610167565b606091505b50509050806101ab576040517f08c379a00000000000000000000000000000000000000000000000000000000081526004016101a2906103b1565b60405180910390fd5b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020819055505050565b346000803373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200190815260200160002060008282546102419190610400565b92505081905550565b600080fd5b600073ffffffffffffffffffffffffffffffffffffffff82169050919050565b600061027a8261024f565b9050919050565b61028a8161026f565b811461029557600080fd5b50565b6000813590506102a781610281565b92915050565b6000602082840312156102c3576102c261024a565b5b60006102d184828501610298565b91505092915050565b6000819050919050565b6102ed816102da565b82525050565b600060208201905061030860008301846102e4565b92915050565b600081905092915050565b50565b600061032960008361030e565b915061033482610319565b600082019050919050565b600061034a8261031c565b9150819050919050565b600082825260208201905092915050565b7f4661696c656420746f2073656e64204574686572000000000000000000000000600082015250565b600061039b601483610354565b91506103a682610365565b602082019050919050565b600060208201905081810360008301526103ca8161038e565b9050919050565b7f4e487b7100000000000000000000000000000000000000000000000000000000600052601160045260246000fd5b600061040b826102da565b9150610416836102da565b925082820190508082111561042e5761042d6103d1565b5b9291505056fea2646970667358221220b3616bed71d88f1b5fd72ea2bfb498060d234bba17beb056f47e3034bb27281864736f6c63430008120033");
+
+#[cfg(test)]
+pub(crate) fn silly_bank_abi() -> BaseContract {
+    ethers::abi::parse_abi(&[
+        "function balances(address) view returns (uint256)",
+        "function deposit() payable",
+        "function withdraw()",
+    ]).unwrap().into()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use revm::{primitives::{Env, hex, hex_literal::*}, InMemoryDB};
-    use ethers::prelude::BaseContract;
+    use revm::{primitives::{Env, hex}, InMemoryDB};
 
     #[test]
     fn deploy_silly_bank() {
         let mut env = Env::default();
         let mut db = InMemoryDB::default();
-        let data = hex!("608060405261046a806100136000396000f3fe6080604052600436106100345760003560e01c806327e235e3146100395780633ccfd60b14610076578063d0e30db01461008d575b600080fd5b34801561004557600080fd5b50610060600480360381019061005b91906102ad565b610097565b60405161006d91906102f3565b60405180910390f35b34801561008257600080fd5b5061008b6100af565b005b6100956101f3565b005b60006020528060005260406000206000915090505481565b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020549050600081116100ff57600080fd5b60003373ffffffffffffffffffffffffffffffffffffffff16826040516101259061033f565b60006040518083038185875af1925050503d8060008114610162576040519150601f19603f3d011682016040523d82523d6000602084013e610167565b606091505b50509050806101ab576040517f08c379a00000000000000000000000000000000000000000000000000000000081526004016101a2906103b1565b60405180910390fd5b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020819055505050565b346000803373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200190815260200160002060008282546102419190610400565b92505081905550565b600080fd5b600073ffffffffffffffffffffffffffffffffffffffff82169050919050565b600061027a8261024f565b9050919050565b61028a8161026f565b811461029557600080fd5b50565b6000813590506102a781610281565b92915050565b6000602082840312156102c3576102c261024a565b5b60006102d184828501610298565b91505092915050565b6000819050919050565b6102ed816102da565b82525050565b600060208201905061030860008301846102e4565b92915050565b600081905092915050565b50565b600061032960008361030e565b915061033482610319565b600082019050919050565b600061034a8261031c565b9150819050919050565b600082825260208201905092915050565b7f4661696c656420746f2073656e64204574686572000000000000000000000000600082015250565b600061039b601483610354565b91506103a682610365565b602082019050919050565b600060208201905081810360008301526103ca8161038e565b9050919050565b7f4e487b7100000000000000000000000000000000000000000000000000000000600052601160045260246000fd5b600061040b826102da565b9150610416836102da565b925082820190508082111561042e5761042d6103d1565b5b9291505056fea2646970667358221220b3616bed71d88f1b5fd72ea2bfb498060d234bba17beb056f47e3034bb27281864736f6c63430008120033").to_vec();
-        let mut json = std::fs::File::open("/home/y-jiji/eth-game/tmp/SillyBank.abi").unwrap();
-        let abi: BaseContract = ethers::abi::Abi::load(&mut json).unwrap().into();
+        let data = SILLY_BANK.to_vec();
+        let abi = silly_bank_abi();
         let mut game = GameEnvironment::new(&mut env, &mut db, B160::random(), U256::from(1000), data.into(), abi.clone());
         println!("====== players ======");
         println!("Attacker: {:?}", game.attacker_account);
@@ -221,4 +697,100 @@ mod test {
         println!("====== journaled state ======");
         println!("{:#?}", game.executor.data.journaled_state);
     }
+    #[test]
+    fn defender_creates_a_contract() {
+        // on every call: CREATE a contract with no code, store its address, call the caller
+        let runtime = "6460006000f36000526005601b6000f060005560006000600060006000335af15000";
+        let data = hex::decode(format!("602280600b6000396000f3{runtime}")).unwrap();
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let attacker = B160::random();
+        let mut game = GameEnvironment::new(&mut env, &mut db, attacker, U256::from(1000), data.into(), ethers::abi::Abi::default().into());
+        game.attacker_move(Bytes::default(), U256::ZERO, 1_000_000);
+        game.defender_pass(true);
+        assert!(matches!(game.stuck_state(), StuckState::CallAttacker { .. }), "{:?}", game.stuck_state());
+        game.attacker_answer(None);
+        game.attacker_pass(true);
+        game.pop_return();
+        game.pop_return();
+        assert!(matches!(game.stuck_state(), StuckState::MoveAttacker));
+        assert!(matches!(game.last_result.as_ref().unwrap().result, return_ok!()));
+        let defender = game.defender_account;
+        let created = revm::primitives::create_address(defender, 1);
+        assert_eq!(game.storage(defender, U256::ZERO), U256::from_be_bytes(<[u8; 32]>::try_from([&[0; 12][..], &created.0].concat()).unwrap()));
+    }
+    #[test]
+    fn clears_transient_storage_between_transactions() {
+        // on every call: store the transient slot 0 in slot 0, then set the transient slot 0
+        let runtime = "60005c600055600160005d00";
+        let data = hex::decode(format!("600c80600b6000396000f3{runtime}")).unwrap();
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let attacker = B160::random();
+        let mut game = GameEnvironment::new(&mut env, &mut db, attacker, U256::from(1000), data.into(), ethers::abi::Abi::default().into());
+        let defender = game.defender_account;
+        for _ in 0..2 {
+            game.attacker_move(Bytes::default(), U256::ZERO, 1_000_000);
+            game.defender_pass(true);
+            game.pop_return();
+            assert!(matches!(game.last_result.as_ref().unwrap().result, return_ok!()));
+            assert_eq!(game.storage(defender, U256::ZERO), U256::ZERO);
+        }
+        assert!(game.executor.data.journaled_state.transient_storage.is_empty());
+    }
+    #[test]
+    fn charges_gas_per_player() {
+        use crate::corpus::{benchmark, ATTACKER};
+        use crate::episode::Move;
+        use crate::mcts::Action;
+        let bank = benchmark("reentrancy").unwrap();
+        let play = |actions: &[Action], gas_model: GasModel, mut checks: Box<dyn DefenderChecks>, attacker_balance: u64| {
+            let mut env = Env::default();
+            let mut db = InMemoryDB::default();
+            let mut game = bank.vulnerable.game(&mut env, &mut db, ATTACKER, U256::from(attacker_balance));
+            game.gas_model = gas_model;
+            let mut actions = actions.iter();
+            loop {
+                let next = match game.stuck_state() {
+                    StuckState::CallDefender { call_inputs, .. } => {
+                        let check = checks.check(call_inputs);
+                        Move::Defender(game.charge_check(&check))
+                    }
+                    _ => match actions.next() {
+                        Some(action) => Move::Attacker(action.clone()),
+                        None => break,
+                    },
+                };
+                next.play(&mut game).unwrap();
+            }
+            (game.balance(ATTACKER), game.attacker_net_balance(), game.gas_used())
+        };
+        let (deposit, withdraw) = (bank.exploit[0].clone(), bank.exploit[1].clone());
+        let honest = [deposit.clone(), withdraw, Action::Return, Action::Pass(true)];
+
+        let (balance, net, gas) = play(&bank.exploit, GasModel::default(), Box::new(NoChecks), 1000);
+        assert_eq!((balance, net), (U256::from(1500), U256::from(1500)));
+        assert!(gas.attacker > 0 && gas.defender == 0);
+        let gas_price = GasModel { gas_price: U256::from(2), ..Default::default() };
+        let (_, net, _) = play(&bank.exploit, gas_price, Box::new(NoChecks), 1_000_000);
+        assert_eq!(net, U256::from(1_000_500 - 2 * gas.attacker));
+
+        // the call into the attacker gets its gas back, unless its answer costs more than it had
+        assert_eq!(play(&honest, GasModel::default(), Box::new(NoChecks), 1000).0, U256::from(1000));
+        let costly = GasModel { fake_frame: 10_000_000, ..Default::default() };
+        assert_eq!(play(&honest, costly, Box::new(NoChecks), 1000).0, U256::from(500));
+        let broke = GasModel { attacker_budget: Some(1), ..Default::default() };
+        let (balance, _, gas) = play(&honest[..2], broke, Box::new(NoChecks), 1000);
+        assert_eq!((balance, gas.attacker), (U256::from(1000), 1));
+
+        // the first deposit is banned, the defender cannot afford to check the second one
+        let ban = |_: &CallInputs| CheckResult { pass: false, gas: 100, memory: 0 };
+        let budget = GasModel { check: 50, defender_budget: Some(200), ..Default::default() };
+        let (balance, _, gas) = play(&[deposit.clone(), deposit.clone()], budget, Box::new(ban), 1000);
+        assert_eq!((balance, gas.defender), (U256::from(1000), 150));
+        // out of gas, the defender rejects the second deposit it would have let through
+        let allow = |_: &CallInputs| CheckResult { pass: true, gas: 100, memory: 0 };
+        let (balance, _, gas) = play(&[deposit.clone(), deposit], budget, Box::new(allow), 1000);
+        assert_eq!((balance, gas.defender), (U256::from(500), 150));
+    }
 }
\ No newline at end of file
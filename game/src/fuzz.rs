@@ -0,0 +1,499 @@
+//! Coverage-guided fuzzer for the attacker, a baseline that does not learn.
+//!
+//! An input is a sequence of transactions generated from the ABI of the defender. Every
+//! transaction carries the answers of the attacker to the calls it gets while the transaction
+//! runs, calling back into the defender or returning, and whether those calls return or revert,
+//! so reentrancy is fuzzed like calldata. Inputs that take new edges of the control flow of the
+//! defender are kept in a corpus and mutated into new inputs.
+//!
+//! Edges are read from the step events of the game, which must be created with
+//! `env.cfg.trace_events` set, without it only new inputs are generated. The defender runs its
+//! [`DefenderChecks`] and the attacker wins by profit or, given [`Invariants`], by breaking one.
+
+use crate::env::{DefenderChecks, GameEnvironment, StuckState};
+use crate::invariant::Invariants;
+use crate::mcts::{utility, Action, Call};
+use ethers::abi::{Function, ParamType, StateMutability, Token};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use revm::interpreter::opcode;
+use revm::primitives::{Bytes, B160, U256};
+use revm::StepEvent;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Debug, Clone)]
+pub struct FuzzConfig {
+    /// Inputs run.
+    pub iterations: usize,
+    /// Transactions of an input.
+    pub max_transactions: usize,
+    /// Decisions of the attacker in a game, answers past it return.
+    pub max_decisions: usize,
+    pub gas_limit: u64,
+    /// Probability of generating a new input instead of mutating one of the corpus.
+    pub generate_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for FuzzConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            max_transactions: 4,
+            max_decisions: 16,
+            gas_limit: 1_000_000,
+            generate_rate: 0.2,
+            seed: 0,
+        }
+    }
+}
+
+/// A transaction of the attacker and its answers, in the order the calls come.
+///
+/// Answers are [`Action::Backcall`] or [`Action::Return`] to a call into the attacker and
+/// [`Action::Pass`] when it returns. An answer that does not fit the call is replaced by
+/// returning and letting the call return, missing answers too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub call: Call,
+    pub answers: Vec<Action>,
+}
+
+/// A jump of the control flow: code address, from and to.
+pub type Edge = (B160, usize, usize);
+
+/// An input that was run.
+#[derive(Debug, Clone)]
+pub struct Seed {
+    pub input: Vec<Transaction>,
+    /// Actions the attacker played, ending with `Stop`. They replay the game, e.g. with
+    /// [`Episode::record`](crate::episode::Episode::record), and seed learned attackers.
+    pub actions: Vec<Action>,
+    /// Profit of the attacker net of the price of its gas, relative to its starting balance,
+    /// plus the reward of the invariants if it broke one.
+    pub utility: f64,
+    pub final_balance: U256,
+    /// Name of the invariant the input breaks, which ends it.
+    pub broken: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzResult {
+    /// Input with the highest utility.
+    pub best: Seed,
+    /// Inputs that took new edges, in the order they were found.
+    pub corpus: Vec<Seed>,
+    /// Distinct edges taken.
+    pub edges: usize,
+}
+
+pub struct Fuzzer<D> {
+    pub config: FuzzConfig,
+    checks: D,
+    invariants: Option<Invariants>,
+    rng: StdRng,
+    coverage: BTreeSet<Edge>,
+    corpus: Vec<Seed>,
+    functions: Vec<Function>,
+    /// Addresses passed as arguments.
+    accounts: Vec<B160>,
+    /// Values sent with transactions stay below the starting balance of the attacker.
+    max_value: U256,
+}
+
+impl<D: DefenderChecks> Fuzzer<D> {
+    pub fn new(config: FuzzConfig, checks: D) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            config,
+            checks,
+            invariants: None,
+            rng,
+            coverage: BTreeSet::new(),
+            corpus: vec![],
+            functions: vec![],
+            accounts: vec![],
+            max_value: U256::ZERO,
+        }
+    }
+
+    /// Ends inputs on a broken invariant, at any turn, which wins the attacker the reward of `invariants`.
+    pub fn with_invariants(mut self, invariants: Invariants) -> Self {
+        self.invariants = Some(invariants);
+        self
+    }
+
+    /// Fuzzes from the current position of `game`, waiting for a transaction of the attacker,
+    /// which is left unchanged. The corpus and the coverage carry over from earlier runs.
+    pub fn run(&mut self, game: &mut GameEnvironment) -> FuzzResult {
+        let outer = game.set_invariants(self.invariants.clone());
+        let root = game.snapshot();
+        game.take_events();
+        let attacker = game.attacker_account;
+        let start = game.attacker_net_balance();
+        self.functions = game.abi.abi().functions().cloned().collect();
+        self.functions.sort_by_key(|function| function.signature());
+        self.accounts = vec![attacker, game.defender_account, B160::zero()];
+        self.max_value = start;
+        let mut best: Option<Seed> = None;
+        for _ in 0..self.config.iterations {
+            let input = if self.corpus.is_empty() || self.rng.gen_bool(self.config.generate_rate) {
+                self.generate()
+            } else {
+                self.mutate()
+            };
+            game.restore(&root);
+            let (seed, edges) = self.execute(game, input, start);
+            let before = self.coverage.len();
+            self.coverage.extend(edges);
+            if !matches!(&best, Some(best) if best.utility >= seed.utility) {
+                best = Some(seed.clone());
+            }
+            if self.coverage.len() > before {
+                self.corpus.push(seed);
+            }
+        }
+        game.restore(&root);
+        game.take_events();
+        game.set_invariants(outer);
+        let best = best.unwrap_or(Seed {
+            input: vec![],
+            actions: vec![Action::Stop],
+            utility: 0.0,
+            final_balance: start,
+            broken: None,
+        });
+        FuzzResult {
+            best,
+            corpus: self.corpus.clone(),
+            edges: self.coverage.len(),
+        }
+    }
+
+    /// Plays `input` and returns it with the edges it took.
+    fn execute(
+        &mut self,
+        game: &mut GameEnvironment,
+        input: Vec<Transaction>,
+        start: U256,
+    ) -> (Seed, BTreeSet<Edge>) {
+        let mut actions = vec![];
+        let mut edges = BTreeSet::new();
+        let mut decisions = 0;
+        let mut broken = None;
+        for transaction in &input {
+            let call = &transaction.call;
+            game.attacker_move(call.data.clone(), call.value, call.gas_limit);
+            actions.push(Action::Transact(call.clone()));
+            decisions += 1;
+            let mut answers = transaction.answers.iter();
+            while game.broken().is_none() {
+                self.advance(game);
+                let answer = answers.next();
+                let action = match (game.stuck_state(), answer) {
+                    (StuckState::CallAttacker { .. }, Some(Action::Backcall(call)))
+                        if decisions < self.config.max_decisions =>
+                    {
+                        game.attacker_answer(Some((call.data.clone(), call.value, call.gas_limit)));
+                        Action::Backcall(call.clone())
+                    }
+                    (StuckState::CallAttacker { .. }, _) => {
+                        game.attacker_answer(None);
+                        Action::Return
+                    }
+                    (StuckState::PrepareAttackerReturn { .. }, answer) => {
+                        let pass = !matches!(answer, Some(Action::Pass(false)));
+                        game.attacker_pass(pass);
+                        Action::Pass(pass)
+                    }
+                    _ => break,
+                };
+                actions.push(action);
+                decisions += 1;
+            }
+            coverage(&game.take_events(), &mut edges);
+            broken = game.broken().map(str::to_owned);
+            if broken.is_some() || !matches!(game.stuck_state(), StuckState::MoveAttacker) {
+                break;
+            }
+        }
+        actions.push(Action::Stop);
+        let final_balance = game.balance(game.attacker_account);
+        let bonus = match (&self.invariants, &broken) {
+            (Some(invariants), Some(_)) => invariants.reward,
+            _ => 0.0,
+        };
+        let seed = Seed {
+            input,
+            actions,
+            utility: utility(start, game.attacker_net_balance()) + bonus,
+            final_balance,
+            broken,
+        };
+        (seed, edges)
+    }
+
+    /// Plays the moves that are not the attacker's, until an invariant breaks.
+    fn advance(&mut self, game: &mut GameEnvironment) {
+        while game.broken().is_none() {
+            match game.stuck_state() {
+                StuckState::CallDefender { call_inputs, .. } => {
+                    let check = self.checks.check(call_inputs);
+                    let pass = game.charge_check(&check);
+                    game.defender_pass(pass);
+                }
+                StuckState::SomeoneReturn { .. } => game.pop_return(),
+                _ => return,
+            }
+        }
+    }
+
+    fn generate(&mut self) -> Vec<Transaction> {
+        let len = self.rng.gen_range(1..=self.config.max_transactions.max(1));
+        (0..len).map(|_| self.transaction()).collect()
+    }
+
+    fn transaction(&mut self) -> Transaction {
+        let len = self.rng.gen_range(0..=3);
+        Transaction {
+            call: self.call(),
+            answers: (0..len).map(|_| self.answer()).collect(),
+        }
+    }
+
+    fn answer(&mut self) -> Action {
+        match self.rng.gen_range(0..4) {
+            0 => Action::Return,
+            1 => Action::Pass(false),
+            _ => Action::Backcall(self.call()),
+        }
+    }
+
+    /// A call of a function of the ABI, or of the fallback now and then.
+    fn call(&mut self) -> Call {
+        let gas_limit = self.config.gas_limit;
+        if self.functions.is_empty() || self.rng.gen_bool(0.05) {
+            return Call {
+                data: Bytes::new(),
+                value: self.value(),
+                gas_limit,
+            };
+        }
+        let function = self.functions.choose(&mut self.rng).unwrap().clone();
+        let tokens: Vec<_> = function
+            .inputs
+            .iter()
+            .map(|param| self.token(&param.kind))
+            .collect();
+        let data = function.encode_input(&tokens).unwrap_or_default();
+        let value = match function.state_mutability {
+            StateMutability::Payable => self.value(),
+            _ => U256::ZERO,
+        };
+        Call {
+            data: data.into(),
+            value,
+            gas_limit,
+        }
+    }
+
+    fn value(&mut self) -> U256 {
+        match self.rng.gen_range(0..4) {
+            0 => U256::ZERO,
+            1 => self.max_value,
+            _ => self.max_value * U256::from(self.rng.gen_range(1..=100u64)) / U256::from(100),
+        }
+    }
+
+    fn token(&mut self, kind: &ParamType) -> Token {
+        let len = |rng: &mut StdRng| rng.gen_range(0..=3);
+        match kind {
+            ParamType::Address => {
+                let address = self.accounts.choose(&mut self.rng).copied();
+                Token::Address(address.unwrap_or_default().0.into())
+            }
+            ParamType::Bool => Token::Bool(self.rng.gen()),
+            ParamType::Uint(bits) => {
+                let word = self.word() & (U256::MAX >> (256 - (*bits).clamp(8, 256)));
+                Token::Uint(abi_word(word))
+            }
+            ParamType::Int(_) => {
+                let words = [U256::ZERO, U256::from(1), U256::MAX];
+                Token::Int(abi_word(*words.choose(&mut self.rng).unwrap()))
+            }
+            ParamType::Bytes => {
+                let len = self.rng.gen_range(0..=64);
+                Token::Bytes((0..len).map(|_| self.rng.gen()).collect())
+            }
+            ParamType::String => {
+                let len = self.rng.gen_range(0..=16);
+                let string = (0..len).map(|_| self.rng.gen_range('a'..='z')).collect();
+                Token::String(string)
+            }
+            ParamType::FixedBytes(len) => {
+                Token::FixedBytes((0..*len).map(|_| self.rng.gen()).collect())
+            }
+            ParamType::Array(kind) => {
+                let len = len(&mut self.rng);
+                Token::Array((0..len).map(|_| self.token(kind)).collect())
+            }
+            ParamType::FixedArray(kind, len) => {
+                Token::FixedArray((0..*len).map(|_| self.token(kind)).collect())
+            }
+            ParamType::Tuple(kinds) => {
+                Token::Tuple(kinds.iter().map(|kind| self.token(kind)).collect())
+            }
+        }
+    }
+
+    /// Edge cases first, then values of the game and random words.
+    fn word(&mut self) -> U256 {
+        match self.rng.gen_range(0..6) {
+            0 => U256::ZERO,
+            1 => U256::from(1),
+            2 => U256::MAX,
+            3 => self.value(),
+            4 => U256::from(self.rng.gen::<u8>()),
+            _ => U256::from_limbs(self.rng.gen()),
+        }
+    }
+
+    /// Mutation of an input of the corpus.
+    fn mutate(&mut self) -> Vec<Transaction> {
+        let mut input = self.corpus.choose(&mut self.rng).unwrap().input.clone();
+        let max_transactions = self.config.max_transactions.max(1);
+        let index = self.rng.gen_range(0..input.len().max(1));
+        match self.rng.gen_range(0..7) {
+            0 if input.len() < max_transactions => {
+                let transaction = self.transaction();
+                input.insert(index.min(input.len()), transaction);
+            }
+            1 if input.len() > 1 => {
+                input.remove(index);
+            }
+            2 => {
+                // the start of this input and the end of another
+                let other = &self.corpus.choose(&mut self.rng).unwrap().input;
+                let split = self.rng.gen_range(0..=other.len());
+                input.truncate(index);
+                input.extend_from_slice(&other[split..]);
+            }
+            3 => {
+                let call = self.call();
+                let transaction = &mut input[index];
+                match transaction
+                    .answers
+                    .iter_mut()
+                    .find_map(|answer| match answer {
+                        Action::Backcall(call) => Some(call),
+                        _ => None,
+                    }) {
+                    Some(backcall) if self.rng.gen() => *backcall = call,
+                    _ => transaction.answers.insert(0, Action::Backcall(call)),
+                }
+            }
+            4 => {
+                let answer = self.answer();
+                let answers = &mut input[index].answers;
+                let at = self.rng.gen_range(0..=answers.len());
+                if at < answers.len() {
+                    answers[at] = answer;
+                } else {
+                    answers.push(answer);
+                }
+            }
+            5 => input[index].call.value = self.value(),
+            _ => {
+                let word = self.word();
+                let data = &mut input[index].call.data;
+                let words = data.len().saturating_sub(4) / 32;
+                if words > 0 {
+                    let at = 4 + 32 * self.rng.gen_range(0..words);
+                    let mut bytes = data.to_vec();
+                    bytes[at..at + 32].copy_from_slice(&word.to_be_bytes::<32>());
+                    *data = bytes.into();
+                } else {
+                    input[index] = self.transaction();
+                }
+            }
+        }
+        input.truncate(max_transactions);
+        if input.is_empty() {
+            input.push(self.transaction());
+        }
+        input
+    }
+}
+
+fn abi_word(word: U256) -> ethers::types::U256 {
+    ethers::types::U256::from_big_endian(&word.to_be_bytes::<32>())
+}
+
+/// Jumps taken in `trace`, between steps of the same frame.
+fn coverage(trace: &[StepEvent], edges: &mut BTreeSet<Edge>) {
+    let mut last: HashMap<u64, &StepEvent> = HashMap::new();
+    for step in trace {
+        if let Some(previous) = last.get(&step.depth) {
+            let jump = matches!(previous.opcode, opcode::JUMP | opcode::JUMPI);
+            if jump && previous.address == step.address && !step.resumed {
+                edges.insert((step.address, previous.pc, step.pc));
+            }
+        }
+        last.insert(step.depth, step);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::corpus::{benchmark, ATTACKER};
+    use crate::env::NoChecks;
+    use crate::episode::Episode;
+    use revm::primitives::Env;
+    use revm::InMemoryDB;
+
+    #[test]
+    fn fuzzes_reentrancy_out_of_silly_bank() {
+        let bank = benchmark("reentrancy").unwrap();
+        let fuzz = |contract: &crate::corpus::Contract| {
+            let mut env = Env::default();
+            env.cfg.trace_events = true;
+            let mut db = InMemoryDB::default();
+            let mut game = contract.game(&mut env, &mut db, ATTACKER, bank.attacker_balance);
+            let fingerprint = game.fingerprint();
+            let config = FuzzConfig {
+                iterations: 500,
+                max_transactions: 3,
+                ..Default::default()
+            };
+            let result = Fuzzer::new(config, NoChecks).run(&mut game);
+            assert_eq!(game.fingerprint(), fingerprint);
+            result
+        };
+
+        let result = fuzz(&bank.vulnerable);
+        assert!(result.best.utility > 0.0, "{:#?}", result.best);
+        assert!(result
+            .best
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::Backcall(_))));
+        assert!(!result.corpus.is_empty() && result.edges > 0);
+        let contract = &bank.vulnerable;
+        let episode = Episode::record(
+            contract.deployment_code.clone(),
+            contract.abi.clone(),
+            ATTACKER,
+            bank.attacker_balance,
+            &result.best.actions,
+            NoChecks,
+        )
+        .unwrap();
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        episode.replay(&mut game).unwrap();
+        assert_eq!(game.balance(ATTACKER), result.best.final_balance);
+
+        assert!(fuzz(&bank.fixed).best.utility <= 0.0);
+    }
+}
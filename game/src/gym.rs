@@ -0,0 +1,431 @@
+//! Gym-style protocol over lines of JSON, for learners outside of Rust.
+//!
+//! The learner plays the attacker, the defender lets every call through or plays its
+//! [`DefenderChecks`]. Every request is one line of JSON and gets one line back:
+//!
+//! - `{"method": "spaces"}` describes the action and observation spaces and names the actions,
+//! - `{"method": "reset"}` starts a game: `{"observation": ..., "info": ...}`,
+//! - `{"method": "step", "action": 4}` plays an action:
+//!   `{"observation": ..., "reward": 0.5, "terminated": false, "truncated": false, "info": ...}`,
+//! - `{"method": "close"}` ends the session.
+//!
+//! A game is terminated when the attacker stops or breaks an invariant of the config, and
+//! truncated when it runs out of transactions or of its gas budget.
+//!
+//! Failed requests get `{"error": "..."}` and leave the game as it was. The actions are, in
+//! order: a transaction per configured call, stop, a backcall per configured call, return, and
+//! letting the call into the attacker return or revert. `info.action_mask` flags the legal ones.
+
+use crate::env::DefenderChecks;
+use crate::episode::serde_hex;
+use crate::invariant::Invariants;
+use crate::mcts::{Action, Call};
+use crate::testcase::Input;
+use crate::vec_env::{AttackerEnv, Observation, OwnedGame};
+use ethers::abi::Abi;
+use ethers::prelude::BaseContract;
+use revm::interpreter::{return_ok, InstructionResult};
+use revm::primitives::{Bytes, B160, U256};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+/// Game a gym session plays, read from JSON.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GymConfig {
+    #[serde(deserialize_with = "serde_hex::bytes::deserialize")]
+    pub deployment_code: Bytes,
+    pub abi: Abi,
+    #[serde(deserialize_with = "serde_hex::address::deserialize")]
+    pub attacker: B160,
+    #[serde(deserialize_with = "serde_hex::word::deserialize")]
+    pub attacker_balance: U256,
+    /// Calls the attacker picks its transactions and backcalls from.
+    pub calls: Vec<CallSpec>,
+    #[serde(default = "default_max_transactions")]
+    pub max_transactions: usize,
+    /// Decisions of the attacker in a game, backcalls are not legal past it.
+    #[serde(default = "default_max_decisions")]
+    pub max_decisions: usize,
+    /// Invariants of the defender, breaking one wins the game.
+    #[serde(default)]
+    pub invariants: Option<Invariants>,
+}
+
+fn default_max_transactions() -> usize {
+    4
+}
+
+fn default_max_decisions() -> usize {
+    16
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CallSpec {
+    #[serde(flatten)]
+    pub input: Input,
+    #[serde(default, deserialize_with = "serde_hex::word::deserialize")]
+    pub value: U256,
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u64,
+}
+
+fn default_gas_limit() -> u64 {
+    1_000_000
+}
+
+impl GymConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Spaces,
+    Reset,
+    Step { action: usize },
+    Close,
+}
+
+/// Positions the attacker moves in, in the order of the `waiting_for` observation.
+const POSITIONS: [&str; 3] = [
+    "attacker to move",
+    "attacker to answer a call",
+    "attacker to return",
+];
+
+pub struct GymEnv<D> {
+    initial: OwnedGame,
+    checks: D,
+    game: AttackerEnv<D>,
+    calls: Vec<Call>,
+    names: Vec<String>,
+    max_transactions: usize,
+    max_decisions: usize,
+    invariants: Option<Invariants>,
+    decisions: usize,
+    /// A game was started and has not ended.
+    running: bool,
+}
+
+impl<D: DefenderChecks + Clone> GymEnv<D> {
+    pub fn new(config: GymConfig, checks: D) -> Result<Self, String> {
+        let abi: BaseContract = config.abi.into();
+        let mut calls = vec![];
+        let mut names = vec![];
+        for spec in &config.calls {
+            let data = spec.input.encode(&abi)?;
+            names.push(match &spec.input {
+                Input::Call { function, args } => format!("{function}({})", args.join(", ")),
+                Input::Calldata { calldata } => {
+                    format!("0x{}", revm::primitives::hex::encode(calldata))
+                }
+            });
+            calls.push(Call {
+                data,
+                value: spec.value,
+                gas_limit: spec.gas_limit,
+            });
+        }
+        let initial = OwnedGame::new(
+            config.attacker,
+            config.attacker_balance,
+            config.deployment_code,
+            abi,
+        );
+        Ok(Self {
+            game: AttackerEnv::new(&initial, checks.clone()),
+            initial,
+            invariants: config.invariants,
+            checks,
+            calls,
+            names,
+            max_transactions: config.max_transactions,
+            max_decisions: config.max_decisions,
+            decisions: 0,
+            running: false,
+        })
+    }
+
+    pub fn actions(&self) -> Vec<Action> {
+        let transactions = self.calls.iter().cloned().map(Action::Transact);
+        let backcalls = self.calls.iter().cloned().map(Action::Backcall);
+        transactions
+            .chain([Action::Stop])
+            .chain(backcalls)
+            .chain([Action::Return, Action::Pass(true), Action::Pass(false)])
+            .collect()
+    }
+
+    pub fn spaces(&self) -> Value {
+        let mut actions: Vec<String> = self
+            .names
+            .iter()
+            .map(|name| format!("transact {name}"))
+            .collect();
+        actions.push("stop".into());
+        actions.extend(self.names.iter().map(|name| format!("call back {name}")));
+        actions.extend(["return", "pass", "revert"].map(String::from));
+        json!({
+            "action_space": { "type": "Discrete", "n": actions.len() },
+            "observation_space": {
+                "type": "Dict",
+                "spaces": {
+                    "waiting_for": { "type": "Discrete", "n": POSITIONS.len() },
+                    "depth": { "type": "Discrete", "n": 2 * self.max_decisions + 2 },
+                    "transactions": { "type": "Discrete", "n": self.max_transactions + 1 },
+                    "last_result": { "type": "Discrete", "n": 3 },
+                    "profit": { "type": "Box", "low": -1.0, "high": null, "shape": [] },
+                },
+            },
+            "actions": actions,
+        })
+    }
+
+    pub fn reset(&mut self) -> Value {
+        let game = AttackerEnv::new(&self.initial, self.checks.clone());
+        self.game = match &self.invariants {
+            Some(invariants) => game.with_invariants(invariants.clone()),
+            None => game,
+        };
+        self.decisions = 0;
+        self.running = true;
+        let observation = self.game.observe();
+        json!({ "observation": self.observation(&observation), "info": self.info(&observation) })
+    }
+
+    pub fn step(&mut self, index: usize) -> Result<Value, String> {
+        if !self.running {
+            return Err("no game is running, reset first".into());
+        }
+        let before = self.game.observe();
+        if !self
+            .action_mask(&before)
+            .get(index)
+            .copied()
+            .unwrap_or(false)
+        {
+            return Err(format!(
+                "action {index} is not legal while waiting for {}",
+                before.waiting_for
+            ));
+        }
+        let action = self.actions().swap_remove(index);
+        let (observation, reward, done) = self.game.step(&action, self.max_transactions)?;
+        self.decisions += 1;
+        let terminated = action == Action::Stop || self.game.broken().is_some();
+        let truncated = !terminated && self.game.truncated(&observation, self.max_transactions);
+        self.running = !done;
+        Ok(json!({
+            "observation": self.observation(&observation),
+            "reward": reward,
+            "terminated": terminated,
+            "truncated": truncated,
+            "info": self.info(&observation),
+        }))
+    }
+
+    fn action_mask(&self, observation: &Observation) -> Vec<bool> {
+        let calls = self.calls.len();
+        let mut mask = vec![false; 2 * calls + 4];
+        match POSITIONS
+            .iter()
+            .position(|position| *position == observation.waiting_for)
+        {
+            Some(0) => {
+                if observation.transactions < self.max_transactions {
+                    mask[..calls].fill(true);
+                }
+                mask[calls] = true;
+            }
+            Some(1) => {
+                if self.decisions < self.max_decisions {
+                    mask[calls + 1..2 * calls + 1].fill(true);
+                }
+                mask[2 * calls + 1] = true;
+            }
+            Some(2) => mask[2 * calls + 2..].fill(true),
+            _ => {}
+        }
+        mask
+    }
+
+    fn observation(&self, observation: &Observation) -> Value {
+        let waiting_for = POSITIONS
+            .iter()
+            .position(|position| *position == observation.waiting_for)
+            .unwrap_or(0);
+        let last_result = match observation.last_result {
+            None => 0,
+            Some(return_ok!()) => 1,
+            Some(_) => 2,
+        };
+        json!({
+            "waiting_for": waiting_for,
+            "depth": observation.depth,
+            "transactions": observation.transactions,
+            "last_result": last_result,
+            "profit": crate::mcts::utility(self.game.start(), observation.attacker_net_balance),
+        })
+    }
+
+    fn info(&self, observation: &Observation) -> Value {
+        json!({
+            "action_mask": self.action_mask(observation),
+            "attacker_balance": observation.attacker_balance.to_string(),
+            "defender_balance": observation.defender_balance.to_string(),
+            "attacker_gas": observation.gas_used.attacker,
+            "defender_gas": observation.gas_used.defender,
+            "fingerprint": format!("{:?}", observation.fingerprint.0),
+        })
+    }
+}
+
+/// Answers the requests read from `input` until `close` or the end of the input.
+pub fn serve<D: DefenderChecks + Clone>(
+    env: &mut GymEnv<D>,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Err(e) => Err(e.to_string()),
+            Ok(Request::Spaces) => Ok(env.spaces()),
+            Ok(Request::Reset) => Ok(env.reset()),
+            Ok(Request::Step { action }) => env.step(action),
+            Ok(Request::Close) => break,
+        };
+        let response = response.unwrap_or_else(|error| json!({ "error": error }));
+        writeln!(output, "{response}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, NoChecks, SILLY_BANK};
+
+    #[test]
+    fn plays_reentrancy_over_json_lines() {
+        let config = json!({
+            "deployment_code": format!("0x{}", revm::primitives::hex::encode(SILLY_BANK)),
+            "abi": silly_bank_abi().abi(),
+            "attacker": "0x00000000000000000000000000000000000a77ac",
+            "attacker_balance": 1000,
+            "calls": [{ "function": "deposit", "value": 500 }, { "calldata": "0x3ccfd60b" }],
+            "max_transactions": 2,
+        });
+        let config: GymConfig = serde_json::from_value(config).unwrap();
+        let mut env = GymEnv::new(config, NoChecks).unwrap();
+        let mut requests = vec![
+            json!({ "method": "spaces" }),
+            json!({ "method": "step", "action": 0 }),
+            json!({ "method": "reset" }),
+            json!({ "method": "step", "action": 6 }),
+        ];
+        // deposit, withdraw, reenter withdraw, return, let both calls into the attacker return
+        for action in [0, 1, 4, 5, 6, 6] {
+            requests.push(json!({ "method": "step", "action": action }));
+        }
+        requests.extend([json!({ "method": "close" }), json!({ "method": "reset" })]);
+        let requests: Vec<String> = requests.iter().map(Value::to_string).collect();
+        let requests = requests.join("\n");
+        let mut output = vec![];
+        serve(&mut env, requests.as_bytes(), &mut output).unwrap();
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 10, "{responses:#?}");
+
+        assert_eq!(responses[0]["action_space"]["n"], 8);
+        assert_eq!(responses[0]["actions"][4], "call back 0x3ccfd60b");
+        assert!(responses[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("reset first"));
+        assert_eq!(
+            responses[2]["info"]["action_mask"],
+            json!([true, true, true, false, false, false, false, false])
+        );
+        assert!(responses[3]["error"].is_string());
+
+        let steps = &responses[4..];
+        let reward: f64 = steps
+            .iter()
+            .map(|step| step["reward"].as_f64().unwrap())
+            .sum();
+        assert_eq!(reward, 0.5);
+        assert_eq!(steps[2]["observation"]["depth"], 3);
+        assert_eq!(
+            steps[3]["info"]["action_mask"],
+            json!([false, false, false, false, false, false, true, true])
+        );
+        assert_eq!(steps[5]["truncated"], true);
+        assert_eq!(steps[5]["observation"]["profit"], 0.5);
+        assert_eq!(steps[5]["info"]["attacker_balance"], "1500");
+    }
+
+    #[test]
+    fn breaking_an_invariant_terminates() {
+        let attacker = "0x00000000000000000000000000000000000a77ac";
+        let mut config = json!({
+            "deployment_code": format!("0x{}", revm::primitives::hex::encode(SILLY_BANK)),
+            "abi": silly_bank_abi().abi(),
+            "attacker": attacker,
+            "attacker_balance": 1000,
+            "calls": [{ "function": "deposit", "value": 500 }, { "calldata": "0x3ccfd60b" }],
+            "max_transactions": 2,
+        });
+        let play = |config: &Value| {
+            let config: GymConfig = serde_json::from_value(config.clone()).unwrap();
+            let mut env = GymEnv::new(config, NoChecks).unwrap();
+            env.reset();
+            let mut last = Value::Null;
+            for action in [0, 1, 4, 5, 6, 6] {
+                last = env.step(action).unwrap();
+                if last["terminated"] == true || last["truncated"] == true {
+                    break;
+                }
+            }
+            last
+        };
+        let last = play(&config);
+        assert_eq!(
+            (&last["terminated"], &last["truncated"]),
+            (&json!(false), &json!(true))
+        );
+
+        // the attacker gets no more out of the bank than it deposited
+        config["invariants"] = json!({
+            "invariants": [{
+                "name": "no profit",
+                "le": [
+                    { "balance": "attacker" },
+                    { "add": [
+                        { "mapping": { "slot": 0, "keys": ["attacker"] } },
+                        "1000"
+                    ] }
+                ]
+            }]
+        });
+        let last = play(&config);
+        assert_eq!(
+            (&last["terminated"], &last["truncated"]),
+            (&json!(true), &json!(false))
+        );
+        // the profit came with the second call returning, the last step only wins the game
+        assert_eq!(last["reward"], 1.0);
+    }
+}
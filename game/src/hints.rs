@@ -0,0 +1,221 @@
+//! Findings of the static detectors on the defender, as hints for both players.
+//!
+//! The defender only needs to check calls into functions with a finding and the attacker tries
+//! those first. See [`revm::interpreter::analysis::detectors`] for what is detected.
+
+use crate::env::{CheckResult, DefenderChecks, GameEnvironment};
+use crate::mcts::{Action, Prior};
+use revm::interpreter::analysis::cfg::{without_metadata, Cfg};
+use revm::interpreter::analysis::detectors::{detect_in_cfg, Finding, Severity};
+use revm::interpreter::CallInputs;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone, Default)]
+pub struct Hints {
+    pub findings: Vec<Finding>,
+    /// Selectors of the dispatcher, calls with other data run the fallback.
+    pub selectors: BTreeSet<[u8; 4]>,
+}
+
+impl Hints {
+    pub fn of_runtime(code: &[u8]) -> Self {
+        let cfg = Cfg::from_slice(without_metadata(code));
+        Self {
+            findings: detect_in_cfg(&cfg),
+            selectors: cfg
+                .function_entries()
+                .iter()
+                .map(|entry| entry.selector)
+                .collect(),
+        }
+    }
+
+    /// Hints on the defender of `game`.
+    pub fn of_game(game: &mut GameEnvironment) -> Self {
+        let defender = game.defender_account;
+        Self::of_runtime(&game.code(defender))
+    }
+
+    /// Function of the dispatcher a call with `data` runs, `None` for the fallback.
+    pub fn function(&self, data: &[u8]) -> Option<[u8; 4]> {
+        let selector: [u8; 4] = data.get(..4)?.try_into().ok()?;
+        self.selectors.contains(&selector).then_some(selector)
+    }
+
+    /// Highest severity of the findings in every function with one.
+    pub fn severities(&self) -> BTreeMap<Option<[u8; 4]>, Severity> {
+        let mut severities = BTreeMap::new();
+        for finding in &self.findings {
+            let severity = severities
+                .entry(finding.selector)
+                .or_insert(finding.severity);
+            *severity = finding.severity.max(*severity);
+        }
+        severities
+    }
+
+    /// Checks that only run on calls into functions with a finding of at least `min` severity,
+    /// every other call passes for free.
+    pub fn guard<D: DefenderChecks>(&self, checks: D, min: Severity) -> Guarded<D> {
+        let functions = self
+            .severities()
+            .into_iter()
+            .filter(|(_, severity)| *severity >= min)
+            .map(|(function, _)| function)
+            .collect();
+        Guarded {
+            checks,
+            hints: self.clone(),
+            functions,
+        }
+    }
+
+    /// Prior of the search that weighs transactions and backcalls by the severity of the
+    /// findings in the function they call, twice as much per level.
+    pub fn prior<'p>(&self) -> Prior<'p> {
+        let hints = self.clone();
+        let severities = self.severities();
+        Prior::Policy(Box::new(move |_, actions: &[Action]| {
+            actions
+                .iter()
+                .map(|action| match action {
+                    Action::Transact(call) | Action::Backcall(call) => {
+                        let function = hints.function(&call.data);
+                        severities
+                            .get(&function)
+                            .map_or(1.0, |severity| f64::from(2 << *severity as u8))
+                    }
+                    _ => 1.0,
+                })
+                .collect()
+        }))
+    }
+}
+
+/// Defender checks placed on the functions with findings, see [`Hints::guard`].
+#[derive(Debug, Clone)]
+pub struct Guarded<D> {
+    pub checks: D,
+    hints: Hints,
+    functions: BTreeSet<Option<[u8; 4]>>,
+}
+
+impl<D: DefenderChecks> DefenderChecks for Guarded<D> {
+    fn check(&mut self, call_inputs: &CallInputs) -> CheckResult {
+        if self
+            .functions
+            .contains(&self.hints.function(&call_inputs.input))
+        {
+            self.checks.check(call_inputs)
+        } else {
+            CheckResult {
+                pass: true,
+                gas: 0,
+                memory: 0,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::corpus::{benchmark, benchmarks, VulnerabilityClass, ATTACKER};
+    use crate::episode::Episode;
+    use crate::mcts::Call;
+    use revm::interpreter::analysis::detectors::Detector;
+    use revm::primitives::{Bytes, Env, U256};
+    use revm::InMemoryDB;
+
+    fn hints(contract: &crate::corpus::Contract) -> Hints {
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = contract.game(&mut env, &mut db, ATTACKER, U256::ZERO);
+        Hints::of_game(&mut game)
+    }
+
+    #[test]
+    fn hints_on_the_corpus() {
+        for benchmark in benchmarks() {
+            let detects = |detector: Detector| match benchmark.class {
+                VulnerabilityClass::Reentrancy => {
+                    matches!(detector, Detector::StateWriteAfterCall { .. })
+                }
+                VulnerabilityClass::TxOrigin => detector == Detector::OriginAuth,
+                VulnerabilityClass::Delegatecall => detector == Detector::DelegatecallToCalldata,
+                VulnerabilityClass::Selfdestruct => detector == Detector::UnguardedSelfdestruct,
+                _ => false,
+            };
+            let detected = |hints: Hints| {
+                hints
+                    .findings
+                    .iter()
+                    .any(|finding| detects(finding.detector))
+            };
+            // the other classes have no detector of their own
+            let expected = matches!(
+                benchmark.class,
+                VulnerabilityClass::Reentrancy
+                    | VulnerabilityClass::TxOrigin
+                    | VulnerabilityClass::Delegatecall
+                    | VulnerabilityClass::Selfdestruct
+            );
+            assert_eq!(
+                detected(hints(&benchmark.vulnerable)),
+                expected,
+                "{}",
+                benchmark.id
+            );
+            assert!(!detected(hints(&benchmark.fixed)), "{} fixed", benchmark.id);
+        }
+
+        // checks only see withdraw, the exploit tries it first
+        let bank = benchmark("reentrancy").unwrap();
+        let hints = hints(&bank.vulnerable);
+        let withdraw = [0x3c, 0xcf, 0xd6, 0x0b];
+        assert_eq!(
+            hints.severities(),
+            [(Some(withdraw), Severity::High)].into()
+        );
+        let mut checked = vec![];
+        let checks = |call_inputs: &CallInputs| {
+            checked.push(call_inputs.input.clone());
+            CheckResult {
+                pass: true,
+                gas: 0,
+                memory: 0,
+            }
+        };
+        let checks = hints.guard(checks, Severity::Medium);
+        let contract = &bank.vulnerable;
+        let abi = contract.abi.clone();
+        Episode::record(
+            contract.deployment_code.clone(),
+            abi,
+            ATTACKER,
+            bank.attacker_balance,
+            &bank.exploit,
+            checks,
+        )
+        .unwrap();
+        assert_eq!(checked, vec![Bytes::from(withdraw.to_vec()); 2]);
+
+        let Prior::Policy(mut prior) = hints.prior() else {
+            panic!("hints give a policy");
+        };
+        let call = |data: &[u8]| Call {
+            data: Bytes::from(data.to_vec()),
+            value: U256::ZERO,
+            gas_limit: 1_000_000,
+        };
+        let actions = [
+            Action::Transact(call(&[0xd0, 0xe3, 0x0d, 0xb0])),
+            Action::Transact(call(&withdraw)),
+            Action::Stop,
+        ];
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let game = contract.game(&mut env, &mut db, ATTACKER, U256::ZERO);
+        assert_eq!(prior(&game, &actions), vec![1.0, 8.0, 1.0]);
+    }
+}
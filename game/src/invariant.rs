@@ -0,0 +1,418 @@
+//! Invariants of the defender, written by the team defending it, whose violation wins the game
+//! for the attacker.
+//!
+//! Invariants are checked by the game, see [`GameEnvironment::set_invariants`], after every move
+//! and every return, halfway through transactions as well: an invariant has to hold whenever
+//! the defender calls the attacker or a call returns. An invariant compares expressions over the
+//! state of the game: words, accounts, balances, storage slots of the defender, entries of its mappings and the
+//! first word returned by a view of the defender, called in a static sub-execution that leaves
+//! the game as it was. They are written in JSON:
+//!
+//! ```json
+//! {
+//!     "reward": 1.0,
+//!     "invariants": [
+//!         {
+//!             "name": "solvent",
+//!             "ge": [
+//!                 { "balance": "defender" },
+//!                 { "mapping": { "slot": 0, "keys": ["attacker"] } }
+//!             ]
+//!         },
+//!         {
+//!             "name": "balances is the mapping",
+//!             "eq": [
+//!                 { "call": { "function": "balances", "args": ["0x00000000000000000000000000000000000a77ac"] } },
+//!                 { "mapping": { "slot": 0, "keys": ["0x00000000000000000000000000000000000a77ac"] } }
+//!             ]
+//!         }
+//!     ]
+//! }
+//! ```
+//!
+//! Accounts are `"attacker"`, `"defender"` or an address, and read as the word of the address
+//! where a word is expected. Words are numbers or decimal or `0x` hex strings.
+
+use crate::env::GameEnvironment;
+use crate::episode::serde_hex;
+use crate::testcase::Input;
+use revm::interpreter::{return_ok, InstructionResult};
+use revm::primitives::{keccak256, B160, U256};
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+
+/// Gas of a view call.
+pub const VIEW_GAS: u64 = 10_000_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Invariants {
+    pub invariants: Vec<Invariant>,
+    /// Reward of the attacker for breaking an invariant, on top of its profit.
+    #[serde(default = "default_reward")]
+    pub reward: f64,
+}
+
+fn default_reward() -> f64 {
+    1.0
+}
+
+impl Invariants {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// First invariant `game` violates. An invariant that fails to evaluate, e.g. because its
+    /// view reverts or calls out and gets stuck, is violated.
+    pub fn violated(&self, game: &mut GameEnvironment) -> Option<&Invariant> {
+        self.invariants
+            .iter()
+            .find(|invariant| invariant.holds(game) != Ok(true))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Invariant {
+    pub name: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+}
+
+impl Invariant {
+    pub fn holds(&self, game: &mut GameEnvironment) -> Result<bool, String> {
+        self.condition
+            .holds(game)
+            .map_err(|e| format!("{}: {e}", self.name))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    Ge([Expr; 2]),
+    Gt([Expr; 2]),
+    Le([Expr; 2]),
+    Lt([Expr; 2]),
+    Eq([Expr; 2]),
+    Ne([Expr; 2]),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn holds(&self, game: &mut GameEnvironment) -> Result<bool, String> {
+        let compare = |[a, b]: &[Expr; 2], game: &mut GameEnvironment| {
+            Ok::<_, String>(a.eval(game)?.cmp(&b.eval(game)?))
+        };
+        Ok(match self {
+            Self::Ge(pair) => compare(pair, game)?.is_ge(),
+            Self::Gt(pair) => compare(pair, game)?.is_gt(),
+            Self::Le(pair) => compare(pair, game)?.is_le(),
+            Self::Lt(pair) => compare(pair, game)?.is_lt(),
+            Self::Eq(pair) => compare(pair, game)?.is_eq(),
+            Self::Ne(pair) => compare(pair, game)?.is_ne(),
+            Self::All(conditions) => {
+                for condition in conditions {
+                    if !condition.holds(game)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+            Self::Any(conditions) => {
+                for condition in conditions {
+                    if condition.holds(game)? {
+                        return Ok(true);
+                    }
+                }
+                false
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Expr {
+    Word(#[serde(with = "serde_hex::word")] U256),
+    Account(Account),
+    Op(Box<Op>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Balance(Account),
+    /// Slot of the defender.
+    Storage(Expr),
+    /// Entry of a mapping of the defender at `slot`, one key per level of nested mappings.
+    Mapping {
+        slot: Expr,
+        keys: Vec<Expr>,
+    },
+    /// First word a view of the defender returns.
+    Call(ViewCall),
+    Add([Expr; 2]),
+    Sub([Expr; 2]),
+    Mul([Expr; 2]),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ViewCall {
+    #[serde(flatten)]
+    pub input: Input,
+    /// The attacker if not given.
+    #[serde(default)]
+    pub caller: Option<Account>,
+}
+
+impl Expr {
+    pub fn eval(&self, game: &mut GameEnvironment) -> Result<U256, String> {
+        match self {
+            Self::Word(word) => Ok(*word),
+            Self::Account(account) => Ok(word(account.address(game))),
+            Self::Op(op) => op.eval(game),
+        }
+    }
+}
+
+impl Op {
+    pub fn eval(&self, game: &mut GameEnvironment) -> Result<U256, String> {
+        let defender = game.defender_account;
+        let arithmetic =
+            |[a, b]: &[Expr; 2], game: &mut GameEnvironment, op: fn(U256, U256) -> Option<U256>| {
+                let (a, b) = (a.eval(game)?, b.eval(game)?);
+                op(a, b).ok_or_else(|| format!("{a} and {b} overflow"))
+            };
+        match self {
+            Self::Balance(account) => {
+                let address = account.address(game);
+                Ok(game.balance(address))
+            }
+            Self::Storage(slot) => {
+                let slot = slot.eval(game)?;
+                Ok(game.storage(defender, slot))
+            }
+            Self::Mapping { slot, keys } => {
+                let mut slot = slot.eval(game)?;
+                for key in keys {
+                    let mut preimage = [0; 64];
+                    preimage[..32].copy_from_slice(&key.eval(game)?.to_be_bytes::<32>());
+                    preimage[32..].copy_from_slice(&slot.to_be_bytes::<32>());
+                    slot = keccak256(&preimage).into();
+                }
+                Ok(game.storage(defender, slot))
+            }
+            Self::Call(call) => {
+                let data = call.input.encode(&game.abi)?;
+                let caller = call
+                    .caller
+                    .as_ref()
+                    .map_or(game.attacker_account, |caller| caller.address(game));
+                let result = game.static_call(caller, data, VIEW_GAS);
+                if !matches!(result.result, return_ok!()) {
+                    return Err(format!("the view ends with {:?}", result.result));
+                }
+                let output = result
+                    .return_value
+                    .get(..32)
+                    .ok_or("the view returns no word")?;
+                Ok(U256::from_be_bytes::<32>(output.try_into().unwrap()))
+            }
+            Self::Add(pair) => arithmetic(pair, game, U256::checked_add),
+            Self::Sub(pair) => arithmetic(pair, game, U256::checked_sub),
+            Self::Mul(pair) => arithmetic(pair, game, U256::checked_mul),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Account {
+    Attacker,
+    Defender,
+    Address(B160),
+}
+
+impl Account {
+    pub fn address(&self, game: &GameEnvironment) -> B160 {
+        match self {
+            Self::Attacker => game.attacker_account,
+            Self::Defender => game.defender_account,
+            Self::Address(address) => *address,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Account {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match String::deserialize(deserializer)?.as_str() {
+            "attacker" => Ok(Self::Attacker),
+            "defender" => Ok(Self::Defender),
+            address => address
+                .parse()
+                .map(Self::Address)
+                .map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// Word of an address, padded on the left.
+fn word(address: B160) -> U256 {
+    let mut word = [0; 32];
+    word[12..].copy_from_slice(&address.0);
+    U256::from_be_bytes(word)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::corpus::{benchmark, ATTACKER};
+    use crate::env::NoChecks;
+    use crate::mcts::{Action, Call, Mcts, MctsConfig, Prior};
+    use crate::vec_env::AttackerEnv;
+    use revm::primitives::Bytes;
+
+    #[test]
+    fn breaking_an_invariant_ends_the_game() {
+        let bank = benchmark("reentrancy").unwrap();
+        // the attacker gets no more out of the bank than it deposited
+        let json = format!(
+            r#"{{
+                "reward": 2.0,
+                "invariants": [
+                    {{
+                        "name": "view",
+                        "eq": [
+                            {{ "call": {{ "function": "balances", "args": ["{ATTACKER:?}"] }} }},
+                            {{ "mapping": {{ "slot": 0, "keys": ["attacker"] }} }}
+                        ]
+                    }},
+                    {{
+                        "name": "no profit",
+                        "le": [
+                            {{ "balance": "attacker" }},
+                            {{ "add": [
+                                {{ "mapping": {{ "slot": "0x0", "keys": ["{ATTACKER:?}"] }} }},
+                                "{}"
+                            ] }}
+                        ]
+                    }}
+                ]
+            }}"#,
+            bank.attacker_balance
+        );
+        let invariants: Invariants = serde_json::from_str(&json).unwrap();
+        let play = |contract: &crate::corpus::Contract, actions: &[Action]| {
+            let game = contract.owned_game(ATTACKER, bank.attacker_balance);
+            let mut env = AttackerEnv::new(&game, NoChecks).with_invariants(invariants.clone());
+            let mut reward = 0.0;
+            for action in actions {
+                let (_, step, done) = env.step(action, 4).unwrap();
+                reward += step;
+                if done {
+                    break;
+                }
+            }
+            (env.broken().map(str::to_owned), reward)
+        };
+
+        // the reentrant withdraw pays out the deposit again, which breaks the invariant as it
+        // returns, in the middle of the transaction
+        assert_eq!(
+            play(&bank.vulnerable, &bank.exploit),
+            (Some("no profit".into()), 0.5 + 2.0)
+        );
+        let honest: Vec<_> = bank.exploit[..2]
+            .iter()
+            .cloned()
+            .chain([Action::Return, Action::Pass(true), Action::Stop])
+            .collect();
+        assert_eq!(play(&bank.vulnerable, &honest), (None, 0.0));
+        assert_eq!(play(&bank.fixed, &honest), (None, 0.0));
+
+        let call = |data: &[u8], value: u64| Call {
+            data: Bytes::from(data.to_vec()),
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let calls = vec![
+            call(&[0xd0, 0xe3, 0x0d, 0xb0], 500),
+            call(&[0x3c, 0xcf, 0xd6, 0x0b], 0),
+        ];
+        let config = MctsConfig {
+            iterations: 400,
+            max_transactions: 2,
+            max_decisions: 8,
+            ..Default::default()
+        };
+        let mut mcts =
+            Mcts::new(config, calls, NoChecks, Prior::Uniform).with_invariants(invariants.clone());
+        let mut env = revm::primitives::Env::default();
+        let mut db = revm::InMemoryDB::default();
+        let mut game = bank
+            .vulnerable
+            .game(&mut env, &mut db, ATTACKER, bank.attacker_balance);
+        // the view runs
+        assert_eq!(invariants.invariants[0].holds(&mut game), Ok(true));
+        let result = mcts.search(&mut game);
+        assert_eq!(result.broken.as_deref(), Some("no profit"), "{result:#?}");
+        assert!(result.utility > 2.0);
+    }
+
+    #[test]
+    fn failing_views_violate_invariants() {
+        let bank = benchmark("reentrancy").unwrap();
+        let json = r#"{ "invariants": [{ "name": "withdraw", "eq": [{ "call": { "function": "withdraw" } }, 0] }] }"#;
+        let invariants: Invariants = serde_json::from_str(json).unwrap();
+        let mut env = revm::primitives::Env::default();
+        let mut db = revm::InMemoryDB::default();
+        let mut game = bank
+            .vulnerable
+            .game(&mut env, &mut db, ATTACKER, bank.attacker_balance);
+        let withdraw = Bytes::from_static(&[0x3c, 0xcf, 0xd6, 0x0b]);
+
+        // nothing to withdraw, the view reverts
+        let result = game.static_call(ATTACKER, withdraw.clone(), VIEW_GAS);
+        assert_eq!(result.result, InstructionResult::Revert);
+        assert!(invariants.violated(&mut game).is_some());
+
+        // the game checks the invariants from then on, even before the defender runs
+        game.set_invariants(Some(invariants.clone()));
+        let deposit = Bytes::from_static(&[0xd0, 0xe3, 0x0d, 0xb0]);
+        game.attacker_move(deposit, U256::from(500), 1_000_000);
+        assert_eq!(game.broken(), None);
+        game.defender_pass(true);
+        assert_eq!(game.broken(), Some("withdraw"));
+
+        // a view that calls out gets stuck
+        let json = r#"{ "invariants": [{ "name": "call", "eq": [{ "call": { "calldata": "0x" } }, 0] }] }"#;
+        let invariants: Invariants = serde_json::from_str(json).unwrap();
+        // CALL the caller with no value, then STOP
+        let runtime = "60006000600060006000335af100";
+        let data =
+            revm::primitives::hex::decode(format!("600e80600b6000396000f3{runtime}")).unwrap();
+        let mut env = revm::primitives::Env::default();
+        let mut db = revm::InMemoryDB::default();
+        let abi = ethers::abi::Abi::default().into();
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            ATTACKER,
+            U256::from(1000),
+            data.into(),
+            abi,
+        );
+        let result = game.static_call(ATTACKER, Bytes::new(), VIEW_GAS);
+        assert_eq!(result.result, InstructionResult::Stuck);
+        assert!(invariants.violated(&mut game).is_some());
+        // the game is left as it was
+        assert!(matches!(
+            game.stuck_state(),
+            crate::env::StuckState::MoveAttacker
+        ));
+        assert_eq!(
+            game.static_call(ATTACKER, Bytes::new(), VIEW_GAS).result,
+            InstructionResult::Stuck
+        );
+    }
+}
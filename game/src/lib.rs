@@ -0,0 +1,21 @@
+//! The attacker/defender game over the EVM, and the tools built around it.
+//!
+//! Nothing here needs libtorch but `agents`, built with the `tch` feature.
+
+#[cfg(feature = "tch")]
+pub mod agents;
+pub mod corpus;
+pub mod curriculum;
+pub mod debugger;
+pub mod env;
+pub mod episode;
+pub mod export;
+pub mod fingerprint;
+pub mod fuzz;
+pub mod gym;
+pub mod hints;
+pub mod invariant;
+pub mod mcts;
+pub mod oracle;
+pub mod testcase;
+pub mod vec_env;
@@ -0,0 +1,493 @@
+//! Monte Carlo tree search for the attacker, a baseline to compare learned attackers against.
+//!
+//! Only the attacker decides, the defender runs its [`DefenderChecks`] and returns are popped
+//! as they come. Positions are shared across transpositions, so the search graph is a DAG and
+//! statistics are kept on its edges.
+
+use crate::env::{DefenderChecks, GameEnvironment, StuckState};
+use crate::episode::serde_hex;
+use crate::fingerprint::{FingerprintBuilder, TranspositionTable};
+use crate::invariant::Invariants;
+use crate::oracle::{Flag, Oracle, OracleConfig};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use revm::primitives::{Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+/// A call the attacker can make into the defender.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Call {
+    #[serde(with = "serde_hex::bytes")]
+    pub data: Bytes,
+    #[serde(with = "serde_hex::word")]
+    pub value: U256,
+    pub gas_limit: u64,
+}
+
+/// A decision of the attacker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Start a transaction into the defender.
+    Transact(Call),
+    /// End the game.
+    Stop,
+    /// Answer a call from the defender by calling back into it.
+    Backcall(Call),
+    /// Answer a call from the defender by returning.
+    Return,
+    /// Let the call into the attacker return or revert.
+    Pass(bool),
+}
+
+/// Weights of the legal actions in a position, not necessarily normalized.
+pub type Policy<'p> = Box<dyn FnMut(&GameEnvironment<'_>, &[Action]) -> Vec<f64> + 'p>;
+
+/// Weights of the legal actions, used as selection priors and to sample rollouts.
+pub enum Prior<'p> {
+    Uniform,
+    Policy(Policy<'p>),
+}
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    pub iterations: usize,
+    /// Transactions the attacker may send in a game.
+    pub max_transactions: usize,
+    /// Decisions of the attacker in a game, backcalls are not offered past it.
+    pub max_decisions: usize,
+    /// Weight of the exploration term of PUCT.
+    pub exploration: f64,
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            max_transactions: 4,
+            max_decisions: 16,
+            exploration: 1.4,
+            seed: 0,
+        }
+    }
+}
+
+/// Best attack found by a search.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub actions: Vec<Action>,
+    /// Profit of the attacker net of the price of its gas, relative to its starting balance,
+    /// plus the reward of the invariants if it broke one.
+    pub utility: f64,
+    pub final_balance: U256,
+    /// Name of the invariant the attack breaks, which ends it.
+    pub broken: Option<String>,
+    /// Violations an oracle flags along the attack, given [`Mcts::with_oracle`]. Turns are
+    /// indices of the actions.
+    pub flags: Vec<Flag>,
+    /// Distinct positions in the search graph.
+    pub positions: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Progress {
+    transactions: usize,
+    decisions: usize,
+    stopped: bool,
+    /// An invariant is broken, which stops the game.
+    broken: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    actions: Vec<Action>,
+    priors: Vec<f64>,
+    children: Vec<Option<usize>>,
+    visits: Vec<u32>,
+    values: Vec<f64>,
+}
+
+pub struct Mcts<'p, D> {
+    pub config: MctsConfig,
+    calls: Vec<Call>,
+    checks: D,
+    prior: Prior<'p>,
+    invariants: Option<Invariants>,
+    oracle: Option<OracleConfig>,
+    nodes: Vec<Node>,
+    table: TranspositionTable<usize>,
+    rng: StdRng,
+}
+
+impl<'p, D: DefenderChecks> Mcts<'p, D> {
+    /// Search over games where the attacker picks its transactions and backcalls among `calls`.
+    pub fn new(config: MctsConfig, calls: Vec<Call>, checks: D, prior: Prior<'p>) -> Self {
+        let rng = StdRng::seed_from_u64(config.seed);
+        Self {
+            config,
+            calls,
+            checks,
+            prior,
+            invariants: None,
+            oracle: None,
+            nodes: vec![],
+            table: TranspositionTable::new(),
+            rng,
+        }
+    }
+
+    /// Ends games on a broken invariant, at any turn, which wins the attacker the reward of
+    /// `invariants`.
+    pub fn with_invariants(mut self, invariants: Invariants) -> Self {
+        self.invariants = Some(invariants);
+        self
+    }
+
+    /// Replays the best attack under an oracle, whose flags go into the result. The reentrancy
+    /// flag needs a game created with `env.cfg.trace_events` set.
+    pub fn with_oracle(mut self, config: OracleConfig) -> Self {
+        self.oracle = Some(config);
+        self
+    }
+
+    /// Searches from the current position of `game`, which is left unchanged.
+    pub fn search(&mut self, game: &mut GameEnvironment) -> SearchResult {
+        let initial = game.snapshot();
+        let outer = game.set_invariants(self.invariants.clone());
+        // the search starts from the next turn of the attacker
+        self.advance(game);
+        let root_snapshot = game.snapshot();
+        let start = game.attacker_net_balance();
+        let root = self.node(game, Progress::default());
+        let mut best: Option<SearchResult> = None;
+        for _ in 0..self.config.iterations {
+            game.restore(&root_snapshot);
+            game.take_events();
+            let mut progress = Progress::default();
+            let mut actions = vec![];
+            let mut path = vec![];
+            let mut node = root;
+            while !self.nodes[node].actions.is_empty() {
+                let edge = self.select(node);
+                let action = self.nodes[node].actions[edge].clone();
+                self.apply(game, &action, &mut progress);
+                actions.push(action);
+                path.push((node, edge));
+                match self.nodes[node].children[edge] {
+                    Some(child) => node = child,
+                    None => {
+                        let child = self.node(game, progress);
+                        self.nodes[node].children[edge] = Some(child);
+                        self.rollout(game, &mut progress, &mut actions);
+                        break;
+                    }
+                }
+            }
+            let balance = game.balance(game.attacker_account);
+            let broken = match (&self.invariants, progress.broken) {
+                (Some(invariants), true) => Some(invariants),
+                _ => None,
+            };
+            let utility = utility(start, game.attacker_net_balance())
+                + broken.map_or(0.0, |broken| broken.reward);
+            for (node, edge) in path {
+                self.nodes[node].visits[edge] += 1;
+                self.nodes[node].values[edge] += utility;
+            }
+            if !matches!(&best, Some(best) if best.utility >= utility) {
+                best = Some(SearchResult {
+                    actions,
+                    utility,
+                    final_balance: balance,
+                    broken: broken.and(game.broken()).map(str::to_owned),
+                    flags: vec![],
+                    positions: 0,
+                });
+            }
+        }
+        game.restore(&root_snapshot);
+        let mut best = best.unwrap_or(SearchResult {
+            actions: vec![],
+            utility: 0.0,
+            final_balance: start,
+            broken: None,
+            flags: vec![],
+            positions: 0,
+        });
+        if let Some(config) = self.oracle.clone() {
+            let mut oracle = Oracle::new(config, game);
+            let mut progress = Progress::default();
+            for action in &best.actions {
+                self.apply(game, action, &mut progress);
+                oracle.observe(game);
+            }
+            best.flags = oracle.flags;
+        }
+        best.positions = self.nodes.len();
+        game.restore(&initial);
+        game.set_invariants(outer);
+        best
+    }
+
+    /// Plays the actions of the attacker from the current position of `game`.
+    pub fn replay(&mut self, game: &mut GameEnvironment, actions: &[Action]) {
+        let outer = game.set_invariants(self.invariants.clone());
+        let mut progress = Progress::default();
+        self.advance(game);
+        for action in actions {
+            self.apply(game, action, &mut progress);
+        }
+        game.set_invariants(outer);
+    }
+
+    fn legal_actions(&self, game: &GameEnvironment, progress: Progress) -> Vec<Action> {
+        if progress.stopped {
+            return vec![];
+        }
+        match game.stuck_state() {
+            StuckState::MoveAttacker if progress.transactions < self.config.max_transactions => {
+                let mut actions: Vec<_> =
+                    self.calls.iter().cloned().map(Action::Transact).collect();
+                actions.push(Action::Stop);
+                actions
+            }
+            StuckState::CallAttacker { .. } => {
+                let mut actions = vec![Action::Return];
+                if progress.decisions < self.config.max_decisions {
+                    actions.extend(self.calls.iter().cloned().map(Action::Backcall));
+                }
+                actions
+            }
+            StuckState::PrepareAttackerReturn { .. } => {
+                vec![Action::Pass(true), Action::Pass(false)]
+            }
+            _ => vec![],
+        }
+    }
+
+    fn weights(&mut self, game: &GameEnvironment, actions: &[Action]) -> Vec<f64> {
+        let uniform = vec![1.0 / actions.len() as f64; actions.len()];
+        let Prior::Policy(policy) = &mut self.prior else {
+            return uniform;
+        };
+        let weights = policy(game, actions);
+        let total: f64 = weights
+            .iter()
+            .filter(|weight| weight.is_finite() && **weight > 0.0)
+            .sum();
+        if weights.len() != actions.len() || total <= 0.0 {
+            return uniform;
+        }
+        weights
+            .iter()
+            .map(|weight| {
+                if weight.is_finite() && *weight > 0.0 {
+                    weight / total
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    /// Position of `game`, added to the search graph if new.
+    fn node(&mut self, game: &GameEnvironment, progress: Progress) -> usize {
+        let key = FingerprintBuilder::new()
+            .hash(game.fingerprint().0)
+            .u64(progress.transactions as u64)
+            .u64(progress.decisions as u64)
+            .tag(progress.stopped as u8)
+            .tag(progress.broken as u8)
+            .finish();
+        if let Some(node) = self.table.get(&key) {
+            return *node;
+        }
+        let actions = self.legal_actions(game, progress);
+        let priors = self.weights(game, &actions);
+        let len = actions.len();
+        self.nodes.push(Node {
+            actions,
+            priors,
+            children: vec![None; len],
+            visits: vec![0; len],
+            values: vec![0.0; len],
+        });
+        self.table.insert(key, self.nodes.len() - 1);
+        self.nodes.len() - 1
+    }
+
+    /// Unvisited edges first, by prior, then PUCT.
+    fn select(&self, node: usize) -> usize {
+        let node = &self.nodes[node];
+        let unvisited = (0..node.actions.len())
+            .filter(|edge| node.visits[*edge] == 0)
+            .max_by(|a, b| node.priors[*a].total_cmp(&node.priors[*b]));
+        if let Some(edge) = unvisited {
+            return edge;
+        }
+        let total = node.visits.iter().sum::<u32>() as f64;
+        let score = |edge: usize| {
+            let visits = node.visits[edge] as f64;
+            node.values[edge] / visits
+                + self.config.exploration * node.priors[edge] * total.sqrt() / (1.0 + visits)
+        };
+        (0..node.actions.len())
+            .max_by(|a, b| score(*a).total_cmp(&score(*b)))
+            .unwrap()
+    }
+
+    fn rollout(
+        &mut self,
+        game: &mut GameEnvironment,
+        progress: &mut Progress,
+        actions: &mut Vec<Action>,
+    ) {
+        loop {
+            let legal = self.legal_actions(game, *progress);
+            if legal.is_empty() {
+                return;
+            }
+            let weights = self.weights(game, &legal);
+            let mut sample = self.rng.gen::<f64>();
+            let mut choice = legal.len() - 1;
+            for (index, weight) in weights.iter().enumerate() {
+                if sample < *weight {
+                    choice = index;
+                    break;
+                }
+                sample -= weight;
+            }
+            let action = legal[choice].clone();
+            self.apply(game, &action, progress);
+            actions.push(action);
+        }
+    }
+
+    fn apply(&mut self, game: &mut GameEnvironment, action: &Action, progress: &mut Progress) {
+        progress.decisions += 1;
+        match action {
+            Action::Transact(call) => {
+                game.attacker_move(call.data.clone(), call.value, call.gas_limit);
+                progress.transactions += 1;
+            }
+            Action::Stop => progress.stopped = true,
+            Action::Backcall(call) => {
+                game.attacker_answer(Some((call.data.clone(), call.value, call.gas_limit)))
+            }
+            Action::Return => game.attacker_answer(None),
+            Action::Pass(pass) => game.attacker_pass(*pass),
+        }
+        self.advance(game);
+        if game.broken().is_some() {
+            progress.stopped = true;
+            progress.broken = true;
+        }
+    }
+
+    /// Plays the moves that are not the attacker's, until an invariant breaks.
+    fn advance(&mut self, game: &mut GameEnvironment) {
+        while game.broken().is_none() {
+            match game.stuck_state() {
+                StuckState::CallDefender { call_inputs, .. } => {
+                    let check = self.checks.check(call_inputs);
+                    let pass = game.charge_check(&check);
+                    game.defender_pass(pass);
+                }
+                StuckState::SomeoneReturn { .. } => game.pop_return(),
+                _ => return,
+            }
+        }
+    }
+}
+
+/// Profit of the attacker relative to its starting balance.
+pub(crate) fn utility(start: U256, balance: U256) -> f64 {
+    let to_f64 = |value: U256| {
+        value
+            .as_limbs()
+            .iter()
+            .rev()
+            .fold(0.0, |acc, limb| acc * 18446744073709551616.0 + *limb as f64)
+    };
+    (to_f64(balance) - to_f64(start)) / to_f64(start).max(1.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, NoChecks, SILLY_BANK};
+    use crate::oracle::Violation;
+    use revm::{
+        primitives::{Env, B160},
+        InMemoryDB,
+    };
+
+    #[test]
+    fn finds_reentrancy_in_silly_bank() {
+        let mut env = Env::default();
+        env.cfg.trace_events = true;
+        let mut db = InMemoryDB::default();
+        let abi = silly_bank_abi();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            attacker,
+            U256::from(1000),
+            SILLY_BANK.to_vec().into(),
+            abi.clone(),
+        );
+        let call = |function: &str, value: u64| Call {
+            data: abi.encode(function, ()).unwrap().0,
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let calls = vec![call("deposit", 500), call("withdraw", 0)];
+        let config = MctsConfig {
+            iterations: 400,
+            max_transactions: 2,
+            max_decisions: 8,
+            ..Default::default()
+        };
+        let oracle = OracleConfig {
+            mapping: Some(U256::ZERO),
+            ..Default::default()
+        };
+        let mut mcts = Mcts::new(config, calls, NoChecks, Prior::Uniform).with_oracle(oracle);
+
+        let fingerprint = game.fingerprint();
+        let result = mcts.search(&mut game);
+        assert_eq!(game.fingerprint(), fingerprint);
+        assert!(result.utility > 0.0, "{result:#?}");
+        assert!(result
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::Backcall(_))));
+
+        let flagged = |violation: fn(&Violation) -> bool| {
+            result.flags.iter().any(|flag| violation(&flag.violation))
+        };
+        assert!(flagged(|violation| matches!(
+            violation,
+            Violation::Reentrancy { .. }
+        )));
+        assert!(flagged(|violation| matches!(
+            violation,
+            Violation::Outflow { .. }
+        )));
+
+        mcts.replay(&mut game, &result.actions);
+        assert_eq!(game.balance(attacker), result.final_balance);
+
+        // a search from a turn of the defender leaves it to play as well
+        let deposit = call("deposit", 500);
+        game.attacker_move(deposit.data, deposit.value, deposit.gas_limit);
+        let fingerprint = game.fingerprint();
+        mcts.search(&mut game);
+        assert_eq!(game.fingerprint(), fingerprint);
+        assert!(matches!(
+            game.stuck_state(),
+            StuckState::CallDefender { .. }
+        ));
+    }
+}
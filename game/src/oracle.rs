@@ -0,0 +1,372 @@
+//! Oracle watching the game as it runs and flagging violated properties as they happen.
+//!
+//! It needs `env.cfg.trace_events`: after every move it goes through the instructions the move
+//! ran, taken with [`GameEnvironment::take_events`], and looks at the balances. It flags:
+//!
+//! - a function of the defender writing a slot it read before an external call, during which
+//!   the defender was entered again: the reentrant call saw the old value,
+//! - the defender holding less than it owes, the sum of a `mapping(address => uint256)` such as
+//!   the balances of a bank, beyond its surplus at the start of the game,
+//! - the attacker taking out more ether than it put in.
+//!
+//! Balances are only checked when the game waits for the next transaction of the attacker,
+//! inside a transaction the state can be halfway through an update.
+
+use crate::env::{GameEnvironment, StuckState};
+use crate::episode::Episode;
+use revm::interpreter::opcode;
+use revm::primitives::{keccak256, Env, B160, U256};
+use revm::{InMemoryDB, Operand, StepEvent};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Default)]
+pub struct OracleConfig {
+    /// Slot of the mapping the defender owes the sum of, solvency is not checked without one.
+    pub mapping: Option<U256>,
+    /// Ether the attacker may take out of the game beyond what it put in.
+    pub allowed_outflow: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// `function` wrote `slot` at `pc` after reading it before an external call, during which
+    /// the defender was entered again through `reentered`.
+    Reentrancy {
+        function: Option<[u8; 4]>,
+        reentered: Option<[u8; 4]>,
+        slot: U256,
+        pc: usize,
+    },
+    /// The surplus of the defender over the mapping went down from `surplus` to `balance - owed`.
+    Insolvent {
+        balance: U256,
+        owed: U256,
+        surplus: U256,
+    },
+    /// The attacker ends a transaction with `amount` more than it started the game with.
+    Outflow { amount: U256 },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let function = |selector: &Option<[u8; 4]>| match selector {
+            Some(selector) => format!("0x{}", revm::primitives::hex::encode(selector)),
+            None => "the fallback".into(),
+        };
+        match self {
+            Self::Reentrancy {
+                function: writer,
+                reentered,
+                slot,
+                pc,
+            } => write!(
+                f,
+                "{} wrote slot {slot} at pc {pc} after the defender was reentered through {}",
+                function(writer),
+                function(reentered)
+            ),
+            Self::Insolvent {
+                balance,
+                owed,
+                surplus,
+            } => write!(
+                f,
+                "the defender holds {balance} and owes {owed}, its surplus was {surplus}"
+            ),
+            Self::Outflow { amount } => write!(f, "the attacker took out {amount}"),
+        }
+    }
+}
+
+/// A violation and the move it happened in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flag {
+    /// Index of the move among the ones observed.
+    pub turn: usize,
+    pub violation: Violation,
+}
+
+/// A frame of the defender in the trace.
+#[derive(Debug, Clone)]
+struct Frame {
+    depth: u64,
+    selector: Option<[u8; 4]>,
+    /// Slots read so far.
+    reads: BTreeSet<U256>,
+    /// Slots read before the last external call.
+    reads_before_call: Option<BTreeSet<U256>>,
+    /// Function the defender was entered through during an external call.
+    reentered: Option<Option<[u8; 4]>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Oracle {
+    config: OracleConfig,
+    defender: B160,
+    attacker: B160,
+    start: U256,
+    /// Surplus of the defender over the mapping at the start, saturated at zero.
+    surplus: U256,
+    /// Storage slots of the mapping the defender hashed.
+    entries: BTreeSet<U256>,
+    frames: Vec<Frame>,
+    insolvent: bool,
+    outflow: bool,
+    turn: usize,
+    pub flags: Vec<Flag>,
+}
+
+impl Oracle {
+    /// Starts watching `game` from its current position, steps run before are dropped.
+    pub fn new(config: OracleConfig, game: &mut GameEnvironment) -> Self {
+        game.take_events();
+        let defender = game.defender_account;
+        let attacker = game.attacker_account;
+        let mut this = Self {
+            config,
+            defender,
+            attacker,
+            start: game.balance(attacker),
+            surplus: U256::ZERO,
+            entries: BTreeSet::new(),
+            frames: vec![],
+            insolvent: false,
+            outflow: false,
+            turn: 0,
+            flags: vec![],
+        };
+        this.surplus = game.balance(defender).saturating_sub(this.owed(game));
+        this
+    }
+
+    /// Replays `episode` under an oracle, the turns of the flags are the indices of the moves.
+    pub fn replay(config: OracleConfig, episode: &Episode) -> Result<Vec<Flag>, String> {
+        let mut env = Env::default();
+        env.cfg.trace_events = true;
+        let mut db = InMemoryDB::default();
+        let mut game = episode.game(&mut env, &mut db);
+        let mut oracle = Self::new(config, &mut game);
+        for (index, next) in episode.moves.iter().enumerate() {
+            next.play(&mut game)
+                .map_err(|e| format!("move {index}: {e}"))?;
+            oracle.observe(&mut game);
+        }
+        Ok(oracle.flags)
+    }
+
+    /// Goes through the steps of the last move and checks the balances, returns the number of
+    /// new violations, which are added to the flags.
+    pub fn observe(&mut self, game: &mut GameEnvironment) -> usize {
+        let flagged = self.flags.len();
+        for step in game.take_events() {
+            self.step(&step);
+        }
+        if matches!(game.stuck_state(), StuckState::MoveAttacker) {
+            self.check_balances(game);
+        }
+        self.turn += 1;
+        self.flags.len() - flagged
+    }
+
+    fn step(&mut self, step: &StepEvent) {
+        // frames deeper than the step returned, a frame at the same depth starting over is a
+        // new call
+        let new_frame = step.pc == 0 && !step.resumed;
+        self.frames
+            .retain(|frame| frame.depth < step.depth || (frame.depth == step.depth && !new_frame));
+        if step.address != self.defender {
+            return;
+        }
+        if new_frame {
+            let selector = step.selector;
+            for frame in &mut self.frames {
+                if frame.reads_before_call.is_some() && frame.reentered.is_none() {
+                    frame.reentered = Some(selector);
+                }
+            }
+            self.frames.push(Frame {
+                depth: step.depth,
+                selector,
+                reads: BTreeSet::new(),
+                reads_before_call: None,
+                reentered: None,
+            });
+        }
+        let Some(frame) = self
+            .frames
+            .iter_mut()
+            .last()
+            .filter(|frame| frame.depth == step.depth)
+        else {
+            return;
+        };
+        match (step.opcode, step.operand) {
+            (opcode::SLOAD, Operand::Slot(slot)) => {
+                frame.reads.insert(slot);
+            }
+            (opcode::SSTORE, Operand::Slot(slot)) => {
+                let stale = frame
+                    .reads_before_call
+                    .as_ref()
+                    .is_some_and(|reads| reads.contains(&slot));
+                if let (true, Some(reentered)) = (stale, frame.reentered) {
+                    let violation = Violation::Reentrancy {
+                        function: frame.selector,
+                        reentered,
+                        slot,
+                        pc: step.pc,
+                    };
+                    let turn = self.turn;
+                    self.flags.push(Flag { turn, violation });
+                }
+            }
+            (opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL, _)
+                if !step.resumed =>
+            {
+                frame.reads_before_call = Some(frame.reads.clone());
+            }
+            (opcode::KECCAK256, Operand::Preimage(preimage)) => {
+                let Some(mapping) = self.config.mapping else {
+                    return;
+                };
+                if U256::from_be_bytes::<32>(preimage[32..].try_into().unwrap()) == mapping {
+                    self.entries.insert(keccak256(&preimage).into());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_balances(&mut self, game: &mut GameEnvironment) {
+        if self.config.mapping.is_some() {
+            let balance = game.balance(self.defender);
+            let owed = self.owed(game);
+            let insolvent = balance.saturating_sub(owed) < self.surplus;
+            if insolvent && !self.insolvent {
+                let surplus = self.surplus;
+                self.flag(Violation::Insolvent {
+                    balance,
+                    owed,
+                    surplus,
+                });
+            }
+            self.insolvent = insolvent;
+        }
+        let amount = game.balance(self.attacker).saturating_sub(self.start);
+        let outflow = amount > self.config.allowed_outflow;
+        if outflow && !self.outflow {
+            self.flag(Violation::Outflow { amount });
+        }
+        self.outflow = outflow;
+    }
+
+    /// Sum of the entries of the mapping seen so far.
+    fn owed(&self, game: &mut GameEnvironment) -> U256 {
+        self.entries
+            .iter()
+            .map(|slot| game.storage(self.defender, *slot))
+            .fold(U256::ZERO, U256::saturating_add)
+    }
+
+    fn flag(&mut self, violation: Violation) {
+        self.flags.push(Flag {
+            turn: self.turn,
+            violation,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::corpus::benchmark;
+    use crate::env::NoChecks;
+    use crate::episode::Move;
+    use crate::mcts::Action;
+    use crate::vec_env::AttackerEnv;
+
+    const WITHDRAW: [u8; 4] = [0x3c, 0xcf, 0xd6, 0x0b];
+
+    #[test]
+    fn flags_reentrancy_as_it_happens() {
+        let config = OracleConfig {
+            mapping: Some(U256::ZERO),
+            ..Default::default()
+        };
+        let bank = benchmark("reentrancy").unwrap();
+        let episode = bank.exploit_episode(&bank.vulnerable).unwrap();
+        let flags = Oracle::replay(config.clone(), &episode).unwrap();
+        let violations: Vec<_> = flags.iter().map(|flag| &flag.violation).collect();
+        assert!(
+            matches!(
+                violations[..],
+                [
+                    Violation::Reentrancy {
+                        function: Some(WITHDRAW),
+                        reentered: Some(WITHDRAW),
+                        ..
+                    },
+                    Violation::Insolvent { .. },
+                    Violation::Outflow { .. },
+                ]
+            ),
+            "{flags:#?}"
+        );
+        // the outer withdraw clears the balance once the attacker lets its call return
+        assert_eq!(
+            episode.moves[flags[0].turn],
+            Move::Attacker(Action::Pass(true))
+        );
+        assert_eq!(
+            flags[2].violation,
+            Violation::Outflow {
+                amount: U256::from(500)
+            }
+        );
+
+        let borrow = [0xe6, 0x8d, 0x35, 0x69];
+        let lender = benchmark("cross_function_reentrancy").unwrap();
+        let episode = lender.exploit_episode(&lender.vulnerable).unwrap();
+        let flags = Oracle::replay(OracleConfig::default(), &episode).unwrap();
+        assert!(flags.iter().any(|flag| matches!(
+            flag.violation,
+            Violation::Reentrancy {
+                reentered: Some(selector),
+                ..
+            } if selector == borrow
+        )));
+
+        // deposit and withdraw without reentering
+        let honest = &bank.exploit[..2];
+        let honest: Vec<_> = honest
+            .iter()
+            .cloned()
+            .chain([Action::Return, Action::Pass(true), Action::Stop])
+            .collect();
+        for contract in [&bank.vulnerable, &bank.fixed] {
+            let episode = Episode::record(
+                contract.deployment_code.clone(),
+                contract.abi.clone(),
+                crate::corpus::ATTACKER,
+                bank.attacker_balance,
+                &honest,
+                NoChecks,
+            )
+            .unwrap();
+            assert_eq!(Oracle::replay(config.clone(), &episode).unwrap(), vec![]);
+        }
+
+        // every violation adds to the reward
+        let game = bank
+            .vulnerable
+            .owned_game(crate::corpus::ATTACKER, bank.attacker_balance);
+        let mut env = AttackerEnv::new(&game, NoChecks).with_oracle(config, 0.25);
+        let mut reward = 0.0;
+        for action in &bank.exploit {
+            reward += env.step(action, 4).unwrap().1;
+        }
+        assert_eq!(env.flags().len(), 3);
+        assert_eq!(reward, 0.5 + 3.0 * 0.25);
+    }
+}
@@ -0,0 +1,521 @@
+//! Pre-written test cases of the defender contract.
+//!
+//! The defender must not ban honest use of its contract. A test case is a sequence of
+//! transactions, each with expectations on its status, output and the state after it. The
+//! [`Runner`] replays every case on a fresh game twice, once without checks and once with the
+//! defender checks, and reports the cases broken by the checks and what the checks cost.
+//!
+//! Cases are written in JSON:
+//!
+//! ```json
+//! {
+//!     "name": "deposit and withdraw",
+//!     "balances": [{ "address": "0x00000000000000000000000000000000000a11ce", "balance": 1000 }],
+//!     "steps": [
+//!         { "caller": "0x00000000000000000000000000000000000a11ce", "function": "deposit", "value": 900 },
+//!         {
+//!             "caller": "0x00000000000000000000000000000000000a11ce",
+//!             "function": "balances",
+//!             "args": ["0x00000000000000000000000000000000000a11ce"],
+//!             "expect": { "returns": ["900"] }
+//!         },
+//!         { "caller": "0x00000000000000000000000000000000000a11ce", "calldata": "0x3ccfd60b" }
+//!     ]
+//! }
+//! ```
+//!
+//! Accounts without an address are the defender contract.
+
+use crate::env::{DefenderChecks, GameEnvironment, NoChecks, StuckState};
+use ethers::abi::token::{LenientTokenizer, Tokenizer};
+use ethers::abi::{Param, Token};
+use ethers::prelude::BaseContract;
+use revm::interpreter::{return_ok, InstructionResult};
+use revm::primitives::{hex, Bytes, Env, B160, U256};
+use revm::{CallResult, InMemoryDB};
+use serde::Deserialize;
+use std::fmt;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TestCase {
+    pub name: String,
+    /// Balances set before the first step.
+    #[serde(default)]
+    pub balances: Vec<Balance>,
+    pub steps: Vec<Step>,
+}
+
+/// Transaction into the defender contract.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    #[serde(deserialize_with = "de::address")]
+    pub caller: B160,
+    #[serde(flatten)]
+    pub input: Input,
+    #[serde(default, deserialize_with = "de::word")]
+    pub value: U256,
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u64,
+    #[serde(default)]
+    pub expect: Expect,
+}
+
+fn default_gas_limit() -> u64 {
+    1_000_000
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Input {
+    Calldata {
+        #[serde(deserialize_with = "de::bytes")]
+        calldata: Bytes,
+    },
+    /// Function of the defender ABI, arguments in the format of `ethabi` command line.
+    Call {
+        function: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+impl Input {
+    pub fn encode(&self, abi: &BaseContract) -> Result<Bytes, String> {
+        match self {
+            Self::Calldata { calldata } => Ok(calldata.clone()),
+            Self::Call { function, args } => {
+                let function = abi.abi().function(function).map_err(|e| e.to_string())?;
+                let tokens = tokenize(&function.inputs, args)?;
+                let input = function.encode_input(&tokens).map_err(|e| e.to_string())?;
+                Ok(input.into())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Expect {
+    pub status: Status,
+    /// Raw return data.
+    #[serde(deserialize_with = "de::optional_bytes")]
+    pub output: Option<Bytes>,
+    /// Return values of an ABI call.
+    pub returns: Option<Vec<String>>,
+    pub balances: Vec<Balance>,
+    pub storage: Vec<Slot>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    #[default]
+    Success,
+    /// Reverted, halted or banned by the defender.
+    Revert,
+    Any,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Balance {
+    #[serde(default, deserialize_with = "de::optional_address")]
+    pub address: Option<B160>,
+    #[serde(deserialize_with = "de::word")]
+    pub balance: U256,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Slot {
+    #[serde(default, deserialize_with = "de::optional_address")]
+    pub address: Option<B160>,
+    #[serde(deserialize_with = "de::word")]
+    pub slot: U256,
+    #[serde(deserialize_with = "de::word")]
+    pub value: U256,
+}
+
+/// Deserializers of hex strings, words can also be decimal strings or numbers.
+mod de {
+    use revm::primitives::{hex, Bytes, B160, U256};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<B160, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+
+    pub fn optional_address<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<B160>, D::Error> {
+        address(deserializer).map(Some)
+    }
+
+    pub fn word<'de, D: Deserializer<'de>>(deserializer: D) -> Result<U256, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Word {
+            Number(u64),
+            String(String),
+        }
+        match Word::deserialize(deserializer)? {
+            Word::Number(number) => Ok(U256::from(number)),
+            Word::String(string) => string.parse().map_err(D::Error::custom),
+        }
+    }
+
+    pub fn bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bytes, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        let bytes =
+            hex::decode(string.strip_prefix("0x").unwrap_or(&string)).map_err(D::Error::custom)?;
+        Ok(bytes.into())
+    }
+
+    pub fn optional_bytes<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Bytes>, D::Error> {
+        bytes(deserializer).map(Some)
+    }
+}
+
+/// Load a JSON file with a case or a list of cases.
+pub fn load_cases(path: &Path) -> Result<Vec<TestCase>, String> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Cases {
+        One(TestCase),
+        Many(Vec<TestCase>),
+    }
+    let json = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    match serde_json::from_str(&json).map_err(|e| format!("{}: {e}", path.display()))? {
+        Cases::One(case) => Ok(vec![case]),
+        Cases::Many(cases) => Ok(cases),
+    }
+}
+
+/// Step of a case that did not go as expected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub step: usize,
+    pub reason: String,
+}
+
+/// Replay of a case.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CaseRun {
+    /// The replay stops at the first failure.
+    pub failure: Option<Failure>,
+    /// Gas of the transactions.
+    pub gas_used: u64,
+    /// Number of calls checked by the defender.
+    pub checks: usize,
+    pub check_gas: u64,
+    /// Peak memory of the checks.
+    pub check_memory: usize,
+}
+
+impl CaseRun {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseReport {
+    pub name: String,
+    pub unchecked: CaseRun,
+    pub checked: CaseRun,
+}
+
+impl CaseReport {
+    /// The case passes without checks but not with them.
+    pub fn broken(&self) -> bool {
+        self.unchecked.passed() && !self.checked.passed()
+    }
+
+    /// The case fails even without checks, so it says nothing about them.
+    pub fn invalid(&self) -> bool {
+        !self.unchecked.passed()
+    }
+
+    /// Gas of the checked replay over the unchecked one, including the gas of the checks.
+    pub fn extra_gas(&self) -> i64 {
+        (self.checked.gas_used + self.checked.check_gas) as i64 - self.unchecked.gas_used as i64
+    }
+
+    pub fn extra_memory(&self) -> usize {
+        self.checked.check_memory
+    }
+}
+
+impl fmt::Display for CaseReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.invalid() {
+            "INVALID"
+        } else if self.broken() {
+            "BROKEN"
+        } else {
+            "ok"
+        };
+        write!(
+            f,
+            "{status} {}: extra gas {}, extra memory {}",
+            self.name,
+            self.extra_gas(),
+            self.extra_memory()
+        )?;
+        let run = if self.invalid() {
+            &self.unchecked
+        } else {
+            &self.checked
+        };
+        if let Some(failure) = &run.failure {
+            write!(f, ", step {}: {}", failure.step, failure.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays cases against a deployment of the defender contract.
+#[derive(Debug, Clone)]
+pub struct Runner {
+    pub code: Bytes,
+    pub abi: BaseContract,
+    pub attacker: B160,
+    pub attacker_balance: U256,
+}
+
+impl Runner {
+    pub fn new(code: Bytes, abi: BaseContract) -> Self {
+        Self {
+            code,
+            abi,
+            attacker: B160::from_low_u64_be(0xa77ac4e5),
+            attacker_balance: U256::ZERO,
+        }
+    }
+
+    pub fn run(&self, case: &TestCase, checks: &mut dyn DefenderChecks) -> CaseReport {
+        CaseReport {
+            name: case.name.clone(),
+            unchecked: self.replay(case, &mut NoChecks),
+            checked: self.replay(case, checks),
+        }
+    }
+
+    pub fn run_all(&self, cases: &[TestCase], checks: &mut dyn DefenderChecks) -> Vec<CaseReport> {
+        cases.iter().map(|case| self.run(case, checks)).collect()
+    }
+
+    fn replay(&self, case: &TestCase, checks: &mut dyn DefenderChecks) -> CaseRun {
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let mut game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            self.attacker,
+            self.attacker_balance,
+            self.code.clone(),
+            self.abi.clone(),
+        );
+        for balance in &case.balances {
+            let address = balance.address.unwrap_or(game.defender_account);
+            game.set_balance(address, balance.balance);
+        }
+        let mut run = CaseRun::default();
+        for (index, step) in case.steps.iter().enumerate() {
+            let result = step.input.encode(&self.abi).and_then(|input| {
+                game.user_move(step.caller, input, step.value, step.gas_limit);
+                finish_transaction(&mut game, checks, &mut run);
+                let result = game.last_result.take().expect("transaction returned");
+                run.gas_used += result.gas.spend();
+                self.verify(&mut game, step, &result)
+            });
+            if let Err(reason) = result {
+                run.failure = Some(Failure {
+                    step: index,
+                    reason,
+                });
+                break;
+            }
+        }
+        run
+    }
+
+    fn verify(
+        &self,
+        game: &mut GameEnvironment,
+        step: &Step,
+        result: &CallResult,
+    ) -> Result<(), String> {
+        let expect = &step.expect;
+        let success = matches!(result.result, return_ok!());
+        match expect.status {
+            Status::Success if !success => {
+                return Err(format!("expected success, got {:?}", result.result))
+            }
+            Status::Revert if success => {
+                return Err(format!("expected revert, got {:?}", result.result))
+            }
+            _ => (),
+        }
+        if let Some(output) = &expect.output {
+            check_output(&result.return_value, output)?;
+        }
+        if let Some(returns) = &expect.returns {
+            let Input::Call { function, .. } = &step.input else {
+                return Err("`returns` needs an ABI call".into());
+            };
+            let function = self
+                .abi
+                .abi()
+                .function(function)
+                .map_err(|e| e.to_string())?;
+            let tokens = tokenize(&function.outputs, returns)?;
+            check_output(&result.return_value, &ethers::abi::encode(&tokens))?;
+        }
+        for balance in &expect.balances {
+            let address = balance.address.unwrap_or(game.defender_account);
+            let actual = game.balance(address);
+            if actual != balance.balance {
+                return Err(format!(
+                    "balance of {address:?} is {actual}, expected {}",
+                    balance.balance
+                ));
+            }
+        }
+        for slot in &expect.storage {
+            let address = slot.address.unwrap_or(game.defender_account);
+            let actual = game.storage(address, slot.slot);
+            if actual != slot.value {
+                return Err(format!(
+                    "slot {} of {address:?} is {actual}, expected {}",
+                    slot.slot, slot.value
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Play the transaction to its end, the attacker account acting like an account without code.
+fn finish_transaction(
+    game: &mut GameEnvironment,
+    checks: &mut dyn DefenderChecks,
+    run: &mut CaseRun,
+) {
+    loop {
+        match game.stuck_state() {
+            StuckState::MoveAttacker => return,
+            StuckState::CallDefender { call_inputs, .. } => {
+                let check = checks.check(call_inputs);
+                run.checks += 1;
+                run.check_gas += check.gas;
+                run.check_memory = run.check_memory.max(check.memory);
+                let pass = game.charge_check(&check);
+                game.defender_pass(pass);
+            }
+            StuckState::CallAttacker { .. } => game.attacker_answer(None),
+            StuckState::PrepareAttackerReturn { .. } => game.attacker_pass(true),
+            StuckState::SomeoneReturn { .. } => game.pop_return(),
+            StuckState::Noop => unreachable!("turn left unfinished"),
+        }
+    }
+}
+
+fn check_output(actual: &[u8], expected: &[u8]) -> Result<(), String> {
+    if actual != expected {
+        return Err(format!(
+            "output 0x{}, expected 0x{}",
+            hex::encode(actual),
+            hex::encode(expected)
+        ));
+    }
+    Ok(())
+}
+
+fn tokenize(params: &[Param], values: &[String]) -> Result<Vec<Token>, String> {
+    if params.len() != values.len() {
+        return Err(format!(
+            "expected {} values, got {}",
+            params.len(),
+            values.len()
+        ));
+    }
+    params
+        .iter()
+        .zip(values)
+        .map(|(param, value)| {
+            LenientTokenizer::tokenize(&param.kind, value).map_err(|e| format!("{value}: {e}"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, CheckResult, SILLY_BANK};
+    use revm::interpreter::CallInputs;
+
+    const CASES: &str = r#"[
+        {
+            "name": "deposit and withdraw",
+            "balances": [{ "address": "0x00000000000000000000000000000000000a11ce", "balance": 1000 }],
+            "steps": [
+                { "caller": "0x00000000000000000000000000000000000a11ce", "function": "deposit", "value": 900 },
+                {
+                    "caller": "0x00000000000000000000000000000000000a11ce",
+                    "function": "balances",
+                    "args": ["00000000000000000000000000000000000a11ce"],
+                    "expect": { "returns": ["900"] }
+                },
+                {
+                    "caller": "0x00000000000000000000000000000000000a11ce",
+                    "calldata": "0x3ccfd60b",
+                    "expect": {
+                        "balances": [{ "address": "0x00000000000000000000000000000000000a11ce", "balance": 1000 }]
+                    }
+                },
+                { "caller": "0x00000000000000000000000000000000000a11ce", "function": "withdraw", "expect": { "status": "revert" } }
+            ]
+        },
+        {
+            "name": "deposit",
+            "steps": [{ "caller": "0x00000000000000000000000000000000000b0b00", "function": "deposit" }]
+        },
+        {
+            "name": "withdraw nothing",
+            "steps": [{ "caller": "0x00000000000000000000000000000000000b0b00", "function": "withdraw" }]
+        }
+    ]"#;
+
+    #[test]
+    fn checks_breaking_withdraw() {
+        let cases: Vec<TestCase> = serde_json::from_str(CASES).unwrap();
+        let runner = Runner::new(SILLY_BANK.to_vec().into(), silly_bank_abi());
+        let withdraw = runner
+            .abi
+            .abi()
+            .function("withdraw")
+            .unwrap()
+            .short_signature();
+        let mut ban_withdraw = |call_inputs: &CallInputs| CheckResult {
+            pass: !call_inputs.input.starts_with(&withdraw),
+            gas: 100,
+            memory: 32,
+        };
+        let reports = runner.run_all(&cases, &mut ban_withdraw);
+        let [withdrawn, deposited, nothing] = &reports[..] else {
+            panic!("{reports:#?}")
+        };
+        assert!(withdrawn.broken());
+        assert_eq!(withdrawn.checked.failure.as_ref().unwrap().step, 2);
+        assert_eq!(withdrawn.unchecked.checks, 5);
+        assert!(!deposited.broken() && !deposited.invalid());
+        assert_eq!(deposited.extra_gas(), 100);
+        assert_eq!(deposited.extra_memory(), 32);
+        assert!(nothing.invalid());
+    }
+}
@@ -0,0 +1,440 @@
+//! Owned games and batches of them stepped in parallel, for training.
+//!
+//! A [`GameEnvironment`] borrows its `Env` and database. [`OwnedGame`] keeps them together with
+//! the rest of the game and lends a `GameEnvironment` for every call, so games can be cloned and
+//! sent to other threads. Clones of a deployed game share the deployed and analysed bytecode of
+//! the defender, which is reference counted.
+
+use crate::env::{DefenderChecks, GameEnvironment, GameParts, GasUsage, StuckState};
+use crate::episode::Move;
+use crate::fingerprint::Fingerprint;
+use crate::invariant::Invariants;
+use crate::mcts::{utility, Action};
+use crate::oracle::{Flag, Oracle, OracleConfig};
+use ethers::prelude::BaseContract;
+use rayon::prelude::*;
+use revm::interpreter::InstructionResult;
+use revm::primitives::{Bytes, Env, B160, U256};
+use revm::InMemoryDB;
+
+/// A game owning its `Env` and database.
+#[derive(Debug, Clone)]
+pub struct OwnedGame {
+    env: Env,
+    db: InMemoryDB,
+    /// Only `None` while lent out by [`OwnedGame::with`].
+    parts: Option<GameParts>,
+}
+
+impl OwnedGame {
+    /// Deploys the defender, as [`GameEnvironment::new`].
+    pub fn new(
+        attacker_account: B160,
+        attacker_balance: U256,
+        deployment_code: Bytes,
+        abi: BaseContract,
+    ) -> Self {
+        let mut env = Env::default();
+        let mut db = InMemoryDB::default();
+        let game = GameEnvironment::new(
+            &mut env,
+            &mut db,
+            attacker_account,
+            attacker_balance,
+            deployment_code,
+            abi,
+        );
+        let parts = Some(game.into_parts());
+        Self { env, db, parts }
+    }
+
+    /// Records the instructions every move runs, for [`GameEnvironment::take_trace`].
+    pub fn set_trace_steps(&mut self, trace_steps: bool) {
+        self.env.cfg.trace_steps = trace_steps;
+    }
+
+    /// Records a light event per instruction every move runs, for
+    /// [`GameEnvironment::take_events`].
+    pub fn set_trace_events(&mut self, trace_events: bool) {
+        self.env.cfg.trace_events = trace_events;
+    }
+
+    pub fn with<R>(&mut self, f: impl FnOnce(&mut GameEnvironment) -> R) -> R {
+        let parts = self.parts.take().expect("game is not lent out");
+        let mut game = GameEnvironment::from_parts(&mut self.env, &mut self.db, parts);
+        let result = f(&mut game);
+        self.parts = Some(game.into_parts());
+        result
+    }
+}
+
+/// What the attacker policy sees of a game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observation {
+    /// Name of what the game waits for, see [`StuckState::name`].
+    pub waiting_for: &'static str,
+    pub attacker_balance: U256,
+    pub defender_balance: U256,
+    /// Balance of the attacker minus the price of its gas, see
+    /// [`GameEnvironment::attacker_net_balance`].
+    pub attacker_net_balance: U256,
+    pub gas_used: GasUsage,
+    /// Calls between the players that are still running.
+    pub depth: usize,
+    /// Status of the last transaction, `None` before the first one ends.
+    pub last_result: Option<InstructionResult>,
+    pub transactions: usize,
+    pub fingerprint: Fingerprint,
+}
+
+/// Result of a step of all the games.
+#[derive(Debug, Clone, Default)]
+pub struct Batch {
+    /// Observations of the next positions, of the new games for the games that ended.
+    pub observations: Vec<Observation>,
+    /// Change of the profit of the attacker, relative to its starting balance, plus the reward
+    /// of the violations the oracle flagged and of a broken invariant if any.
+    pub rewards: Vec<f64>,
+    pub dones: Vec<bool>,
+    /// Why the action of a game was illegal, which ended the game.
+    pub errors: Vec<Option<String>>,
+}
+
+/// A game played by an attacker policy, the defender plays its checks.
+#[derive(Debug, Clone)]
+pub struct AttackerEnv<D> {
+    game: OwnedGame,
+    checks: D,
+    start: U256,
+    transactions: usize,
+    /// Oracle watching the game and the reward of every violation it flags.
+    oracle: Option<(Oracle, f64)>,
+    /// Reward of breaking an invariant, the game checks them.
+    invariant_reward: f64,
+    /// Name of the invariant the attacker broke, which ended the game.
+    broken: Option<String>,
+}
+
+/// Independent games played by one attacker policy, the defender plays its checks.
+pub struct VecEnv<D> {
+    initial: OwnedGame,
+    checks: D,
+    games: Vec<AttackerEnv<D>>,
+    /// A game ends when the attacker stops or after this many transactions.
+    pub max_transactions: usize,
+    /// Reward of an illegal action, which ends its game.
+    pub illegal_action_reward: f64,
+    oracle: Option<(OracleConfig, f64)>,
+    invariants: Option<Invariants>,
+}
+
+impl<D: DefenderChecks + Clone + Send> VecEnv<D> {
+    /// `len` copies of `initial`, each with its own copy of `checks`.
+    pub fn new(initial: OwnedGame, len: usize, checks: D, max_transactions: usize) -> Self {
+        let mut this = Self {
+            initial,
+            checks,
+            games: vec![],
+            max_transactions,
+            illegal_action_reward: -1.0,
+            oracle: None,
+            invariants: None,
+        };
+        this.games = (0..len).map(|_| this.new_game()).collect();
+        this
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Restarts every game.
+    pub fn reset(&mut self) -> Vec<Observation> {
+        let games = (0..self.games.len()).map(|_| self.new_game()).collect();
+        self.games = games;
+        self.games
+            .par_iter_mut()
+            .map(AttackerEnv::observe)
+            .collect()
+    }
+
+    /// Plays one action in every game, in parallel. Games that end are restarted.
+    ///
+    /// An illegal action ends its game alone, with [`VecEnv::illegal_action_reward`].
+    pub fn step(&mut self, actions: &[Action]) -> Batch {
+        assert_eq!(actions.len(), self.games.len(), "one action per game");
+        let max_transactions = self.max_transactions;
+        let outcomes: Vec<_> = self
+            .games
+            .par_iter_mut()
+            .zip(actions)
+            .map(|(game, action)| game.step(action, max_transactions))
+            .collect();
+        let mut batch = Batch::default();
+        for (index, outcome) in outcomes.into_iter().enumerate() {
+            let (observation, reward, done, error) = match outcome {
+                Ok((observation, reward, done)) => (observation, reward, done, None),
+                // the observation of the new game replaces it
+                Err(e) => {
+                    let observation = self.games[index].observe();
+                    (observation, self.illegal_action_reward, true, Some(e))
+                }
+            };
+            batch.observations.push(observation);
+            batch.rewards.push(reward);
+            batch.dones.push(done);
+            batch.errors.push(error);
+        }
+        for (index, done) in batch.dones.iter().enumerate() {
+            if *done {
+                self.games[index] = self.new_game();
+                batch.observations[index] = self.games[index].observe();
+            }
+        }
+        batch
+    }
+
+    /// Watches every game with an oracle, see [`AttackerEnv::with_oracle`]. Games restart.
+    pub fn with_oracle(mut self, config: OracleConfig, reward: f64) -> Self {
+        self.oracle = Some((config, reward));
+        self.reset();
+        self
+    }
+
+    /// Ends every game on a broken invariant, see [`AttackerEnv::with_invariants`]. Games
+    /// restart.
+    pub fn with_invariants(mut self, invariants: Invariants) -> Self {
+        self.invariants = Some(invariants);
+        self.reset();
+        self
+    }
+
+    fn new_game(&self) -> AttackerEnv<D> {
+        let mut game = AttackerEnv::new(&self.initial, self.checks.clone());
+        if let Some((config, reward)) = &self.oracle {
+            game = game.with_oracle(config.clone(), *reward);
+        }
+        if let Some(invariants) = &self.invariants {
+            game = game.with_invariants(invariants.clone());
+        }
+        game
+    }
+}
+
+impl<D: DefenderChecks> AttackerEnv<D> {
+    pub fn new(initial: &OwnedGame, checks: D) -> Self {
+        let mut game = initial.clone();
+        let start = game.with(|game| game.attacker_net_balance());
+        Self {
+            game,
+            checks,
+            start,
+            transactions: 0,
+            oracle: None,
+            invariant_reward: 0.0,
+            broken: None,
+        }
+    }
+
+    /// Watches the game with an [`Oracle`] from its current position, every violation it flags
+    /// adds `reward` to the reward of the step.
+    pub fn with_oracle(mut self, config: OracleConfig, reward: f64) -> Self {
+        self.game.set_trace_events(true);
+        let oracle = self.game.with(|game| Oracle::new(config, game));
+        self.oracle = Some((oracle, reward));
+        self
+    }
+
+    /// Ends the game when the attacker breaks one of `invariants`, at any turn, which adds
+    /// their reward to the reward of the step.
+    pub fn with_invariants(mut self, invariants: Invariants) -> Self {
+        self.invariant_reward = invariants.reward;
+        self.game.with(|game| game.set_invariants(Some(invariants)));
+        self
+    }
+
+    /// Name of the invariant the attacker broke, if any.
+    pub fn broken(&self) -> Option<&str> {
+        self.broken.as_deref()
+    }
+
+    /// Violations flagged so far, empty without an oracle.
+    pub fn flags(&self) -> &[Flag] {
+        self.oracle
+            .as_ref()
+            .map_or(&[], |(oracle, _)| &oracle.flags)
+    }
+
+    /// Plays the action and the checks of the defender it leads to, returns the next
+    /// observation, the reward and whether the game ended.
+    pub fn step(
+        &mut self,
+        action: &Action,
+        max_transactions: usize,
+    ) -> Result<(Observation, f64, bool), String> {
+        let Self {
+            game,
+            checks,
+            start,
+            oracle,
+            invariant_reward,
+            broken,
+            ..
+        } = self;
+        let reward = game.with(|game| {
+            let before = game.attacker_net_balance();
+            let mut violations = 0;
+            let mut play = |next: Move, game: &mut GameEnvironment| {
+                next.play(game)?;
+                if let Some((oracle, _)) = oracle {
+                    violations += oracle.observe(game);
+                }
+                Ok::<_, String>(())
+            };
+            play(Move::Attacker(action.clone()), game)?;
+            while let StuckState::CallDefender { call_inputs, .. } = game.stuck_state() {
+                if game.broken().is_some() {
+                    break;
+                }
+                let check = checks.check(call_inputs);
+                let pass = game.charge_check(&check);
+                play(Move::Defender(pass), game)?;
+            }
+            let after = game.attacker_net_balance();
+            let bonus = oracle.as_ref().map_or(0.0, |(_, reward)| *reward);
+            let mut reward =
+                utility(*start, after) - utility(*start, before) + bonus * violations as f64;
+            if broken.is_none() {
+                *broken = game.broken().map(str::to_owned);
+                if broken.is_some() {
+                    reward += *invariant_reward;
+                }
+            }
+            Ok::<_, String>(reward)
+        })?;
+        if matches!(action, Action::Transact(_)) {
+            self.transactions += 1;
+        }
+        let observation = self.observe();
+        let done = matches!(action, Action::Stop)
+            || self.broken.is_some()
+            || self.truncated(&observation, max_transactions);
+        Ok((observation, reward, done))
+    }
+
+    /// The game hit a limit, rather than being won or lost: the attacker waits for its next
+    /// transaction with none left, or with its gas budget spent.
+    pub fn truncated(&mut self, observation: &Observation, max_transactions: usize) -> bool {
+        let budget = self.game.with(|game| game.gas_model.attacker_budget);
+        observation.waiting_for == StuckState::MoveAttacker.name()
+            && (self.transactions >= max_transactions
+                || budget.is_some_and(|budget| observation.gas_used.attacker >= budget))
+    }
+
+    /// Net balance of the attacker at the start of the game, rewards are relative to it.
+    pub fn start(&self) -> U256 {
+        self.start
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        let transactions = self.transactions;
+        self.game.with(|game| Observation {
+            waiting_for: game.stuck_state().name(),
+            attacker_balance: game.balance(game.attacker_account),
+            defender_balance: game.balance(game.defender_account),
+            attacker_net_balance: game.attacker_net_balance(),
+            gas_used: game.gas_used(),
+            depth: game.depth(),
+            last_result: game.last_result.as_ref().map(|result| result.result),
+            transactions,
+            fingerprint: game.fingerprint(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::env::{silly_bank_abi, NoChecks, SILLY_BANK};
+    use crate::mcts::Call;
+
+    #[test]
+    fn steps_games_in_parallel() {
+        fn assert_send<T: Send>() {}
+        assert_send::<OwnedGame>();
+
+        let abi = silly_bank_abi();
+        let attacker = B160::from_low_u64_be(0xa77ac);
+        let call = |function: &str, value: u64| Call {
+            data: abi.encode(function, ()).unwrap().0,
+            value: U256::from(value),
+            gas_limit: 1_000_000,
+        };
+        let initial = OwnedGame::new(
+            attacker,
+            U256::from(1000),
+            SILLY_BANK.to_vec().into(),
+            abi.clone(),
+        );
+        let mut games = VecEnv::new(initial, 4, NoChecks, 2);
+        let observations = games.reset();
+        assert_eq!(observations.len(), 4);
+        assert!(observations.windows(2).all(|pair| pair[0] == pair[1]));
+
+        let deposit = Action::Transact(call("deposit", 500));
+        let withdraw = Action::Transact(call("withdraw", 0));
+        let batch = games.step(&[deposit.clone(), deposit.clone(), deposit, Action::Stop]);
+        assert_eq!(batch.rewards[..3], [-0.5; 3]);
+        assert_eq!(batch.dones, [false, false, false, true]);
+        assert_eq!(batch.observations[3], observations[3]);
+
+        // the first game reenters, the others run out of transactions
+        let batch = games.step(&[withdraw.clone(), withdraw.clone(), withdraw, Action::Stop]);
+        assert_eq!(
+            batch.observations[0].waiting_for,
+            "attacker to answer a call"
+        );
+        assert_eq!(batch.observations[0].depth, 1);
+        assert_eq!(batch.rewards[..3], [0.5; 3]);
+        let batch = games.step(&[
+            Action::Backcall(call("withdraw", 0)),
+            Action::Return,
+            Action::Return,
+            Action::Stop,
+        ]);
+        assert_eq!(batch.rewards[0], 0.5);
+        let batch = games.step(&[
+            Action::Return,
+            Action::Pass(true),
+            Action::Pass(true),
+            Action::Stop,
+        ]);
+        assert_eq!(batch.rewards[1..3], [0.0; 2]);
+        assert_eq!(batch.dones, [false, true, true, true]);
+        assert_eq!(batch.observations[1], observations[1]);
+
+        let mut game = games.games[0].game.clone();
+        for _ in 0..2 {
+            game.with(|game| Move::Attacker(Action::Pass(true)).play(game))
+                .unwrap();
+        }
+        assert_eq!(game.with(|game| game.balance(attacker)), U256::from(1500));
+
+        // the first game waits for a pass, its illegal action ends it alone
+        let batch = games.step(&[
+            Action::Return,
+            Action::Transact(call("deposit", 500)),
+            Action::Stop,
+            Action::Stop,
+        ]);
+        assert!(batch.errors[0].is_some());
+        assert_eq!(batch.errors[1..], [None, None, None]);
+        assert_eq!(batch.rewards[..2], [-1.0, -0.5]);
+        assert_eq!(batch.dones, [true, false, true, true]);
+        assert_eq!(batch.observations[0], observations[0]);
+    }
+}
@@ -0,0 +1,61 @@
+//! Runs the `gym` binary over stdin and stdout.
+
+use game::corpus::{benchmark, ATTACKER};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn stdout_is_json_lines() {
+    let bank = benchmark("reentrancy").unwrap();
+    let config = json!({
+        "deployment_code": format!("0x{}", revm::primitives::hex::encode(&bank.vulnerable.deployment_code)),
+        "abi": bank.vulnerable.abi,
+        "attacker": format!("{ATTACKER:?}"),
+        "attacker_balance": 1000,
+        "calls": [{ "function": "deposit", "value": 500 }, { "function": "withdraw" }],
+        "max_transactions": 2,
+    });
+    let path = std::env::temp_dir().join(format!("gym-{}.json", std::process::id()));
+    std::fs::write(&path, config.to_string()).unwrap();
+
+    let mut gym = Command::new(env!("CARGO_BIN_EXE_gym"))
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let requests = [
+        json!({ "method": "spaces" }),
+        json!({ "method": "reset" }),
+        json!({ "method": "step", "action": 0 }),
+        json!({ "method": "close" }),
+    ];
+    let mut stdin = gym.stdin.take().unwrap();
+    for request in &requests {
+        writeln!(stdin, "{request}").unwrap();
+    }
+    drop(stdin);
+    let output = gym.wait_with_output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{line:?}: {e}")))
+        .collect();
+    // close ends the session without a response
+    assert_eq!(responses.len(), requests.len() - 1, "{stdout}");
+    assert!(
+        responses
+            .iter()
+            .all(|response| response.get("error").is_none()),
+        "{stdout}"
+    );
+}
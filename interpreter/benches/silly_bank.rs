@@ -0,0 +1,75 @@
+//! Rollout throughput of the SillyBank defender with the bare interpreter.
+//!
+//! Run with `cargo bench -p revm-interpreter --bench silly_bank`.
+
+use revm_interpreter::{
+    primitives::{hex, Bytecode, Bytes, Env, ShanghaiSpec, SpecId, B160, U256},
+    Contract, DummyHost, InstructionResult, Interpreter,
+};
+use std::time::{Duration, Instant};
+
+/// Runtime code of `tmp/SillyBank.bin`.
+const SILLY_BANK: &str = "6080604052600436106100345760003560e01c806327e235e3146100395780633ccfd60b14610076578063d0e30db01461008d575b600080fd5b34801561004557600080fd5b50610060600480360381019061005b91906102ad565b610097565b60405161006d91906102f3565b60405180910390f35b34801561008257600080fd5b5061008b6100af565b005b6100956101f3565b005b60006020528060005260406000206000915090505481565b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020549050600081116100ff57600080fd5b60003373ffffffffffffffffffffffffffffffffffffffff16826040516101259061033f565b60006040518083038185875af1925050503d8060008114610162576040519150601f19603f3d011682016040523d82523d6000602084013e610167565b606091505b50509050806101ab576040517f08c379a00000000000000000000000000000000000000000000000000000000081526004016101a2906103b1565b60405180910390fd5b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020819055505050565b346000803373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200190815260200160002060008282546102419190610400565b92505081905550565b600080fd5b600073ffffffffffffffffffffffffffffffffffffffff82169050919050565b600061027a8261024f565b9050919050565b61028a8161026f565b811461029557600080fd5b50565b6000813590506102a781610281565b92915050565b6000602082840312156102c3576102c261024a565b5b60006102d184828501610298565b91505092915050565b6000819050919050565b6102ed816102da565b82525050565b600060208201905061030860008301846102e4565b92915050565b600081905092915050565b50565b600061032960008361030e565b915061033482610319565b600082019050919050565b600061034a8261031c565b9150819050919050565b600082825260208201905092915050565b7f4661696c656420746f2073656e64204574686572000000000000000000000000600082015250565b600061039b601483610354565b91506103a682610365565b602082019050919050565b600060208201905081810360008301526103ca8161038e565b9050919050565b7f4e487b7100000000000000000000000000000000000000000000000000000000600052601160045260246000fd5b600061040b826102da565b9150610416836102da565b925082820190508082111561042e5761042d6103d1565b5b9291505056fea2646970667358221220b3616bed71d88f1b5fd72ea2bfb498060d234bba17beb056f47e3034bb27281864736f6c63430008120033";
+
+const DEPOSIT: [u8; 4] = [0xd0, 0xe3, 0x0d, 0xb0];
+const BALANCES: [u8; 4] = [0x27, 0xe2, 0x35, 0xe3];
+
+/// One rollout: every player deposits and then reads its balance back.
+fn rollout(bytecode: &Bytecode, host: &mut DummyHost, players: &[B160]) -> u64 {
+    host.clear();
+    let mut gas = 0;
+    for (i, player) in players.iter().enumerate() {
+        let mut balances = BALANCES.to_vec();
+        balances.extend_from_slice(&[0; 12]);
+        balances.extend_from_slice(player.as_bytes());
+        for (input, value) in [
+            (DEPOSIT.to_vec(), U256::from(i + 1)),
+            (balances, U256::ZERO),
+        ] {
+            let contract = Contract::new(
+                Bytes::from(input),
+                bytecode.clone(),
+                B160::zero(),
+                *player,
+                value,
+                SpecId::SHANGHAI,
+            );
+            let mut interpreter = Interpreter::new(Box::new(contract), 1_000_000, false);
+            let result = interpreter.run::<_, ShanghaiSpec>(host);
+            assert!(
+                matches!(result, InstructionResult::Stop | InstructionResult::Return),
+                "{result:?}"
+            );
+            gas += interpreter.gas().spend();
+        }
+    }
+    gas
+}
+
+fn main() {
+    let code = hex::decode(SILLY_BANK).unwrap();
+    let bytecode =
+        revm_interpreter::analysis::to_analysed(Bytecode::new_raw(code.into()), SpecId::SHANGHAI);
+    let players: Vec<B160> = (1..=16u64).map(B160::from_low_u64_be).collect();
+    let mut host = DummyHost::new(Env::default());
+
+    // warm up
+    for _ in 0..1000 {
+        rollout(&bytecode, &mut host, &players);
+    }
+
+    let budget = Duration::from_secs(3);
+    let start = Instant::now();
+    let (mut rollouts, mut gas) = (0u64, 0u64);
+    while start.elapsed() < budget {
+        gas += rollout(&bytecode, &mut host, &players);
+        rollouts += 1;
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "silly_bank: {:.0} rollouts/s, {:.1} Mgas/s ({} calls per rollout)",
+        rollouts as f64 / elapsed,
+        gas as f64 / elapsed / 1e6,
+        players.len() * 2
+    );
+}
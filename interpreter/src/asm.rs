@@ -0,0 +1,421 @@
+//! Bytecode disassembler and a small label-aware assembler.
+//!
+//! Text format is one mnemonic per line, with the immediate of a `PUSHn` on its own
+//! line written as `0x` followed by upper case hex digits (leading zeros stripped):
+//!
+//! ```text
+//! PUSH1
+//! 0x80
+//! PUSH1
+//! 0x40
+//! MSTORE
+//! ```
+//!
+//! A byte that is not an opcode (e.g. inside the metadata) is written as a bare hex byte.
+//!
+//! The assembler additionally accepts the immediate on the same line as the mnemonic,
+//! decimal immediates, `;` comments, label definitions (`name:`) and label references
+//! (`@name`) as push immediates. `PUSH @name` without an explicit size is encoded as `PUSH2`.
+//! A bare hex token outside of a push is emitted as raw bytes.
+
+use crate::opcode::{self, OpCode, OPCODE_JUMPMAP};
+use crate::primitives::{Bytecode, Bytes};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt;
+
+/// One decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    /// Offset of the opcode in the bytecode.
+    pub pc: usize,
+    /// Raw opcode byte, it may be an unknown opcode (e.g. inside metadata).
+    pub opcode: u8,
+    /// Push immediate. For a push truncated by the end of code, missing bytes are zero filled.
+    pub immediate: Vec<u8>,
+}
+
+impl Instruction {
+    /// Known opcode, `None` if the byte is not a defined instruction.
+    pub fn op(&self) -> Option<OpCode> {
+        OpCode::try_from_u8(self.opcode)
+    }
+
+    /// Number of bytes of the instruction including its immediate.
+    pub fn size(&self) -> usize {
+        1 + immediate_size(self.opcode)
+    }
+
+    /// Offset of the following instruction.
+    pub fn next_pc(&self) -> usize {
+        self.pc + self.size()
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_mnemonic(f, self.opcode)?;
+        if immediate_size(self.opcode) != 0 {
+            f.write_str(" ")?;
+            write_immediate(f, &self.immediate)?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of immediate bytes that follow `opcode`.
+#[inline]
+pub const fn immediate_size(opcode: u8) -> usize {
+    let push_offset = opcode.wrapping_sub(opcode::PUSH1);
+    if push_offset < 32 {
+        push_offset as usize + 1
+    } else {
+        0
+    }
+}
+
+/// Disassemble original (unpadded) bytes of the bytecode.
+pub fn disassemble(bytecode: &Bytecode) -> Vec<Instruction> {
+    disassemble_slice(bytecode.original_bytes().as_ref())
+}
+
+/// Disassemble raw code into instruction records.
+pub fn disassemble_slice(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let size = immediate_size(opcode);
+        let mut immediate = Vec::with_capacity(size);
+        if size != 0 {
+            let end = core::cmp::min(pc + 1 + size, code.len());
+            immediate.extend_from_slice(&code[pc + 1..end]);
+            immediate.resize(size, 0);
+        }
+        instructions.push(Instruction {
+            pc,
+            opcode,
+            immediate,
+        });
+        pc += 1 + size;
+    }
+    instructions
+}
+
+/// Render instructions in the one-token-per-line dump format.
+pub fn to_text(instructions: &[Instruction]) -> String {
+    struct Text<'a>(&'a [Instruction]);
+    impl fmt::Display for Text<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            for instruction in self.0 {
+                write_mnemonic(f, instruction.opcode)?;
+                writeln!(f)?;
+                if immediate_size(instruction.opcode) != 0 {
+                    write_immediate(f, &instruction.immediate)?;
+                    writeln!(f)?;
+                }
+            }
+            Ok(())
+        }
+    }
+    Text(instructions).to_string()
+}
+
+/// Bytes that are not opcodes (e.g. inside metadata) are written as a bare hex byte.
+fn write_mnemonic(f: &mut fmt::Formatter<'_>, opcode: u8) -> fmt::Result {
+    match OPCODE_JUMPMAP[opcode as usize] {
+        Some(name) => f.write_str(name),
+        None => write_immediate(f, &[opcode]),
+    }
+}
+
+fn write_immediate(f: &mut fmt::Formatter<'_>, immediate: &[u8]) -> fmt::Result {
+    let mut digits = immediate.iter().skip_while(|b| **b == 0);
+    match digits.next() {
+        None => f.write_str("0x0"),
+        Some(first) => {
+            write!(f, "0x{first:X}")?;
+            digits.try_for_each(|b| write!(f, "{b:02X}"))
+        }
+    }
+}
+
+/// Error returned by [`assemble`]. Lines are counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic { line: usize, token: String },
+    MissingImmediate { line: usize },
+    InvalidImmediate { line: usize, token: String },
+    ImmediateTooLarge { line: usize, token: String },
+    UnexpectedImmediate { line: usize, token: String },
+    DuplicateLabel { line: usize, label: String },
+    UndefinedLabel { line: usize, label: String },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownMnemonic { line, token } => {
+                write!(f, "line {line}: unknown mnemonic `{token}`")
+            }
+            Self::MissingImmediate { line } => write!(f, "line {line}: push without immediate"),
+            Self::InvalidImmediate { line, token } => {
+                write!(f, "line {line}: invalid immediate `{token}`")
+            }
+            Self::ImmediateTooLarge { line, token } => {
+                write!(f, "line {line}: immediate `{token}` does not fit the push")
+            }
+            Self::UnexpectedImmediate { line, token } => {
+                write!(f, "line {line}: immediate `{token}` without a push")
+            }
+            Self::DuplicateLabel { line, label } => {
+                write!(f, "line {line}: label `{label}` defined twice")
+            }
+            Self::UndefinedLabel { line, label } => {
+                write!(f, "line {line}: label `{label}` is not defined")
+            }
+        }
+    }
+}
+
+/// Push waiting for its immediate token.
+struct PendingPush {
+    line: usize,
+    /// `None` for a sized-by-label `PUSH`.
+    size: Option<usize>,
+}
+
+/// Label reference to be patched once all labels are known.
+struct Fixup {
+    line: usize,
+    offset: usize,
+    size: usize,
+    label: String,
+}
+
+/// Assemble mnemonic text (see module docs) into raw bytes.
+pub fn assemble(text: &str) -> Result<Bytes, AsmError> {
+    let mut code: Vec<u8> = Vec::new();
+    let mut labels: Vec<(String, usize)> = Vec::new();
+    let mut fixups: Vec<Fixup> = Vec::new();
+    let mut pending: Option<PendingPush> = None;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw_line.split(';').next().unwrap_or_default();
+        for token in content.split_whitespace() {
+            if let Some(push) = pending.take() {
+                push_immediate(&mut code, &mut fixups, push, token)?;
+                continue;
+            }
+            if let Some(label) = token.strip_suffix(':') {
+                if labels.iter().any(|(name, _)| name == label) {
+                    return Err(AsmError::DuplicateLabel {
+                        line,
+                        label: label.into(),
+                    });
+                }
+                labels.push((label.into(), code.len()));
+                continue;
+            }
+            if let Some(hex) = token.strip_prefix("0x") {
+                let bytes = parse_raw(hex).ok_or_else(|| AsmError::InvalidImmediate {
+                    line,
+                    token: token.into(),
+                })?;
+                code.extend_from_slice(&bytes);
+                continue;
+            }
+            if token.starts_with('@') {
+                return Err(AsmError::UnexpectedImmediate {
+                    line,
+                    token: token.into(),
+                });
+            }
+            let upper = token.to_ascii_uppercase();
+            if upper == "PUSH" {
+                pending = Some(PendingPush { line, size: None });
+                continue;
+            }
+            let opcode = parse_mnemonic(&upper).ok_or_else(|| AsmError::UnknownMnemonic {
+                line,
+                token: token.into(),
+            })?;
+            code.push(opcode);
+            let size = immediate_size(opcode);
+            if size != 0 {
+                pending = Some(PendingPush {
+                    line,
+                    size: Some(size),
+                });
+            }
+        }
+    }
+    if let Some(push) = pending {
+        return Err(AsmError::MissingImmediate { line: push.line });
+    }
+
+    for fixup in fixups {
+        let target = labels
+            .iter()
+            .find(|(name, _)| *name == fixup.label)
+            .map(|(_, offset)| *offset)
+            .ok_or(AsmError::UndefinedLabel {
+                line: fixup.line,
+                label: fixup.label.clone(),
+            })?;
+        let bytes = target.to_be_bytes();
+        if bytes[..bytes.len() - fixup.size].iter().any(|b| *b != 0) {
+            return Err(AsmError::ImmediateTooLarge {
+                line: fixup.line,
+                token: fixup.label,
+            });
+        }
+        code[fixup.offset..fixup.offset + fixup.size]
+            .copy_from_slice(&bytes[bytes.len() - fixup.size..]);
+    }
+    Ok(code.into())
+}
+
+fn push_immediate(
+    code: &mut Vec<u8>,
+    fixups: &mut Vec<Fixup>,
+    push: PendingPush,
+    token: &str,
+) -> Result<(), AsmError> {
+    let PendingPush { line, size } = push;
+    if let Some(label) = token.strip_prefix('@') {
+        let size = match size {
+            Some(size) => size,
+            None => {
+                code.push(opcode::PUSH2);
+                2
+            }
+        };
+        fixups.push(Fixup {
+            line,
+            offset: code.len(),
+            size,
+            label: label.into(),
+        });
+        code.resize(code.len() + size, 0);
+        return Ok(());
+    }
+    let Some(size) = size else {
+        return Err(AsmError::InvalidImmediate {
+            line,
+            token: token.into(),
+        });
+    };
+    let value = parse_immediate(token).ok_or_else(|| AsmError::InvalidImmediate {
+        line,
+        token: token.into(),
+    })?;
+    if value.len() > size {
+        return Err(AsmError::ImmediateTooLarge {
+            line,
+            token: token.into(),
+        });
+    }
+    code.resize(code.len() + size - value.len(), 0);
+    code.extend_from_slice(&value);
+    Ok(())
+}
+
+fn parse_mnemonic(upper: &str) -> Option<u8> {
+    OPCODE_JUMPMAP
+        .iter()
+        .position(|name| *name == Some(upper))
+        .map(|opcode| opcode as u8)
+}
+
+/// Big endian bytes of the immediate, without leading zeros.
+fn parse_immediate(token: &str) -> Option<Vec<u8>> {
+    if let Some(hex) = token.strip_prefix("0x") {
+        let mut bytes = parse_raw(hex)?;
+        let zeros = bytes.iter().take_while(|b| **b == 0).count();
+        bytes.drain(..zeros);
+        Some(bytes)
+    } else {
+        let value: u128 = token.parse().ok()?;
+        Some(
+            value
+                .to_be_bytes()
+                .into_iter()
+                .skip_while(|b| *b == 0)
+                .collect(),
+        )
+    }
+}
+
+/// Hex digits as bytes, an odd number of digits gets an implicit leading zero.
+fn parse_raw(hex: &str) -> Option<Vec<u8>> {
+    if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let odd = hex.len() % 2;
+    let mut bytes = Vec::with_capacity(hex.len() / 2 + odd);
+    if odd == 1 {
+        bytes.push(u8::from_str_radix(&hex[..1], 16).ok()?);
+    }
+    for pair in hex.as_bytes()[odd..].chunks(2) {
+        bytes.push(u8::from_str_radix(core::str::from_utf8(pair).ok()?, 16).ok()?);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::hex_literal::hex;
+
+    #[test]
+    fn text_round_trip() {
+        let code = hex!("6080604052348015600f57600080fd5b5061046a8061001f6000396000f3fe");
+        let instructions = disassemble_slice(&code);
+        let text = to_text(&instructions);
+        assert!(text.starts_with("PUSH1\n0x80\nPUSH1\n0x40\nMSTORE\nCALLVALUE\n"));
+        assert!(text.contains("PUSH2\n0x46A\n"));
+        assert_eq!(assemble(&text).unwrap().as_ref(), &code[..]);
+    }
+
+    #[test]
+    fn truncated_push_is_zero_filled() {
+        let instructions = disassemble_slice(&hex!("0062aabb"));
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].immediate, hex!("aabb00"));
+        assert_eq!(instructions[1].to_string(), "PUSH3 0xAABB00");
+        let data = disassemble_slice(&hex!("0c"));
+        assert_eq!(to_text(&data), "0xC\n");
+        assert_eq!(assemble("0xC").unwrap().as_ref(), &hex!("0c")[..]);
+    }
+
+    #[test]
+    fn labels() {
+        let code = assemble(
+            "PUSH @end ; jump over the revert
+             JUMP
+             PUSH1 0 DUP1 REVERT
+             end: JUMPDEST
+             STOP",
+        )
+        .unwrap();
+        assert_eq!(code.as_ref(), &hex!("61000856600080fd5b00")[..]);
+        assert_eq!(
+            assemble("PUSH1 @missing"),
+            Err(AsmError::UndefinedLabel {
+                line: 1,
+                label: "missing".into()
+            })
+        );
+        assert_eq!(
+            assemble("PUSH1 0x100"),
+            Err(AsmError::ImmediateTooLarge {
+                line: 1,
+                token: "0x100".into()
+            })
+        );
+    }
+}
@@ -32,6 +32,10 @@ pub trait Host {
         index: U256,
         value: U256,
     ) -> Option<(U256, U256, U256, bool)>;
+    /// Get transient storage value of address at index.
+    fn tload(&mut self, address: B160, index: U256) -> U256;
+    /// Set transient storage value of account address at index.
+    fn tstore(&mut self, address: B160, index: U256, value: U256);
     /// Create a log owned by address with given topics and data.
     fn log(&mut self, address: B160, topics: Vec<B256>, data: Bytes);
     /// Mark an address to be deleted, with funds transferred to target.
@@ -5,14 +5,14 @@ mod bitwise;
 mod control;
 mod host;
 mod host_env;
-mod i256;
+pub(crate) mod i256;
 mod memory;
 pub mod opcode;
 mod stack;
 mod system;
 
 use crate::{interpreter::Interpreter, primitives::Spec, Host};
-pub use opcode::{OpCode, OPCODE_JUMPMAP};
+pub use opcode::OPCODE_JUMPMAP;
 
 pub use crate::{return_ok, return_revert, InstructionResult};
 pub fn return_stop(interpreter: &mut Interpreter, _host: &mut dyn Host) {
@@ -28,10 +28,13 @@ pub fn return_not_found(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 
 #[inline(always)]
 pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H) {
-    println!("{}", OpCode::try_from_u8(opcode).unwrap().as_str());
-    println!("-- memory {:?}", interp.memory());
-    println!("-- stack  {:?}", interp.stack());
-    println!("-- pc     {:?}", interp.program_counter());
+    #[cfg(feature = "trace")]
+    {
+        println!("{}", opcode::OpCode::try_from_u8(opcode).unwrap().as_str());
+        println!("-- memory {:?}", interp.memory());
+        println!("-- stack  {:?}", interp.stack());
+        println!("-- pc     {:?}", interp.program_counter());
+    }
     match opcode {
         opcode::STOP => return_stop(interp, host),
         opcode::ADD => arithmetic::wrapped_add(interp, host),
@@ -165,6 +168,8 @@ pub fn eval<H: Host, S: Spec>(opcode: u8, interp: &mut Interpreter, host: &mut H
         opcode::GASLIMIT => host_env::gaslimit(interp, host),
         opcode::SLOAD => host::sload::<S>(interp, host),
         opcode::SSTORE => host::sstore::<S>(interp, host),
+        opcode::TLOAD => host::tload::<S>(interp, host),
+        opcode::TSTORE => host::tstore::<S>(interp, host),
         opcode::GAS => system::gas(interp, host),
         opcode::LOG0 => host::log::<0>(interp, host),
         opcode::LOG1 => host::log::<1>(interp, host),
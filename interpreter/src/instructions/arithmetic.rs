@@ -6,43 +6,36 @@ use crate::{
 };
 
 pub fn wrapped_add(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop_top!(interpreter, op1, op2);
     *op2 = op1.wrapping_add(*op2);
 }
 
 pub fn wrapping_mul(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     *op2 = op1.wrapping_mul(*op2);
 }
 
 pub fn wrapping_sub(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop_top!(interpreter, op1, op2);
     *op2 = op1.wrapping_sub(*op2);
 }
 
 pub fn div(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     *op2 = op1.checked_div(*op2).unwrap_or_default()
 }
 
 pub fn sdiv(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     *op2 = i256_div(op1, *op2);
 }
 
 pub fn rem(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     *op2 = op1.checked_rem(*op2).unwrap_or_default()
 }
 
 pub fn smod(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     if *op2 != U256::ZERO {
         *op2 = i256_mod(op1, *op2)
@@ -50,13 +43,11 @@ pub fn smod(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn addmod(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::MID);
     pop_top!(interpreter, op1, op2, op3);
     *op3 = op1.add_mod(op2, *op3)
 }
 
 pub fn mulmod(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::MID);
     pop_top!(interpreter, op1, op2, op3);
     *op3 = op1.mul_mod(op2, *op3)
 }
@@ -83,7 +74,6 @@ pub fn eval_exp<SPEC: Spec>(interpreter: &mut Interpreter, _host: &mut dyn Host)
 /// `b == 0` then the yellow paper says the output should start with all zeros, then end with
 /// bits from `b`; this is equal to `y & mask` where `&` is bitwise `AND`.
 pub fn signextend(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::LOW);
     pop_top!(interpreter, op1, op2);
     if op1 < U256::from(32) {
         // `low_u32` works since op1 < 32
@@ -1,10 +1,9 @@
 use crate::{
-    gas, interpreter::Interpreter, primitives::Spec, primitives::SpecId::*, primitives::U256, Host,
+    interpreter::Interpreter, primitives::Spec, primitives::SpecId::*, primitives::U256, Host,
     InstructionResult,
 };
 
 pub fn jump(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::MID);
     pop!(interpreter, dest);
     let dest = as_usize_or_fail!(interpreter, dest, InstructionResult::InvalidJump);
     if interpreter.contract.is_valid_jump(dest) {
@@ -18,7 +17,6 @@ pub fn jump(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn jumpi(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::HIGH);
     pop!(interpreter, dest, value);
     if value != U256::ZERO {
         let dest = as_usize_or_fail!(interpreter, dest, InstructionResult::InvalidJump);
@@ -30,15 +28,21 @@ pub fn jumpi(interpreter: &mut Interpreter, _host: &mut dyn Host) {
         } else {
             interpreter.instruction_result = InstructionResult::InvalidJump
         }
+    } else {
+        interpreter.enter_gas_block();
     }
 }
 
+/// Charges static gas of the whole gas block started by this `JUMPDEST`, `JUMPDEST` included.
 pub fn jumpdest(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::JUMPDEST);
+    let block_gas = interpreter
+        .contract
+        .bytecode
+        .gas_block(interpreter.program_counter() - 1);
+    gas!(interpreter, block_gas);
 }
 
 pub fn pc(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.program_counter() - 1));
 }
 
@@ -4,12 +4,18 @@ use crate::{
     alloc::boxed::Box,
     alloc::vec::Vec,
     gas::{self, COLD_ACCOUNT_ACCESS_COST, WARM_STORAGE_READ_COST},
+    instructions::opcode,
     interpreter::Interpreter,
     return_ok, return_revert, CallContext, CallInputs, CallScheme, CreateInputs, CreateScheme,
     Host, InstructionResult, Transfer,
 };
 use core::cmp::min;
 
+/// Part of the opcode gas already charged with its gas block.
+fn block_gas<SPEC: Spec>(opcode: u8) -> u64 {
+    opcode::spec_opcode_gas(SPEC::SPEC_ID)[opcode as usize].get_gas() as u64
+}
+
 pub fn balance<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
     pop_address!(interpreter, address);
     let ret = host.balance(address);
@@ -35,7 +41,6 @@ pub fn balance<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
 pub fn selfbalance<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
     // EIP-1884: Repricing for trie-size-dependent opcodes
     check!(interpreter, SPEC::enabled(ISTANBUL));
-    gas!(interpreter, gas::LOW);
     let ret = host.balance(interpreter.contract.address);
     if ret.is_none() {
         interpreter.instruction_result = InstructionResult::FatalExternalError;
@@ -53,20 +58,18 @@ pub fn extcodesize<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Hos
         return;
     }
     let (code, is_cold) = ret.unwrap();
-    if SPEC::enabled(BERLIN) {
-        gas!(
-            interpreter,
-            if is_cold {
-                COLD_ACCOUNT_ACCESS_COST
-            } else {
-                WARM_STORAGE_READ_COST
-            }
-        );
+    let cost = if SPEC::enabled(BERLIN) {
+        if is_cold {
+            COLD_ACCOUNT_ACCESS_COST
+        } else {
+            WARM_STORAGE_READ_COST
+        }
     } else if SPEC::enabled(TANGERINE) {
-        gas!(interpreter, 700);
+        700
     } else {
-        gas!(interpreter, 20);
-    }
+        20
+    };
+    gas!(interpreter, cost - block_gas::<SPEC>(opcode::EXTCODESIZE));
 
     push!(interpreter, U256::from(code.len()));
 }
@@ -80,20 +83,18 @@ pub fn extcodehash<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Hos
         return;
     }
     let (code_hash, is_cold) = ret.unwrap();
-    if SPEC::enabled(BERLIN) {
-        gas!(
-            interpreter,
-            if is_cold {
-                COLD_ACCOUNT_ACCESS_COST
-            } else {
-                WARM_STORAGE_READ_COST
-            }
-        );
+    let cost = if SPEC::enabled(BERLIN) {
+        if is_cold {
+            COLD_ACCOUNT_ACCESS_COST
+        } else {
+            WARM_STORAGE_READ_COST
+        }
     } else if SPEC::enabled(ISTANBUL) {
-        gas!(interpreter, 700);
+        700
     } else {
-        gas!(interpreter, 400);
-    }
+        400
+    };
+    gas!(interpreter, cost - block_gas::<SPEC>(opcode::EXTCODEHASH));
     push_b256!(interpreter, code_hash);
 }
 
@@ -112,6 +113,7 @@ pub fn extcodecopy<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Hos
     gas_or_fail!(
         interpreter,
         gas::extcodecopy_cost::<SPEC>(len as u64, is_cold)
+            .map(|cost| cost - block_gas::<SPEC>(opcode::EXTCODECOPY))
     );
     if len == 0 {
         return;
@@ -131,7 +133,6 @@ pub fn extcodecopy<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Hos
 }
 
 pub fn blockhash(interpreter: &mut Interpreter, host: &mut dyn Host) {
-    gas!(interpreter, gas::BLOCKHASH);
     pop_top!(interpreter, number);
 
     if let Some(diff) = host.env().block.number.checked_sub(*number) {
@@ -178,6 +179,21 @@ pub fn sstore<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
         gas::sstore_cost::<SPEC>(original, old, new, remaining_gas, is_cold)
     });
     refund!(interpreter, gas::sstore_refund::<SPEC>(original, old, new));
+    interpreter.enter_gas_block();
+}
+
+/// EIP-1153: Transient storage opcodes
+pub fn tload<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
+    check!(interpreter, SPEC::enabled(CANCUN));
+    pop!(interpreter, index);
+    push!(interpreter, host.tload(interpreter.contract.address, index));
+}
+
+pub fn tstore<SPEC: Spec>(interpreter: &mut Interpreter, host: &mut dyn Host) {
+    check!(interpreter, SPEC::enabled(CANCUN));
+    check_staticcall!(interpreter);
+    pop!(interpreter, index, value);
+    host.tstore(interpreter.contract.address, index, value);
 }
 
 pub fn log<const N: u8>(interpreter: &mut Interpreter, host: &mut dyn Host) {
@@ -303,6 +319,7 @@ pub fn create<const IS_CREATE2: bool, SPEC: Spec>(
             return;
         };
         interpreter.stuck_reason = Create(create_input);
+        interpreter.instruction_result = InstructionResult::Stuck;
     } else {
         let CreateReturn(return_reason, address, gas, return_data) = 
             std::mem::replace(&mut interpreter.stuck_reason, Execute) else {
@@ -336,6 +353,9 @@ pub fn create<const IS_CREATE2: bool, SPEC: Spec>(
                 push_b256!(interpreter, B256::zero());
             }
         }
+        if interpreter.instruction_result == InstructionResult::Continue {
+            interpreter.enter_gas_block();
+        }
     }
 }
 
@@ -568,5 +588,8 @@ pub fn call_inner<SPEC: Spec>(
                 push!(interpreter, U256::ZERO);
             }
         }
+        if interpreter.instruction_result == InstructionResult::Continue {
+            interpreter.enter_gas_block();
+        }
     }
 }
@@ -10,7 +10,6 @@ use crate::{
 };
 
 pub fn mload(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop!(interpreter, index);
     let index = as_usize_or_fail!(interpreter, index, InstructionResult::InvalidOperandOOG);
     memory_resize!(interpreter, index, 32);
@@ -23,7 +22,6 @@ pub fn mload(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn mstore(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop!(interpreter, index, value);
     let index = as_usize_or_fail!(interpreter, index, InstructionResult::InvalidOperandOOG);
     memory_resize!(interpreter, index, 32);
@@ -31,7 +29,6 @@ pub fn mstore(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn mstore8(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop!(interpreter, index, value);
     let index = as_usize_or_fail!(interpreter, index, InstructionResult::InvalidOperandOOG);
     memory_resize!(interpreter, index, 1);
@@ -41,7 +38,6 @@ pub fn mstore8(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn msize(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.memory.effective_len()));
 }
 
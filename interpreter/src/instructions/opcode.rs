@@ -138,6 +138,8 @@ pub const GASLIMIT: u8 = 0x45;
 pub const SELFBALANCE: u8 = 0x47;
 pub const SLOAD: u8 = 0x54;
 pub const SSTORE: u8 = 0x55;
+pub const TLOAD: u8 = 0x5c;
+pub const TSTORE: u8 = 0x5d;
 pub const GAS: u8 = 0x5a;
 pub const LOG0: u8 = 0xa0;
 pub const LOG1: u8 = 0xa1;
@@ -275,8 +277,8 @@ pub const OPCODE_JUMPMAP: [Option<&'static str>; 256] = [
     /* 0x59 */ Some("MSIZE"),
     /* 0x5a */ Some("GAS"),
     /* 0x5b */ Some("JUMPDEST"),
-    /* 0x5c */ None,
-    /* 0x5d */ None,
+    /* 0x5c */ Some("TLOAD"),
+    /* 0x5d */ Some("TSTORE"),
     /* 0x5e */ Some("MCOPY"),
     /* 0x5f */ Some("PUSH0"),
     /* 0x60 */ Some("PUSH1"),
@@ -657,8 +659,18 @@ macro_rules! gas_opcodee {
             /* 0x5b  JUMPDEST */
             // gas::JUMPDEST gas is calculated in function call,
             OpInfo::jumpdest(),
-            /* 0x5c */ OpInfo::none(),
-            /* 0x5d */ OpInfo::none(),
+            /* 0x5c  TLOAD */
+            OpInfo::gas(if SpecId::enabled($spec_id, SpecId::CANCUN) {
+                gas::WARM_STORAGE_READ_COST
+            } else {
+                0
+            }),
+            /* 0x5d  TSTORE */
+            OpInfo::gas(if SpecId::enabled($spec_id, SpecId::CANCUN) {
+                gas::WARM_STORAGE_READ_COST
+            } else {
+                0
+            }),
             /* 0x5e  MCOPY */ OpInfo::dynamic_gas(),
             /* 0x5f PUSH0 */
             OpInfo::gas(if SpecId::enabled($spec_id, SpecId::SHANGHAI) {
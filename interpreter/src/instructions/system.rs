@@ -22,17 +22,14 @@ pub fn calculate_keccak256(interpreter: &mut Interpreter, _host: &mut dyn Host)
 }
 
 pub fn address(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push_b256!(interpreter, B256::from(interpreter.contract.address));
 }
 
 pub fn caller(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push_b256!(interpreter, B256::from(interpreter.contract.caller));
 }
 
 pub fn codesize(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.contract.bytecode.len()));
 }
 
@@ -61,7 +58,6 @@ pub fn codecopy(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn calldataload(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::VERYLOW);
     pop!(interpreter, index);
     let index = as_usize_saturated!(index);
 
@@ -78,13 +74,12 @@ pub fn calldataload(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn calldatasize(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.contract.input.len()));
 }
 
 pub fn callvalue(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, interpreter.contract.value);
+    #[cfg(feature = "trace")]
     println!("-- value: {:?}", interpreter.contract.value);
 }
 
@@ -110,7 +105,6 @@ pub fn calldatacopy(interpreter: &mut Interpreter, _host: &mut dyn Host) {
 }
 
 pub fn returndatasize<SPEC: Spec>(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     // EIP-211: New opcodes: RETURNDATASIZE and RETURNDATACOPY
     check!(interpreter, SPEC::enabled(BYZANTIUM));
     push!(
@@ -146,6 +140,6 @@ pub fn returndatacopy<SPEC: Spec>(interpreter: &mut Interpreter, _host: &mut dyn
 }
 
 pub fn gas(interpreter: &mut Interpreter, _host: &mut dyn Host) {
-    gas!(interpreter, gas::BASE);
     push!(interpreter, U256::from(interpreter.gas.remaining()));
+    interpreter.enter_gas_block();
 }
@@ -62,6 +62,29 @@ pub struct Interpreter {
     pub memory_limit: u64,
 }
 
+impl Clone for Interpreter {
+    /// The instruction pointer of the clone points into the bytecode of the cloned contract.
+    fn clone(&self) -> Self {
+        let contract = self.contract.clone();
+        // Safety: the program counter is within the bytecode, which is the same in the clone.
+        let instruction_pointer = unsafe { contract.bytecode.as_ptr().add(self.program_counter()) };
+        Self {
+            stuck_reason: self.stuck_reason.clone(),
+            instruction_pointer,
+            instruction_result: self.instruction_result,
+            gas: self.gas,
+            memory: self.memory.clone(),
+            stack: self.stack.clone(),
+            return_data_buffer: self.return_data_buffer.clone(),
+            return_range: self.return_range.clone(),
+            is_static: self.is_static,
+            contract,
+            #[cfg(feature = "memory_limit")]
+            memory_limit: self.memory_limit,
+        }
+    }
+}
+
 impl Interpreter {
     /// Current opcode
     pub fn current_opcode(&self) -> u8 {
@@ -72,7 +95,7 @@ impl Interpreter {
     pub fn new(contract: Box<Contract>, gas_limit: u64, is_static: bool) -> Self {
         #[cfg(not(feature = "memory_limit"))]
         {
-            Self {
+            let mut interpreter = Self {
                 stuck_reason: StuckReason::Execute,
                 instruction_pointer: contract.bytecode.as_ptr(),
                 return_range: Range::default(),
@@ -83,7 +106,9 @@ impl Interpreter {
                 instruction_result: InstructionResult::Continue,
                 is_static,
                 gas: Gas::new(gas_limit),
-            }
+            };
+            interpreter.enter_gas_block();
+            interpreter
         }
 
         #[cfg(feature = "memory_limit")]
@@ -99,7 +124,8 @@ impl Interpreter {
         is_static: bool,
         memory_limit: u64,
     ) -> Self {
-        Self {
+        let mut interpreter = Self {
+            stuck_reason: StuckReason::Execute,
             instruction_pointer: contract.bytecode.as_ptr(),
             return_range: Range::default(),
             memory: Memory::new(),
@@ -110,7 +136,9 @@ impl Interpreter {
             is_static,
             gas: Gas::new(gas_limit),
             memory_limit,
-        }
+        };
+        interpreter.enter_gas_block();
+        interpreter
     }
 
     pub fn contract(&self) -> &Contract {
@@ -140,6 +168,21 @@ impl Interpreter {
         }
     }
 
+    /// Charge static gas of the gas block starting at the current instruction.
+    ///
+    /// Called at the start of the code and by gas block ends that continue to the next
+    /// instruction. Blocks starting with `JUMPDEST` are charged by the `JUMPDEST` instruction.
+    #[inline(always)]
+    pub(crate) fn enter_gas_block(&mut self) {
+        if !crate::USE_GAS || self.current_opcode() == instructions::opcode::JUMPDEST {
+            return;
+        }
+        let gas = self.contract.bytecode.gas_block(self.program_counter());
+        if !self.gas.record_cost(gas) {
+            self.instruction_result = InstructionResult::OutOfGas;
+        }
+    }
+
     /// Execute next instruction
     #[inline(always)]
     pub fn step<H: Host, SPEC: Spec>(&mut self, host: &mut H) {
@@ -177,3 +220,63 @@ impl Interpreter {
         }
     }
 }
+
+/// An interpreter that is not running, with its program counter in place of its instruction
+/// pointer. Unlike [`Interpreter`] it holds no raw pointer, so it can be sent to other threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParkedInterpreter {
+    pub stuck_reason: StuckReason,
+    pub program_counter: usize,
+    pub instruction_result: InstructionResult,
+    pub gas: Gas,
+    pub memory: Memory,
+    pub stack: Stack,
+    pub return_data_buffer: Bytes,
+    pub return_range: Range<usize>,
+    pub is_static: bool,
+    pub contract: Box<Contract>,
+    #[cfg(feature = "memory_limit")]
+    pub memory_limit: u64,
+}
+
+impl Interpreter {
+    /// Parks the interpreter, see [`ParkedInterpreter::unpark`].
+    pub fn park(self) -> ParkedInterpreter {
+        ParkedInterpreter {
+            program_counter: self.program_counter(),
+            stuck_reason: self.stuck_reason,
+            instruction_result: self.instruction_result,
+            gas: self.gas,
+            memory: self.memory,
+            stack: self.stack,
+            return_data_buffer: self.return_data_buffer,
+            return_range: self.return_range,
+            is_static: self.is_static,
+            contract: self.contract,
+            #[cfg(feature = "memory_limit")]
+            memory_limit: self.memory_limit,
+        }
+    }
+}
+
+impl ParkedInterpreter {
+    /// The interpreter, to resume where it was parked.
+    pub fn unpark(self) -> Interpreter {
+        let bytecode = self.contract.bytecode.as_ptr();
+        let instruction_pointer = bytecode.wrapping_add(self.program_counter);
+        Interpreter {
+            instruction_pointer,
+            stuck_reason: self.stuck_reason,
+            instruction_result: self.instruction_result,
+            gas: self.gas,
+            memory: self.memory,
+            stack: self.stack,
+            return_data_buffer: self.return_data_buffer,
+            return_range: self.return_range,
+            is_static: self.is_static,
+            contract: self.contract,
+            #[cfg(feature = "memory_limit")]
+            memory_limit: self.memory_limit,
+        }
+    }
+}
@@ -1,6 +1,12 @@
-use crate::opcode;
-use crate::primitives::{Bytecode, BytecodeState, Bytes, B256};
-use alloc::sync::Arc;
+pub mod cfg;
+pub mod detectors;
+pub mod dispatcher;
+
+use crate::asm::immediate_size;
+use crate::gas;
+use crate::opcode::{self, spec_opcode_gas};
+use crate::primitives::{Bytecode, BytecodeState, Bytes, GasBlocks, SpecId, B256};
+use alloc::{sync::Arc, vec, vec::Vec};
 // use bitvec::order::Lsb0;
 // use bitvec::prelude::bitvec;
 // use bitvec::vec::BitVec;
@@ -11,10 +17,11 @@ use revm_primitives::{
 
 /// Perform bytecode analysis.
 ///
-/// The analysis finds and caches valid jump destinations for later execution as an optimization step.
+/// The analysis finds and caches valid jump destinations and the static gas of gas blocks
+/// for later execution as an optimization step.
 ///
-/// If the bytecode is already analyzed, it is returned as-is.
-pub fn to_analysed(bytecode: Bytecode) -> Bytecode {
+/// If the bytecode is already analyzed for the same spec, it is returned as-is.
+pub fn to_analysed(bytecode: Bytecode, spec_id: SpecId) -> Bytecode {
     let hash = bytecode.hash;
     let (bytecode, len) = match bytecode.state {
         BytecodeState::Raw => {
@@ -23,14 +30,37 @@ pub fn to_analysed(bytecode: Bytecode) -> Bytecode {
             (checked.bytecode, len)
         }
         BytecodeState::Checked { len } => (bytecode.bytecode, len),
-        _ => return bytecode,
+        BytecodeState::Analysed {
+            len,
+            jump_map,
+            gas_blocks,
+        } => {
+            let gas_blocks = if gas_blocks.spec_id == spec_id {
+                gas_blocks
+            } else {
+                analyze_gas_blocks(bytecode.bytecode.as_ref(), spec_id)
+            };
+            return Bytecode {
+                state: BytecodeState::Analysed {
+                    len,
+                    jump_map,
+                    gas_blocks,
+                },
+                ..bytecode
+            };
+        }
     };
     let jump_map = analyze(bytecode.as_ref());
+    let gas_blocks = analyze_gas_blocks(bytecode.as_ref(), spec_id);
 
     Bytecode {
         bytecode,
         hash,
-        state: BytecodeState::Analysed { len, jump_map },
+        state: BytecodeState::Analysed {
+            len,
+            jump_map,
+            gas_blocks,
+        },
     }
 }
 
@@ -63,12 +93,53 @@ fn analyze(code: &[u8]) -> JumpMap {
     JumpMap(Arc::new(jumps))
 }
 
+/// Sums static gas of gas blocks of (padded) code.
+///
+/// A gas block starts at pc zero, at every `JUMPDEST` and after every opcode flagged as
+/// gas block end, the end opcode itself belongs to the block. `JUMPDEST` gas is included in
+/// the block it starts. Dynamic gas is still charged by the instructions.
+fn analyze_gas_blocks(code: &[u8], spec_id: SpecId) -> GasBlocks {
+    let opcode_gas = spec_opcode_gas(spec_id);
+    let mut blocks: Vec<u32> = vec![0; code.len()];
+
+    let mut start = 0;
+    let mut block_gas: u32 = 0;
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        if opcode == opcode::JUMPDEST {
+            blocks[start] = block_gas;
+            start = pc;
+            block_gas = gas::JUMPDEST as u32;
+            pc += 1;
+            continue;
+        }
+        let info = &opcode_gas[opcode as usize];
+        block_gas = block_gas.saturating_add(info.get_gas());
+        pc += 1 + immediate_size(opcode);
+        if info.is_gas_block_end() {
+            blocks[start] = block_gas;
+            start = pc;
+            block_gas = 0;
+        }
+    }
+    if start < code.len() {
+        blocks[start] = block_gas;
+    }
+
+    GasBlocks {
+        spec_id,
+        gas: Arc::new(blocks),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BytecodeLocked {
     bytecode: Bytes,
     len: usize,
     hash: B256,
     jump_map: JumpMap,
+    gas_blocks: GasBlocks,
 }
 
 impl Default for BytecodeLocked {
@@ -83,12 +154,18 @@ impl TryFrom<Bytecode> for BytecodeLocked {
     type Error = ();
 
     fn try_from(bytecode: Bytecode) -> Result<Self, Self::Error> {
-        if let BytecodeState::Analysed { len, jump_map } = bytecode.state {
+        if let BytecodeState::Analysed {
+            len,
+            jump_map,
+            gas_blocks,
+        } = bytecode.state
+        {
             Ok(BytecodeLocked {
                 bytecode: bytecode.bytecode,
                 len,
                 hash: bytecode.hash,
                 jump_map,
+                gas_blocks,
             })
         } else {
             Err(())
@@ -119,6 +196,7 @@ impl BytecodeLocked {
             state: BytecodeState::Analysed {
                 len: self.len,
                 jump_map: self.jump_map,
+                gas_blocks: self.gas_blocks,
             },
         }
     }
@@ -133,4 +211,68 @@ impl BytecodeLocked {
     pub fn jump_map(&self) -> &JumpMap {
         &self.jump_map
     }
+
+    pub fn gas_blocks(&self) -> &GasBlocks {
+        &self.gas_blocks
+    }
+
+    /// Static gas of the gas block starting at `pc`.
+    #[inline(always)]
+    pub fn gas_block(&self, pc: usize) -> u64 {
+        self.gas_blocks.gas(pc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::primitives::{Env, ShanghaiSpec, B160, U256};
+    use crate::{Contract, DummyHost, Interpreter};
+    use alloc::boxed::Box;
+
+    /// Runtime code of the `SillyBank` the game crate plays on, as solc 0.8.18 emits it with its
+    /// metadata.
+    pub(super) const SILLY_BANK: &[u8] = &crate::primitives::hex_literal::hex!("6080604052600436106100345760003560e01c806327e235e3146100395780633ccfd60b14610076578063d0e30db01461008d575b600080fd5b34801561004557600080fd5b50610060600480360381019061005b91906102ad565b610097565b60405161006d91906102f3565b60405180910390f35b34801561008257600080fd5b5061008b6100af565b005b6100956101f3565b005b60006020528060005260406000206000915090505481565b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020549050600081116100ff57600080fd5b60003373ffffffffffffffffffffffffffffffffffffffff16826040516101259061033f565b60006040518083038185875af1925050503d8060008114610162576040519150601f19603f3d011682016040523d82523d6000602084013e610167565b606091505b50509050806101ab576040517f08c379a00000000000000000000000000000000000000000000000000000000081526004016101a2906103b1565b60405180910390fd5b60008060003373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff168152602001908152602001600020819055505050565b346000803373ffffffffffffffffffffffffffffffffffffffff1673ffffffffffffffffffffffffffffffffffffffff16815260200190815260200160002060008282546102419190610400565b92505081905550565b600080fd5b600073ffffffffffffffffffffffffffffffffffffffff82169050919050565b600061027a8261024f565b9050919050565b61028a8161026f565b811461029557600080fd5b50565b6000813590506102a781610281565b92915050565b6000602082840312156102c3576102c261024a565b5b60006102d184828501610298565b91505092915050565b6000819050919050565b6102ed816102da565b82525050565b600060208201905061030860008301846102e4565b92915050565b600081905092915050565b50565b600061032960008361030e565b915061033482610319565b600082019050919050565b600061034a8261031c565b9150819050919050565b600082825260208201905092915050565b7f4661696c656420746f2073656e64204574686572000000000000000000000000600082015250565b600061039b601483610354565b91506103a682610365565b602082019050919050565b600060208201905081810360008301526103ca8161038e565b9050919050565b7f4e487b7100000000000000000000000000000000000000000000000000000000600052601160045260246000fd5b600061040b826102da565b9150610416836102da565b925082820190508082111561042e5761042d6103d1565b5b9291505056fea2646970667358221220b3616bed71d88f1b5fd72ea2bfb498060d234bba17beb056f47e3034bb27281864736f6c63430008120033");
+
+    #[test]
+    fn gas_blocks_match_interpreter() {
+        let code = assemble(
+            "PUSH1 0 PUSH @skip JUMPI
+             PUSH1 1 POP
+             skip: JUMPDEST PUSH1 1 PUSH1 2 ADD STOP",
+        )
+        .unwrap();
+        let bytecode = to_analysed(Bytecode::new_raw(code), SpecId::SHANGHAI);
+        let BytecodeState::Analysed { gas_blocks, .. } = &bytecode.state else {
+            panic!("bytecode should be analysed");
+        };
+        assert_eq!(gas_blocks.gas(0), 3 + 3 + 10);
+        assert_eq!(gas_blocks.gas(6), 3 + 2);
+        assert_eq!(gas_blocks.gas(9), 1 + 3 + 3 + 3);
+
+        let contract = Contract::new(
+            Bytes::new(),
+            bytecode,
+            B160::zero(),
+            B160::zero(),
+            U256::ZERO,
+            SpecId::SHANGHAI,
+        );
+        let mut interpreter = Interpreter::new(Box::new(contract), 100, false);
+        let mut host = DummyHost::new(Env::default());
+        interpreter.run::<_, ShanghaiSpec>(&mut host);
+        assert_eq!(
+            interpreter.instruction_result,
+            crate::InstructionResult::Stop
+        );
+        assert_eq!(interpreter.gas().spend(), 16 + 5 + 10);
+
+        let mut interpreter = Interpreter::new(Box::new(interpreter.contract().clone()), 20, false);
+        interpreter.run::<_, ShanghaiSpec>(&mut host);
+        assert_eq!(
+            interpreter.instruction_result,
+            crate::InstructionResult::OutOfGas
+        );
+    }
 }
@@ -0,0 +1,412 @@
+//! Basic blocks and control-flow graph of a contract.
+
+use crate::asm::{disassemble_slice, Instruction};
+use crate::opcode;
+use crate::primitives::Bytecode;
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    string::String,
+    vec::Vec,
+};
+use core::fmt::Write;
+
+/// How control leaves a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    /// Block runs into the next block (which starts with `JUMPDEST`).
+    Fallthrough { next: usize },
+    /// `JUMP`, target is `None` when it is not a static `PUSH` + `JUMP`.
+    Jump { target: Option<usize> },
+    /// `JUMPI`, target is `None` when it is not a static `PUSH` + `JUMPI`.
+    JumpI { target: Option<usize>, next: usize },
+    /// Execution of the frame ends with the given opcode
+    /// (`STOP`, `RETURN`, `REVERT`, `INVALID`, `SELFDESTRUCT` or an unknown opcode).
+    Halt { opcode: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Pc of the first instruction.
+    pub start: usize,
+    /// Pc one past the last byte of the block.
+    pub end: usize,
+    /// Index range of the block inside [`Cfg::instructions`].
+    pub instructions: core::ops::Range<usize>,
+    pub terminator: Terminator,
+}
+
+impl BasicBlock {
+    /// Static successors of the block. Dynamic jump targets are not included.
+    pub fn successors(&self) -> impl Iterator<Item = usize> {
+        let (first, second) = match self.terminator {
+            Terminator::Fallthrough { next } => (Some(next), None),
+            Terminator::Jump { target } => (target, None),
+            Terminator::JumpI { target, next } => (target, Some(next)),
+            Terminator::Halt { .. } => (None, None),
+        };
+        first.into_iter().chain(second)
+    }
+
+    /// Block ends in a jump whose target is only known at runtime.
+    pub fn has_dynamic_jump(&self) -> bool {
+        matches!(
+            self.terminator,
+            Terminator::Jump { target: None } | Terminator::JumpI { target: None, .. }
+        )
+    }
+}
+
+/// Function found in the Solidity selector dispatcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionEntry {
+    pub selector: [u8; 4],
+    /// Jump destination taken when the selector matches.
+    pub pc: usize,
+    /// Index of the push of the selector in [`Cfg::instructions`].
+    pub index: usize,
+}
+
+/// Control-flow graph of a contract, keyed by block start pc.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cfg {
+    pub instructions: Vec<Instruction>,
+    pub blocks: BTreeMap<usize, BasicBlock>,
+}
+
+/// Code without the CBOR metadata solc appends, whose bytes would read as instructions. The
+/// last two bytes are the length of the metadata, which is a CBOR map.
+pub fn without_metadata(code: &[u8]) -> &[u8] {
+    let [.., high, low] = *code else {
+        return code;
+    };
+    let len = u16::from_be_bytes([high, low]) as usize;
+    match code.len().checked_sub(len + 2) {
+        Some(start) if (0xa1..=0xa5).contains(&code[start]) => &code[..start],
+        _ => code,
+    }
+}
+
+impl Cfg {
+    /// Build CFG over the original bytes of the bytecode, without the solc metadata.
+    pub fn new(bytecode: &Bytecode) -> Self {
+        Self::from_slice(without_metadata(bytecode.original_bytes().as_ref()))
+    }
+
+    pub fn from_slice(code: &[u8]) -> Self {
+        let instructions = disassemble_slice(code);
+        let jumpdests: BTreeSet<usize> = instructions
+            .iter()
+            .filter(|i| i.opcode == opcode::JUMPDEST)
+            .map(|i| i.pc)
+            .collect();
+
+        let mut blocks = BTreeMap::new();
+        let mut first = 0;
+        for (index, instruction) in instructions.iter().enumerate() {
+            let next = instruction.next_pc();
+            let terminator = match instruction.opcode {
+                opcode::JUMP => Terminator::Jump {
+                    target: static_target(&instructions[first..index], &jumpdests),
+                },
+                opcode::JUMPI => Terminator::JumpI {
+                    target: static_target(&instructions[first..index], &jumpdests),
+                    next,
+                },
+                op if is_halt(op) => Terminator::Halt { opcode: op },
+                _ if jumpdests.contains(&next) => Terminator::Fallthrough { next },
+                // last instruction runs into the implicit STOP of the padding.
+                _ if index + 1 == instructions.len() => Terminator::Halt {
+                    opcode: opcode::STOP,
+                },
+                _ => continue,
+            };
+            let start = instructions[first].pc;
+            blocks.insert(
+                start,
+                BasicBlock {
+                    start,
+                    end: next,
+                    instructions: first..index + 1,
+                    terminator,
+                },
+            );
+            first = index + 1;
+        }
+
+        Self {
+            instructions,
+            blocks,
+        }
+    }
+
+    /// Block that contains `pc`.
+    pub fn block_at(&self, pc: usize) -> Option<&BasicBlock> {
+        self.blocks
+            .range(..=pc)
+            .next_back()
+            .map(|(_, block)| block)
+            .filter(|block| pc < block.end)
+    }
+
+    /// Instructions of the block.
+    pub fn block_instructions(&self, block: &BasicBlock) -> &[Instruction] {
+        &self.instructions[block.instructions.clone()]
+    }
+
+    /// Static predecessors of every block.
+    pub fn predecessors(&self) -> BTreeMap<usize, Vec<usize>> {
+        let mut predecessors: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for block in self.blocks.values() {
+            for successor in block.successors() {
+                predecessors.entry(successor).or_default().push(block.start);
+            }
+        }
+        predecessors
+    }
+
+    /// Blocks reachable from `start` following static edges only.
+    pub fn reachable_from(&self, start: usize) -> BTreeSet<usize> {
+        let mut visited = BTreeSet::new();
+        let mut queue = Vec::from([start]);
+        while let Some(pc) = queue.pop() {
+            let Some(block) = self.blocks.get(&pc) else {
+                continue;
+            };
+            if visited.insert(pc) {
+                queue.extend(block.successors());
+            }
+        }
+        visited
+    }
+
+    /// Functions of the Solidity selector dispatcher, in dispatcher order.
+    ///
+    /// Recognizes `PUSH4 selector (DUP2)? EQ PUSHn target JUMPI`, which covers
+    /// both `DUP1 PUSH4 EQ` and `PUSH4 DUP2 EQ` forms emitted by solc. Selectors with
+    /// leading zero bytes are pushed with `PUSH1` to `PUSH3` by the optimizer; those
+    /// pushes also compare other words, so they need the `DUP1` or the `DUP2`. The
+    /// dispatcher ends before the first function it jumps to, comparisons in the
+    /// functions are not selectors.
+    pub fn function_entries(&self) -> Vec<FunctionEntry> {
+        let mut entries = Vec::new();
+        let mut dispatcher_end = usize::MAX;
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if instruction.pc >= dispatcher_end {
+                break;
+            }
+            if !(opcode::PUSH1..=opcode::PUSH4).contains(&instruction.opcode) {
+                continue;
+            }
+            let after_dup1 = index
+                .checked_sub(1)
+                .is_some_and(|previous| self.instructions[previous].opcode == opcode::DUP1);
+            let mut rest = self.instructions[index + 1..].iter();
+            let mut next = rest.next();
+            let before_dup2 = next.map(|i| i.opcode) == Some(opcode::DUP2);
+            if before_dup2 {
+                next = rest.next();
+            }
+            if next.map(|i| i.opcode) != Some(opcode::EQ) {
+                continue;
+            }
+            if instruction.opcode != opcode::PUSH4 && !after_dup1 && !before_dup2 {
+                continue;
+            }
+            let (Some(push), Some(jumpi)) = (rest.next(), rest.next()) else {
+                continue;
+            };
+            if jumpi.opcode != opcode::JUMPI || push.immediate.is_empty() {
+                continue;
+            }
+            let Some(pc) = as_jumpdest(&push.immediate, self) else {
+                continue;
+            };
+            let mut selector = [0; 4];
+            selector[4 - instruction.immediate.len()..].copy_from_slice(&instruction.immediate);
+            dispatcher_end = dispatcher_end.min(pc);
+            entries.push(FunctionEntry {
+                selector,
+                pc,
+                index,
+            });
+        }
+        entries
+    }
+
+    /// Graphviz rendering of the graph, one node per block with its disassembly.
+    pub fn to_dot(&self) -> String {
+        let entries = self.function_entries();
+        let mut dot = String::from("digraph cfg {\n    node [shape=box fontname=monospace];\n");
+        for block in self.blocks.values() {
+            let _ = write!(dot, "    b{} [label=\"", block.start);
+            if let Some(entry) = entries.iter().find(|entry| entry.pc == block.start) {
+                let _ = write!(dot, "fn 0x");
+                for byte in entry.selector {
+                    let _ = write!(dot, "{byte:02x}");
+                }
+                dot.push_str("\\l");
+            }
+            for instruction in self.block_instructions(block) {
+                let _ = write!(dot, "{:04x}: {}\\l", instruction.pc, instruction);
+            }
+            dot.push_str("\"];\n");
+            match block.terminator {
+                Terminator::JumpI { target, next } => {
+                    if let Some(target) = target {
+                        let _ = writeln!(dot, "    b{} -> b{target} [label=\"T\"];", block.start);
+                    }
+                    let _ = writeln!(dot, "    b{} -> b{next} [label=\"F\"];", block.start);
+                }
+                _ => {
+                    for successor in block.successors() {
+                        let _ = writeln!(dot, "    b{} -> b{successor};", block.start);
+                    }
+                }
+            }
+            if block.has_dynamic_jump() {
+                let _ = writeln!(dot, "    b{} -> dynamic [style=dashed];", block.start);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn is_halt(opcode: u8) -> bool {
+    matches!(
+        opcode,
+        opcode::STOP | opcode::RETURN | opcode::REVERT | opcode::INVALID | opcode::SELFDESTRUCT
+    ) || opcode::OPCODE_JUMPMAP[opcode as usize].is_none()
+}
+
+/// Target of a jump that directly follows a push.
+fn static_target(block: &[Instruction], jumpdests: &BTreeSet<usize>) -> Option<usize> {
+    let push = block.last().filter(|i| !i.immediate.is_empty())?;
+    let target = be_usize(&push.immediate)?;
+    jumpdests.contains(&target).then_some(target)
+}
+
+fn as_jumpdest(immediate: &[u8], cfg: &Cfg) -> Option<usize> {
+    let target = be_usize(immediate)?;
+    cfg.block_instructions(cfg.blocks.get(&target)?)
+        .first()
+        .filter(|i| i.opcode == opcode::JUMPDEST)
+        .map(|_| target)
+}
+
+pub(super) fn be_usize(bytes: &[u8]) -> Option<usize> {
+    let significant = bytes.iter().skip_while(|b| **b == 0);
+    if significant.clone().count() > core::mem::size_of::<usize>() {
+        return None;
+    }
+    Some(significant.fold(0, |acc, b| (acc << 8) | *b as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn dispatcher_blocks() {
+        let code = assemble(
+            "PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR
+             DUP1 PUSH4 0x3ccfd60b EQ PUSH @withdraw JUMPI
+             DUP1 PUSH4 0xd0e30db0 EQ PUSH @deposit JUMPI
+             PUSH1 0 DUP1 REVERT
+             withdraw: JUMPDEST PUSH @ret PUSH @internal JUMP
+             ret: JUMPDEST STOP
+             deposit: JUMPDEST CALLVALUE POP
+             internal: JUMPDEST JUMP",
+        )
+        .unwrap();
+        let cfg = Cfg::from_slice(&code);
+        let entries = cfg.function_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].selector, [0x3c, 0xcf, 0xd6, 0x0b]);
+        assert_eq!(entries[1].selector, [0xd0, 0xe3, 0x0d, 0xb0]);
+
+        let withdraw = &cfg.blocks[&entries[0].pc];
+        let ret = cfg.block_at(withdraw.end).unwrap().start;
+        let Terminator::Jump {
+            target: Some(internal),
+        } = withdraw.terminator
+        else {
+            panic!("withdraw should jump statically");
+        };
+        assert!(cfg.blocks[&internal].has_dynamic_jump());
+
+        let deposit = &cfg.blocks[&entries[1].pc];
+        assert_eq!(
+            deposit.terminator,
+            Terminator::Fallthrough { next: internal }
+        );
+        assert!(cfg.reachable_from(0).contains(&internal));
+        assert!(!cfg.reachable_from(0).contains(&ret));
+        assert_eq!(cfg.predecessors()[&internal].len(), 2);
+        assert!(cfg.to_dot().contains("fn 0xd0e30db0"));
+    }
+
+    #[test]
+    fn short_selectors() {
+        let code = assemble(
+            "PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR
+             DUP1 PUSH3 0xf55d9d EQ PUSH @destroy JUMPI
+             PUSH1 0x2a DUP2 EQ PUSH @answer JUMPI
+             DUP1 PUSH4 0x3ccfd60b EQ PUSH @withdraw JUMPI
+             PUSH1 0 DUP1 REVERT
+             destroy: JUMPDEST CALLVALUE PUSH1 1 DUP2 EQ PUSH @answer JUMPI STOP
+             answer: JUMPDEST STOP
+             withdraw: JUMPDEST STOP",
+        )
+        .unwrap();
+        let cfg = Cfg::from_slice(&code);
+        let selectors: Vec<_> = cfg
+            .function_entries()
+            .iter()
+            .map(|entry| entry.selector)
+            .collect();
+        // the comparison in a function is not a selector
+        assert_eq!(
+            selectors,
+            [
+                [0x00, 0xf5, 0x5d, 0x9d],
+                [0x00, 0x00, 0x00, 0x2a],
+                [0x3c, 0xcf, 0xd6, 0x0b]
+            ]
+        );
+    }
+
+    #[test]
+    fn silly_bank() {
+        let code = super::super::tests::SILLY_BANK;
+        let cfg = Cfg::new(&Bytecode::new_raw(code.to_vec().into()));
+        let end = without_metadata(code).len();
+        assert_eq!(end, code.len() - 0x35);
+        let last = cfg.instructions.last().unwrap();
+        assert_eq!((last.pc, last.opcode), (end - 1, opcode::INVALID));
+        assert!(cfg.blocks.values().all(|block| block.end <= end));
+        let selectors: Vec<_> = cfg
+            .function_entries()
+            .iter()
+            .map(|entry| entry.selector)
+            .collect();
+        assert_eq!(
+            selectors,
+            [
+                [0x27, 0xe2, 0x35, 0xe3],
+                [0x3c, 0xcf, 0xd6, 0x0b],
+                [0xd0, 0xe3, 0x0d, 0xb0]
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_metadata() {
+        // a CBOR map of 3 bytes, then its length
+        let code = [0x00, 0xa1, 0x01, 0x02, 0x00, 0x03];
+        assert_eq!(without_metadata(&code), [0x00]);
+        let code = [0x60, 0x01, 0x60, 0x02];
+        assert_eq!(without_metadata(&code), code);
+    }
+}
@@ -0,0 +1,417 @@
+//! Cheap static detectors of vulnerability patterns in runtime bytecode.
+//!
+//! Every function of the dispatcher is walked path by path with an abstract stack that only
+//! knows where values come from: the calldata, `CALLER`, `ORIGIN` or a constant. Memory and
+//! storage are not tracked, so `balances[msg.sender]` is not taken for a check of the caller
+//! while `msg.sender == owner` is. Jumps to constants on the stack are followed, so internal
+//! functions are walked with their caller.
+//!
+//! Findings are hints for where to place defender checks and which calls the attacker should
+//! try first, they are neither sound nor complete.
+
+use super::cfg::{be_usize, Cfg, Terminator};
+use crate::opcode;
+use crate::primitives::Bytecode;
+use alloc::{collections::BTreeSet, vec::Vec};
+
+/// Max number of blocks visited from an entry, over all paths.
+const MAX_VISITED_BLOCKS: usize = 4096;
+
+/// Calls forwarding at most this much gas cannot write state in the callee.
+const STIPEND: usize = 2300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Detector {
+    /// `SSTORE` after a `CALL` at pc `call` on the same path, the callee can reenter before
+    /// the state is updated.
+    StateWriteAfterCall { call: usize },
+    /// The target or the value of a `CALL` comes from the calldata.
+    UserControlledCall { target: bool, value: bool },
+    /// `DELEGATECALL` to an address from the calldata.
+    DelegatecallToCalldata,
+    /// `SELFDESTRUCT` on a path that does not check `CALLER`.
+    UnguardedSelfdestruct,
+    /// Branch on `ORIGIN`.
+    OriginAuth,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Finding {
+    pub detector: Detector,
+    /// Pc of the flagged instruction.
+    pub pc: usize,
+    /// Function of the dispatcher the instruction is reached from, `None` outside of them.
+    pub selector: Option<[u8; 4]>,
+    pub severity: Severity,
+}
+
+/// Where a value may come from.
+type Taint = u8;
+const CALLDATA: Taint = 1;
+const CALLER: Taint = 1 << 1;
+const ORIGIN: Taint = 1 << 2;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+struct Value {
+    taint: Taint,
+    constant: Option<usize>,
+    /// Offset of the calldata word the value is, masked or not.
+    calldata: Option<usize>,
+    /// Offset of the calldata word the value compares for equality with a value not from the
+    /// calldata.
+    equals: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct State {
+    /// Start of the block to walk.
+    pc: usize,
+    stack: Vec<Value>,
+    /// Taint of the conditions branched on so far.
+    checked: Taint,
+    /// Offsets of the calldata words branched on for equality with values not from the calldata,
+    /// as in `require(target == address(this))`. They are no longer controlled by the caller.
+    pinned: BTreeSet<usize>,
+    /// Last `CALL` on the path that forwards more than the stipend.
+    call: Option<usize>,
+}
+
+/// Run every detector over the bytecode.
+pub fn detect(bytecode: &Bytecode) -> Vec<Finding> {
+    detect_in_cfg(&Cfg::new(bytecode))
+}
+
+/// Run every detector over already built CFG, findings are sorted and deduplicated.
+pub fn detect_in_cfg(cfg: &Cfg) -> Vec<Finding> {
+    let entries = cfg.function_entries();
+    let entry_pcs: BTreeSet<usize> = entries.iter().map(|entry| entry.pc).collect();
+    let mut findings = BTreeSet::new();
+    // the dispatcher and the fallback, without the functions
+    walk(cfg, 0, None, &entry_pcs, &mut findings);
+    for entry in &entries {
+        walk(
+            cfg,
+            entry.pc,
+            Some(entry.selector),
+            &entry_pcs,
+            &mut findings,
+        );
+    }
+    findings.into_iter().collect()
+}
+
+fn walk(
+    cfg: &Cfg,
+    start: usize,
+    selector: Option<[u8; 4]>,
+    entry_pcs: &BTreeSet<usize>,
+    findings: &mut BTreeSet<Finding>,
+) {
+    let mut report = |detector, pc, checked: Taint, high| {
+        let severity = if checked & CALLER != 0 {
+            Severity::Low
+        } else if high {
+            Severity::High
+        } else {
+            Severity::Medium
+        };
+        findings.insert(Finding {
+            detector,
+            pc,
+            selector,
+            severity,
+        });
+    };
+    let mut visited = BTreeSet::new();
+    let mut queue = Vec::from([State {
+        pc: start,
+        stack: Vec::new(),
+        checked: 0,
+        pinned: BTreeSet::new(),
+        call: None,
+    }]);
+    while let Some(mut state) = queue.pop() {
+        if visited.len() >= MAX_VISITED_BLOCKS || !visited.insert(state.clone()) {
+            continue;
+        }
+        let Some(block) = cfg.blocks.get(&state.pc) else {
+            continue;
+        };
+        let stack = &mut state.stack;
+        let mut next = None;
+        for instruction in cfg.block_instructions(block) {
+            let op = instruction.opcode;
+            match op {
+                opcode::PUSH0..=opcode::PUSH32 => stack.push(Value {
+                    constant: be_usize(&instruction.immediate),
+                    ..Default::default()
+                }),
+                opcode::DUP1..=opcode::DUP16 => {
+                    let value = peek(stack, (op - opcode::DUP1) as usize);
+                    stack.push(value);
+                }
+                opcode::SWAP1..=opcode::SWAP16 => {
+                    let depth = (op - opcode::SWAP1) as usize + 1;
+                    while stack.len() <= depth {
+                        stack.insert(0, Value::default());
+                    }
+                    let top = stack.len() - 1;
+                    stack.swap(top, top - depth);
+                }
+                opcode::CALL | opcode::CALLCODE => {
+                    let gas = peek(stack, 0);
+                    let target = peek(stack, 1);
+                    let value = peek(stack, 2);
+                    let target = controlled(target, &state.pinned);
+                    let value = controlled(value, &state.pinned);
+                    if target || value {
+                        let detector = Detector::UserControlledCall { target, value };
+                        report(detector, instruction.pc, state.checked, target && value);
+                    }
+                    if gas.constant.is_none_or(|gas| gas > STIPEND) {
+                        state.call = Some(instruction.pc);
+                    }
+                }
+                opcode::DELEGATECALL if controlled(peek(stack, 1), &state.pinned) => {
+                    let detector = Detector::DelegatecallToCalldata;
+                    report(detector, instruction.pc, state.checked, true);
+                }
+                opcode::SSTORE => {
+                    if let Some(call) = state.call {
+                        let detector = Detector::StateWriteAfterCall { call };
+                        report(detector, instruction.pc, state.checked, true);
+                    }
+                }
+                opcode::SELFDESTRUCT if state.checked & CALLER == 0 => {
+                    let detector = Detector::UnguardedSelfdestruct;
+                    report(detector, instruction.pc, state.checked, true);
+                }
+                opcode::JUMP => {
+                    next = peek(stack, 0).constant;
+                }
+                opcode::JUMPI => {
+                    let condition = peek(stack, 1);
+                    if condition.taint & ORIGIN != 0 {
+                        // `tx.origin == msg.sender` keeps contracts out, `tx.origin == owner`
+                        // lets any contract the owner calls act for it
+                        let high = condition.taint & CALLER == 0;
+                        report(Detector::OriginAuth, instruction.pc, state.checked, high);
+                    }
+                    next = peek(stack, 0).constant;
+                    state.pinned.extend(condition.equals);
+                    // `tx.origin == msg.sender` does not tell who the caller is
+                    state.checked |= if condition.taint & ORIGIN != 0 {
+                        condition.taint & !CALLER
+                    } else {
+                        condition.taint
+                    };
+                }
+                _ => {}
+            }
+            if matches!(op, opcode::PUSH0..=opcode::SWAP16) {
+                continue;
+            }
+            let (inputs, outputs) = stack_effect(op);
+            let mut popped = [Value::default(); 7];
+            for input in popped.iter_mut().take(inputs) {
+                *input = stack.pop().unwrap_or_default();
+            }
+            if outputs > 0 {
+                stack.push(output(op, &popped[..inputs]));
+            }
+        }
+        if stack.len() > crate::STACK_LIMIT as usize {
+            continue;
+        }
+        let jumpdest = |target: Option<usize>| {
+            target.filter(|target| {
+                cfg.blocks.contains_key(target)
+                    && cfg.instructions[cfg.blocks[target].instructions.start].opcode
+                        == opcode::JUMPDEST
+            })
+        };
+        let successors = match block.terminator {
+            Terminator::Fallthrough { next } => [Some(next), None],
+            Terminator::Jump { target } => [jumpdest(target.or(next)), None],
+            Terminator::JumpI { target, next: fall } => [jumpdest(target.or(next)), Some(fall)],
+            Terminator::Halt { .. } => [None, None],
+        };
+        for pc in successors.into_iter().flatten() {
+            if pc == start || !entry_pcs.contains(&pc) {
+                queue.push(State {
+                    pc,
+                    ..state.clone()
+                });
+            }
+        }
+    }
+}
+
+/// Value pushed by `op`, other than `PUSH`, `DUP` and `SWAP`, on `inputs` popped from the top.
+fn output(op: u8, inputs: &[Value]) -> Value {
+    let taint = inputs.iter().fold(0, |taint, input| taint | input.taint);
+    let tainted = |taint| Value {
+        taint,
+        ..Default::default()
+    };
+    match op {
+        opcode::CALLDATALOAD => Value {
+            calldata: inputs[0].constant,
+            ..tainted(CALLDATA)
+        },
+        opcode::CALLER => tainted(CALLER),
+        opcode::ORIGIN => tainted(ORIGIN),
+        // masks clean addresses
+        opcode::AND => {
+            let (a, b) = (inputs[0], inputs[1]);
+            let calldata = match (a.constant, b.constant) {
+                (Some(_), _) => b.calldata,
+                (_, Some(_)) => a.calldata,
+                _ => None,
+            };
+            Value {
+                calldata,
+                ..tainted(taint)
+            }
+        }
+        opcode::EQ => {
+            let (a, b) = (inputs[0], inputs[1]);
+            let equals = match (a.calldata, b.calldata) {
+                (Some(offset), _) if b.taint & CALLDATA == 0 => Some(offset),
+                (_, Some(offset)) if a.taint & CALLDATA == 0 => Some(offset),
+                _ => None,
+            };
+            Value {
+                equals,
+                ..tainted(taint)
+            }
+        }
+        opcode::ISZERO => Value {
+            equals: inputs[0].equals,
+            ..tainted(taint)
+        },
+        opcode::ADD..=opcode::SAR => tainted(taint),
+        _ => Value::default(),
+    }
+}
+
+/// The value comes from the calldata and no check pins it.
+fn controlled(value: Value, pinned: &BTreeSet<usize>) -> bool {
+    value.taint & CALLDATA != 0
+        && !value
+            .calldata
+            .is_some_and(|offset| pinned.contains(&offset))
+}
+
+/// `n`-th value from the top, unknown below the bottom of the stack.
+fn peek(stack: &[Value], n: usize) -> Value {
+    stack
+        .len()
+        .checked_sub(n + 1)
+        .map(|index| stack[index])
+        .unwrap_or_default()
+}
+
+/// Values popped and pushed by an opcode other than `PUSH`, `DUP` and `SWAP`.
+fn stack_effect(op: u8) -> (usize, usize) {
+    match op {
+        opcode::ADDMOD | opcode::MULMOD => (3, 1),
+        opcode::ISZERO | opcode::NOT => (1, 1),
+        opcode::ADD..=opcode::SAR | opcode::KECCAK256 => (2, 1),
+        opcode::BALANCE
+        | opcode::CALLDATALOAD
+        | opcode::EXTCODESIZE
+        | opcode::EXTCODEHASH
+        | opcode::BLOCKHASH
+        | opcode::MLOAD
+        | opcode::SLOAD
+        | opcode::TLOAD => (1, 1),
+        opcode::CALLDATACOPY | opcode::CODECOPY | opcode::RETURNDATACOPY | opcode::MCOPY => (3, 0),
+        opcode::EXTCODECOPY => (4, 0),
+        opcode::ADDRESS..=opcode::BASEFEE | opcode::PC | opcode::MSIZE | opcode::GAS => (0, 1),
+        opcode::POP | opcode::JUMP | opcode::SELFDESTRUCT => (1, 0),
+        opcode::MSTORE
+        | opcode::MSTORE8
+        | opcode::SSTORE
+        | opcode::TSTORE
+        | opcode::JUMPI
+        | opcode::RETURN
+        | opcode::REVERT => (2, 0),
+        opcode::LOG0..=opcode::LOG4 => ((op - opcode::LOG0) as usize + 2, 0),
+        opcode::CREATE => (3, 1),
+        opcode::CREATE2 => (4, 1),
+        opcode::CALL | opcode::CALLCODE => (7, 1),
+        opcode::DELEGATECALL | opcode::STATICCALL => (6, 1),
+        _ => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use alloc::{collections::BTreeMap, format};
+
+    fn findings(code: &str) -> BTreeMap<Detector, Severity> {
+        let code = assemble(code).unwrap();
+        detect(&Bytecode::new_raw(code))
+            .into_iter()
+            .map(|finding| (finding.detector, finding.severity))
+            .collect()
+    }
+
+    #[test]
+    fn flags_patterns_unless_caller_checked() {
+        let dispatcher = "PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR
+             DUP1 PUSH4 0x3ccfd60b EQ PUSH @withdraw JUMPI
+             DUP1 PUSH4 0x00f55d9d EQ PUSH @destroy JUMPI
+             PUSH1 0 DUP1 REVERT";
+        // balances[msg.sender] is paid out before it is cleared, through an internal function
+        let withdraw = "withdraw: JUMPDEST PUSH @cleared CALLER PUSH1 0 MSTORE
+             PUSH1 0x20 PUSH1 0 KECCAK256 SLOAD PUSH @send JUMP
+             cleared: JUMPDEST PUSH1 0 PUSH1 0x20 PUSH1 0 KECCAK256 SSTORE STOP
+             send: JUMPDEST PUSH1 0 DUP1 DUP1 DUP1 DUP5 CALLER GAS CALL POP POP JUMP";
+        let destroy =
+            |check: &str| format!("destroy: JUMPDEST {check} PUSH1 4 CALLDATALOAD SELFDESTRUCT");
+        let unguarded = findings(&format!("{dispatcher} {withdraw} {}", destroy("")));
+        assert_eq!(unguarded.len(), 2, "{unguarded:?}");
+        assert_eq!(unguarded[&Detector::UnguardedSelfdestruct], Severity::High);
+        assert!(matches!(
+            unguarded.iter().next(),
+            Some((Detector::StateWriteAfterCall { .. }, Severity::High))
+        ));
+
+        let owner = "PUSH1 1 SLOAD CALLER EQ PUSH @owner JUMPI PUSH1 0 DUP1 REVERT owner: JUMPDEST";
+        let guarded = findings(&format!("{dispatcher} {withdraw} {}", destroy(owner)));
+        assert_eq!(guarded.len(), 1, "{guarded:?}");
+
+        let origin =
+            "PUSH1 1 SLOAD ORIGIN EQ PUSH @owner JUMPI PUSH1 0 DUP1 REVERT owner: JUMPDEST";
+        let by_origin = findings(&format!("{dispatcher} {withdraw} {}", destroy(origin)));
+        assert_eq!(by_origin[&Detector::OriginAuth], Severity::High);
+        assert!(by_origin.contains_key(&Detector::UnguardedSelfdestruct));
+
+        // transfer with the stipend cannot reenter, the target and value come from calldata
+        let calls = findings(
+            "PUSH1 0 DUP1 DUP1 DUP1 PUSH1 0x24 CALLDATALOAD PUSH1 4 CALLDATALOAD PUSH2 0x08fc CALL
+             PUSH1 1 PUSH1 0 SSTORE
+             PUSH1 0 DUP1 DUP1 DUP1 PUSH1 4 CALLDATALOAD GAS DELEGATECALL STOP",
+        );
+        assert_eq!(calls.len(), 2, "{calls:?}");
+        assert_eq!(
+            calls[&Detector::UserControlledCall {
+                target: true,
+                value: true
+            }],
+            Severity::High
+        );
+        assert_eq!(calls[&Detector::DelegatecallToCalldata], Severity::High);
+    }
+}
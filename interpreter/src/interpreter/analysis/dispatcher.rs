@@ -0,0 +1,184 @@
+//! Recovery of the external functions of a Solidity contract from runtime bytecode.
+//!
+//! Used to build an action space for contracts we have no ABI for.
+
+use super::cfg::{Cfg, Terminator};
+use crate::asm::Instruction;
+use crate::opcode;
+use crate::primitives::Bytecode;
+use alloc::{collections::BTreeSet, vec::Vec};
+
+/// Max number of blocks visited from a function entry when looking for calldata checks.
+const MAX_VISITED_BLOCKS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DispatchedFunction {
+    pub selector: [u8; 4],
+    /// Jump destination taken by the dispatcher for this selector.
+    pub pc: usize,
+    /// No `CALLVALUE` check guards the entry.
+    pub payable: bool,
+    /// Size of the argument head found in `CALLDATASIZE` checks of the ABI decoder.
+    /// It is a lower bound: zero when the function takes no arguments or no check was found.
+    pub args_size: usize,
+}
+
+impl DispatchedFunction {
+    /// Minimal calldata length accepted by the function, selector included.
+    pub fn calldata_size(&self) -> usize {
+        4 + self.args_size
+    }
+}
+
+/// Recover dispatched functions of the bytecode, the solc metadata at its end is skipped.
+pub fn recover_dispatcher(bytecode: &Bytecode) -> Vec<DispatchedFunction> {
+    recover_from_cfg(&Cfg::new(bytecode))
+}
+
+/// Recover dispatched functions from already built CFG, in dispatcher order.
+pub fn recover_from_cfg(cfg: &Cfg) -> Vec<DispatchedFunction> {
+    let entries = cfg.function_entries();
+    let entry_pcs: BTreeSet<usize> = entries.iter().map(|entry| entry.pc).collect();
+
+    // solc checks callvalue once before the dispatcher when no function is payable.
+    let first_selector = entries.first().map_or(0, |entry| entry.index);
+    let prelude_checks_value = checks_callvalue(&cfg.instructions[..first_selector]);
+
+    entries
+        .iter()
+        .map(|entry| {
+            let entry_block = &cfg.blocks[&entry.pc];
+            let payable =
+                !prelude_checks_value && !checks_callvalue(cfg.block_instructions(entry_block));
+            DispatchedFunction {
+                selector: entry.selector,
+                pc: entry.pc,
+                payable,
+                args_size: args_size(cfg, entry.pc, &entry_pcs),
+            }
+        })
+        .collect()
+}
+
+/// `CALLVALUE (DUP1)? ISZERO`
+fn checks_callvalue(instructions: &[Instruction]) -> bool {
+    instructions.windows(3).any(|window| {
+        window[0].opcode == opcode::CALLVALUE
+            && (window[1].opcode == opcode::ISZERO
+                || (window[1].opcode == opcode::DUP1 && window[2].opcode == opcode::ISZERO))
+    })
+}
+
+/// Largest constant compared against the calldata length in blocks statically reachable
+/// from the entry, only if the calldata size is read on the way.
+fn args_size(cfg: &Cfg, entry: usize, entry_pcs: &BTreeSet<usize>) -> usize {
+    let mut visited = BTreeSet::new();
+    let mut queue = Vec::from([entry]);
+    let mut reads_size = false;
+    let mut size = 0;
+    while let Some(pc) = queue.pop() {
+        if visited.len() >= MAX_VISITED_BLOCKS || !visited.insert(pc) {
+            continue;
+        }
+        let Some(block) = cfg.blocks.get(&pc) else {
+            continue;
+        };
+        let instructions = cfg.block_instructions(block);
+        reads_size |= instructions
+            .iter()
+            .any(|i| i.opcode == opcode::CALLDATASIZE);
+        for (index, instruction) in instructions.iter().enumerate() {
+            if let Some(bound) = length_check(instruction, &instructions[index + 1..]) {
+                size = size.max(bound);
+            }
+        }
+        if matches!(block.terminator, Terminator::Halt { .. }) {
+            continue;
+        }
+        queue.extend(
+            block
+                .successors()
+                .filter(|successor| !entry_pcs.contains(successor)),
+        );
+    }
+    if reads_size {
+        size
+    } else {
+        0
+    }
+}
+
+/// `PUSHn k (DUPn|SWAPn){0,3} (SUB)? (LT|GT|SLT|SGT)` with `k` a whole number of words.
+fn length_check(push: &Instruction, rest: &[Instruction]) -> Option<usize> {
+    if push.immediate.is_empty() || push.immediate.len() > 4 {
+        return None;
+    }
+    let bound = push
+        .immediate
+        .iter()
+        .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    if bound == 0 || bound % 32 != 0 {
+        return None;
+    }
+    let mut rest = rest
+        .iter()
+        .map(|i| i.opcode)
+        .skip_while(|op| (opcode::DUP1..=opcode::SWAP16).contains(op));
+    let mut op = rest.next()?;
+    if op == opcode::SUB {
+        op = rest.next()?;
+    }
+    matches!(op, opcode::LT | opcode::GT | opcode::SLT | opcode::SGT).then_some(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    #[test]
+    fn payable_and_arguments() {
+        let code = assemble(
+            "PUSH1 0x80 PUSH1 0x40 MSTORE
+             PUSH1 4 CALLDATASIZE LT PUSH @fallback JUMPI
+             PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR
+             DUP1 PUSH4 0x27e235e3 EQ PUSH @balances JUMPI
+             DUP1 PUSH4 0xd0e30db0 EQ PUSH @deposit JUMPI
+             fallback: JUMPDEST PUSH1 0 DUP1 REVERT
+             balances: JUMPDEST CALLVALUE DUP1 ISZERO PUSH @nonpayable JUMPI PUSH1 0 DUP1 REVERT
+             nonpayable: JUMPDEST POP PUSH1 4 DUP1 CALLDATASIZE SUB DUP2 ADD SWAP1 PUSH @decode JUMP
+             deposit: JUMPDEST STOP
+             decode: JUMPDEST PUSH1 0 PUSH1 0x20 DUP3 DUP5 SUB SLT ISZERO PUSH @ok JUMPI
+             PUSH1 0 DUP1 REVERT
+             ok: JUMPDEST JUMP",
+        )
+        .unwrap();
+        let functions = recover_dispatcher(&Bytecode::new_raw(code));
+        assert_eq!(functions.len(), 2);
+        assert_eq!(functions[0].selector, [0x27, 0xe2, 0x35, 0xe3]);
+        assert!(!functions[0].payable);
+        assert_eq!(functions[0].calldata_size(), 36);
+        assert_eq!(functions[1].selector, [0xd0, 0xe3, 0x0d, 0xb0]);
+        assert!(functions[1].payable);
+        assert_eq!(functions[1].args_size, 0);
+    }
+
+    #[test]
+    fn silly_bank() {
+        let code = super::super::tests::SILLY_BANK;
+        let functions = recover_dispatcher(&Bytecode::new_raw(code.to_vec().into()));
+        let recovered: Vec<_> = functions
+            .iter()
+            .map(|function| (function.selector, function.payable, function.args_size))
+            .collect();
+        // balances(address), withdraw() and deposit()
+        assert_eq!(
+            recovered,
+            [
+                ([0x27, 0xe2, 0x35, 0xe3], false, 32),
+                ([0x3c, 0xcf, 0xd6, 0x0b], false, 0),
+                ([0xd0, 0xe3, 0x0d, 0xb0], true, 0)
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::analysis::{to_analysed, BytecodeLocked};
-use crate::primitives::{Bytecode, Bytes, B160, U256};
+use crate::primitives::{Bytecode, Bytes, SpecId, B160, U256};
 use crate::CallContext;
 use revm_primitives::{Env, TransactTo};
 
@@ -19,8 +19,17 @@ pub struct Contract {
 }
 
 impl Contract {
-    pub fn new(input: Bytes, bytecode: Bytecode, address: B160, caller: B160, value: U256) -> Self {
-        let bytecode = to_analysed(bytecode).try_into().expect("it is analyzed");
+    pub fn new(
+        input: Bytes,
+        bytecode: Bytecode,
+        address: B160,
+        caller: B160,
+        value: U256,
+        spec_id: SpecId,
+    ) -> Self {
+        let bytecode = to_analysed(bytecode, spec_id)
+            .try_into()
+            .expect("it is analyzed");
 
         Self {
             input,
@@ -43,6 +52,7 @@ impl Contract {
             contract_address,
             env.tx.caller,
             env.tx.value,
+            env.cfg.spec_id,
         )
     }
 
@@ -50,13 +60,19 @@ impl Contract {
         self.bytecode.jump_map().is_valid(possition)
     }
 
-    pub fn new_with_context(input: Bytes, bytecode: Bytecode, call_context: &CallContext) -> Self {
+    pub fn new_with_context(
+        input: Bytes,
+        bytecode: Bytecode,
+        call_context: &CallContext,
+        spec_id: SpecId,
+    ) -> Self {
         Self::new(
             input,
             bytecode,
             call_context.address,
             call_context.caller,
             call_context.apparent_value,
+            spec_id,
         )
     }
 }
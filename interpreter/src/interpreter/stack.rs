@@ -4,7 +4,7 @@ use crate::{alloc::vec::Vec, InstructionResult};
 pub const STACK_LIMIT: usize = 1024;
 
 /// EVM stack.
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Stack {
     data: Vec<U256>,
@@ -28,6 +28,16 @@ impl std::fmt::Display for Stack {
     }
 }
 
+impl Clone for Stack {
+    /// The clone has the full capacity as well, the stack of a suspended interpreter is cloned
+    /// by the snapshots of a game.
+    fn clone(&self) -> Self {
+        let mut data = Vec::with_capacity(STACK_LIMIT);
+        data.extend_from_slice(&self.data);
+        Self { data }
+    }
+}
+
 impl Default for Stack {
     fn default() -> Self {
         Self::new()
@@ -86,10 +96,10 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop_unsafe(&mut self) -> U256 {
-        let mut len = self.data.len();
-        len -= 1;
+        let len = self.data.len() - 1;
+        let pop = *self.data.get_unchecked(len);
         self.data.set_len(len);
-        *self.data.get_unchecked(len)
+        pop
     }
 
     #[inline(always)]
@@ -137,13 +147,13 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop2_unsafe(&mut self) -> (U256, U256) {
-        let mut len = self.data.len();
-        len -= 2;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 2;
+        let pop = (
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline(always)]
@@ -152,14 +162,14 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop3_unsafe(&mut self) -> (U256, U256, U256) {
-        let mut len = self.data.len();
-        len -= 3;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 3;
+        let pop = (
             *self.data.get_unchecked(len + 2),
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline(always)]
@@ -168,15 +178,15 @@ impl Stack {
     /// # Safety
     /// The caller is responsible to check length of array
     pub unsafe fn pop4_unsafe(&mut self) -> (U256, U256, U256, U256) {
-        let mut len = self.data.len();
-        len -= 4;
-        self.data.set_len(len);
-        (
+        let len = self.data.len() - 4;
+        let pop = (
             *self.data.get_unchecked(len + 3),
             *self.data.get_unchecked(len + 2),
             *self.data.get_unchecked(len + 1),
             *self.data.get_unchecked(len),
-        )
+        );
+        self.data.set_len(len);
+        pop
     }
 
     #[inline]
@@ -222,8 +232,10 @@ impl Stack {
             Some(InstructionResult::StackOverflow)
         } else {
             // Safety: check for out of bounds is done above and it makes this safe to do.
+            // Capacity is STACK_LIMIT so writing one past the length is in bounds of the allocation.
             unsafe {
-                *self.data.get_unchecked_mut(len) = *self.data.get_unchecked(len - N);
+                let ptr = self.data.as_mut_ptr();
+                ptr.add(len).write(*ptr.add(len - N));
                 self.data.set_len(len + 1);
             }
             None
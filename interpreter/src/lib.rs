@@ -1,11 +1,13 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+pub mod asm;
 pub mod gas;
 mod host;
 pub mod inner_models;
 pub mod instruction_result;
 mod instructions;
 mod interpreter;
+pub mod symbolic;
 
 extern crate alloc;
 extern crate core;
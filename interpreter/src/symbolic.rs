@@ -0,0 +1,770 @@
+//! Symbolic execution of a single call frame.
+//!
+//! Calldata, callvalue and storage are symbolic, everything else is taken from the environment.
+//! Paths fork at `JUMPI` on symbolic conditions and every explored path is reported with its
+//! constraints and a model found by the built-in [`Solver`]. Calldata of the models can seed
+//! attacker exploration.
+//!
+//! Approximations: symbolic memory offsets, sizes and jump destinations are fixed to their value
+//! in the current model (with an equality constraint), external calls succeed without being
+//! executed and return no data, other accounts have no balance or code, and gas is not metered.
+
+mod expr;
+mod solver;
+
+pub use expr::{apply, arity, Expr, Model, Var};
+pub use solver::{Constraint, Solver, MAX_CALLDATA_SIZE};
+
+use crate::interpreter::analysis::to_analysed;
+use crate::opcode;
+use crate::primitives::{hex, Bytecode, Bytes, Env, SpecId, B160, B256, U256};
+use crate::{BytecodeLocked, InstructionResult, STACK_LIMIT};
+use alloc::{sync::Arc, vec, vec::Vec};
+use core::fmt;
+
+/// Memory accesses past this size end the path with `MemoryOOG`.
+pub const MEMORY_LIMIT: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SymbolicConfig {
+    /// Max number of reported paths.
+    pub max_paths: usize,
+    /// Max number of instructions of one path, longer paths end with `OutOfGas`.
+    pub max_steps: usize,
+    pub solver: Solver,
+}
+
+impl Default for SymbolicConfig {
+    fn default() -> Self {
+        Self {
+            max_paths: 64,
+            max_steps: 10_000,
+            solver: Solver::default(),
+        }
+    }
+}
+
+/// Explored path of the frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    pub result: InstructionResult,
+    /// Pc of the instruction that ended the path.
+    pub pc: usize,
+    pub constraints: Vec<Constraint>,
+    /// Inputs that follow this path.
+    pub model: Model,
+    /// Storage written on the path as `(slot, value)`, in write order.
+    pub storage: Vec<(Arc<Expr>, Arc<Expr>)>,
+}
+
+impl Path {
+    pub fn calldata(&self) -> Bytes {
+        self.model.calldata()
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:?} at {:04x}", self.result, self.pc)?;
+        writeln!(f, "  calldata  0x{}", hex::encode(&self.model.calldata))?;
+        f.write_str("  callvalue ")?;
+        expr::write_word(f, self.model.callvalue)?;
+        writeln!(f)?;
+        for constraint in &self.constraints {
+            writeln!(f, "  {constraint}")?;
+        }
+        for (slot, value) in &self.storage {
+            writeln!(f, "  sstore {slot} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Memory with symbolic bytes.
+#[derive(Debug, Clone, Default)]
+struct SymbolicMemory {
+    /// Concrete bytes, zero where the byte is symbolic.
+    bytes: Vec<u8>,
+    /// Symbolic bytes as byte index of a word.
+    symbolic: Vec<Option<(Arc<Expr>, u8)>>,
+}
+
+impl SymbolicMemory {
+    fn resize(&mut self, offset: usize, len: usize) -> Result<(), InstructionResult> {
+        if len == 0 {
+            return Ok(());
+        }
+        let end = offset
+            .checked_add(len)
+            .filter(|end| *end <= MEMORY_LIMIT)
+            .ok_or(InstructionResult::MemoryOOG)?;
+        if end > self.bytes.len() {
+            let end = end.div_ceil(32) * 32;
+            self.bytes.resize(end, 0);
+            self.symbolic.resize(end, None);
+        }
+        Ok(())
+    }
+
+    fn store_byte(&mut self, offset: usize, word: &Arc<Expr>, index: u8) {
+        match word.as_const() {
+            Some(value) => {
+                self.bytes[offset] = value.byte(31 - index as usize);
+                self.symbolic[offset] = None;
+            }
+            None => {
+                self.bytes[offset] = 0;
+                self.symbolic[offset] = Some((word.clone(), index));
+            }
+        }
+    }
+
+    fn store_word(&mut self, offset: usize, word: &Arc<Expr>) {
+        for index in 0..32 {
+            self.store_byte(offset + index, word, index as u8);
+        }
+    }
+
+    fn store_slice(&mut self, offset: usize, data: &[u8]) {
+        self.bytes[offset..offset + data.len()].copy_from_slice(data);
+        self.symbolic[offset..offset + data.len()].fill(None);
+    }
+
+    fn load_word(&self, offset: usize) -> Arc<Expr> {
+        let symbolic = &self.symbolic[offset..offset + 32];
+        if symbolic.iter().all(Option::is_none) {
+            let mut word = [0u8; 32];
+            word.copy_from_slice(&self.bytes[offset..offset + 32]);
+            return Expr::constant(U256::from_be_bytes(word));
+        }
+        if let Some((first, _)) = &symbolic[0] {
+            let whole = symbolic.iter().enumerate().all(|(index, byte)| {
+                matches!(byte, Some((word, i)) if Arc::ptr_eq(word, first) && *i as usize == index)
+            });
+            if whole {
+                return first.clone();
+            }
+        }
+        // mixed word, assembled byte by byte
+        let mut concrete = [0u8; 32];
+        let mut word = Vec::new();
+        for (index, byte) in symbolic.iter().enumerate() {
+            match byte {
+                None => concrete[index] = self.bytes[offset + index],
+                Some((source, i)) => {
+                    let byte = Expr::op(
+                        opcode::BYTE,
+                        vec![Expr::constant(U256::from(*i)), source.clone()],
+                    );
+                    let shift = Expr::constant(U256::from(8 * (31 - index)));
+                    word.push(Expr::op(opcode::SHL, vec![shift, byte]));
+                }
+            }
+        }
+        word.into_iter().fold(
+            Expr::constant(U256::from_be_bytes(concrete)),
+            |acc, byte| Expr::op(opcode::OR, vec![acc, byte]),
+        )
+    }
+
+    /// Words covering `len` bytes at `offset`, the last one is zero padded.
+    fn load(&mut self, offset: usize, len: usize) -> Result<Vec<Arc<Expr>>, InstructionResult> {
+        self.resize(offset, len)?;
+        let words = len.div_ceil(32);
+        self.resize(offset, words * 32)?;
+        let mut result: Vec<Arc<Expr>> = (0..words)
+            .map(|word| self.load_word(offset + 32 * word))
+            .collect();
+        let tail = len % 32;
+        if let (Some(last), true) = (result.last_mut(), tail != 0) {
+            let mask = !(U256::MAX >> (8 * tail));
+            *last = Expr::op(opcode::AND, vec![last.clone(), Expr::constant(mask)]);
+        }
+        Ok(result)
+    }
+
+    /// Copy `len` bytes within memory.
+    fn copy(&mut self, dst: usize, src: usize, len: usize) {
+        let bytes = self.bytes[src..src + len].to_vec();
+        let symbolic = self.symbolic[src..src + len].to_vec();
+        self.bytes[dst..dst + len].copy_from_slice(&bytes);
+        self.symbolic[dst..dst + len].clone_from_slice(&symbolic);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    pc: usize,
+    stack: Vec<Arc<Expr>>,
+    memory: SymbolicMemory,
+    storage: Vec<(Arc<Expr>, Arc<Expr>)>,
+    /// Transient storage writes, every slot starts at zero.
+    transient: Vec<(Arc<Expr>, Arc<Expr>)>,
+    constraints: Vec<Constraint>,
+    model: Model,
+    steps: usize,
+    /// Path ended before running, set for forks that take an invalid jump.
+    halted: Option<InstructionResult>,
+}
+
+impl State {
+    fn pop(&mut self) -> Result<Arc<Expr>, InstructionResult> {
+        self.stack.pop().ok_or(InstructionResult::StackUnderflow)
+    }
+
+    fn push(&mut self, value: Arc<Expr>) -> Result<(), InstructionResult> {
+        if self.stack.len() as u64 >= STACK_LIMIT {
+            return Err(InstructionResult::StackOverflow);
+        }
+        self.stack.push(value);
+        Ok(())
+    }
+
+    fn push_const(&mut self, value: U256) -> Result<(), InstructionResult> {
+        self.push(Expr::constant(value))
+    }
+
+    /// Value of the expression in the current model, the path is restricted to it.
+    fn concretize(&mut self, expr: Arc<Expr>) -> U256 {
+        if let Some(value) = expr.as_const() {
+            return value;
+        }
+        let value = expr.eval(&self.model);
+        self.constraints.push(Constraint {
+            pc: self.pc,
+            expr: Expr::op(opcode::EQ, vec![expr, Expr::constant(value)]),
+            taken: true,
+        });
+        value
+    }
+
+    /// Pop a memory offset and size. The offset is zero when the size is zero.
+    fn pop_range(&mut self) -> Result<(usize, usize), InstructionResult> {
+        let offset = self.pop()?;
+        let len = self.pop()?;
+        self.range(offset, len)
+    }
+
+    /// Pop the operands of a copy into memory: memory offset, source offset and size, the
+    /// memory offset is zero when the size is zero.
+    fn pop_copy(&mut self) -> Result<(usize, usize, usize), InstructionResult> {
+        let offset = self.pop()?;
+        let source = self.pop_usize()?;
+        let len = self.pop()?;
+        let (offset, len) = self.range(offset, len)?;
+        Ok((offset, source, len))
+    }
+
+    fn range(
+        &mut self,
+        offset: Arc<Expr>,
+        len: Arc<Expr>,
+    ) -> Result<(usize, usize), InstructionResult> {
+        let len = self.concretize(len);
+        if len == U256::ZERO {
+            return Ok((0, 0));
+        }
+        let offset = self.concretize(offset);
+        let limit = U256::from(MEMORY_LIMIT);
+        if offset > limit || len > limit {
+            return Err(InstructionResult::MemoryOOG);
+        }
+        Ok((offset.to::<usize>(), len.to::<usize>()))
+    }
+
+    fn pop_usize(&mut self) -> Result<usize, InstructionResult> {
+        let value = self.pop()?;
+        let value = self.concretize(value);
+        Ok(usize::try_from(value).unwrap_or(usize::MAX))
+    }
+
+    fn sload(&self, slot: Arc<Expr>) -> Arc<Expr> {
+        self.storage
+            .iter()
+            .rev()
+            .find(|(written, _)| *written == slot)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| Expr::var(Var::Storage(slot)))
+    }
+
+    fn tload(&self, slot: Arc<Expr>) -> Arc<Expr> {
+        self.transient
+            .iter()
+            .rev()
+            .find(|(written, _)| *written == slot)
+            .map(|(_, value)| value.clone())
+            .unwrap_or_else(|| Expr::constant(U256::ZERO))
+    }
+}
+
+/// Symbolic executor of a contract frame.
+#[derive(Debug)]
+pub struct SymbolicExecutor<'a> {
+    code: BytecodeLocked,
+    address: B160,
+    env: &'a Env,
+    config: SymbolicConfig,
+}
+
+impl<'a> SymbolicExecutor<'a> {
+    /// Executor of `bytecode` deployed at `address`. Caller and block come from `env`.
+    pub fn new(bytecode: Bytecode, address: B160, env: &'a Env, config: SymbolicConfig) -> Self {
+        let code = to_analysed(bytecode, env.cfg.spec_id)
+            .try_into()
+            .expect("it is analyzed");
+        Self {
+            code,
+            address,
+            env,
+            config,
+        }
+    }
+
+    /// Explore paths with all inputs starting at zero.
+    pub fn explore(&self) -> Vec<Path> {
+        self.explore_from(Model::default())
+    }
+
+    /// Explore paths, `seed` is where the solver starts (for example the current storage).
+    pub fn explore_from(&self, seed: Model) -> Vec<Path> {
+        let mut paths = Vec::new();
+        let mut pending = vec![State {
+            pc: 0,
+            stack: Vec::new(),
+            memory: SymbolicMemory::default(),
+            storage: Vec::new(),
+            transient: Vec::new(),
+            constraints: Vec::new(),
+            model: seed,
+            steps: 0,
+            halted: None,
+        }];
+        while let Some(mut state) = pending.pop() {
+            if paths.len() >= self.config.max_paths {
+                break;
+            }
+            let result = loop {
+                if let Some(result) = state.halted {
+                    break result;
+                }
+                if state.steps >= self.config.max_steps {
+                    break InstructionResult::OutOfGas;
+                }
+                state.steps += 1;
+                let budget = self.config.max_paths - paths.len() - pending.len();
+                match self.step(&mut state, budget) {
+                    Ok(Some(fork)) => pending.push(fork),
+                    Ok(None) => (),
+                    Err(result) => break result,
+                }
+            };
+            paths.push(Path {
+                result,
+                pc: state.pc,
+                constraints: state.constraints,
+                model: state.model,
+                storage: state.storage,
+            });
+        }
+        paths
+    }
+
+    /// Execute one instruction. Returns the other side of a `JUMPI` when both are feasible and
+    /// `budget` allows another path, and the result when the path ended.
+    fn step(&self, state: &mut State, budget: usize) -> Result<Option<State>, InstructionResult> {
+        let code = self.code.bytecode();
+        let op = code[state.pc];
+        let mut next = state.pc + 1;
+        if let Some(arity) = arity(op) {
+            let args = (0..arity)
+                .map(|_| state.pop())
+                .collect::<Result<Vec<_>, _>>()?;
+            state.push(Expr::op(op, args))?;
+            state.pc = next;
+            return Ok(None);
+        }
+        let env = self.env;
+        let mut fork = None;
+        match op {
+            opcode::STOP => return Err(InstructionResult::Stop),
+            opcode::KECCAK256 => {
+                let (offset, len) = state.pop_range()?;
+                let words = state.memory.load(offset, len)?;
+                state.push(Expr::keccak(words, len))?;
+            }
+            opcode::ADDRESS => state.push_const(address_word(self.address))?,
+            opcode::BALANCE | opcode::EXTCODESIZE | opcode::EXTCODEHASH | opcode::BLOCKHASH => {
+                state.pop()?;
+                state.push_const(U256::ZERO)?;
+            }
+            opcode::ORIGIN | opcode::CALLER => state.push_const(address_word(env.tx.caller))?,
+            opcode::CALLVALUE => state.push(Expr::var(Var::CallValue))?,
+            opcode::CALLDATALOAD => {
+                let offset = state.pop_usize()?;
+                if offset < MAX_CALLDATA_SIZE {
+                    state.push(Expr::var(Var::CallData(offset)))?;
+                } else {
+                    state.push_const(U256::ZERO)?;
+                }
+            }
+            opcode::CALLDATASIZE => state.push(Expr::var(Var::CallDataSize))?,
+            opcode::CALLDATACOPY => {
+                let (offset, data, len) = state.pop_copy()?;
+                state.memory.resize(offset, len)?;
+                for start in (0..len).step_by(32) {
+                    let source = data.saturating_add(start);
+                    let word = if source < MAX_CALLDATA_SIZE {
+                        Expr::var(Var::CallData(source))
+                    } else {
+                        Expr::constant(U256::ZERO)
+                    };
+                    for index in start..len.min(start + 32) {
+                        let byte = (index - start) as u8;
+                        state.memory.store_byte(offset + index, &word, byte);
+                    }
+                }
+            }
+            opcode::CODESIZE => state.push_const(U256::from(self.code.len()))?,
+            opcode::CODECOPY => {
+                let (offset, source, len) = state.pop_copy()?;
+                state.memory.resize(offset, len)?;
+                let original = self.code.original_bytecode_slice();
+                let data: Vec<u8> = (0..len)
+                    .map(|index| {
+                        source
+                            .checked_add(index)
+                            .and_then(|at| original.get(at))
+                            .copied()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                state.memory.store_slice(offset, &data);
+            }
+            opcode::GASPRICE => state.push_const(env.effective_gas_price())?,
+            opcode::EXTCODECOPY => {
+                state.pop()?;
+                let (offset, _, len) = state.pop_copy()?;
+                state.memory.resize(offset, len)?;
+                state.memory.store_slice(offset, &vec![0; len]);
+            }
+            opcode::RETURNDATASIZE => state.push_const(U256::ZERO)?,
+            opcode::RETURNDATACOPY => {
+                let (_, source, len) = state.pop_copy()?;
+                if len != 0 || source != 0 {
+                    return Err(InstructionResult::OutOfOffset);
+                }
+            }
+            opcode::COINBASE => state.push_const(address_word(env.block.coinbase))?,
+            opcode::TIMESTAMP => state.push_const(env.block.timestamp)?,
+            opcode::NUMBER => state.push_const(env.block.number)?,
+            opcode::DIFFICULTY => match env.block.prevrandao {
+                Some(prevrandao) if SpecId::enabled(env.cfg.spec_id, SpecId::MERGE) => {
+                    state.push_const(U256::from_be_bytes(prevrandao.0))?
+                }
+                _ => state.push_const(env.block.difficulty)?,
+            },
+            opcode::GASLIMIT => state.push_const(env.block.gas_limit)?,
+            opcode::CHAINID => state.push_const(env.cfg.chain_id)?,
+            opcode::SELFBALANCE => state.push_const(U256::ZERO)?,
+            opcode::BASEFEE => state.push_const(env.block.basefee)?,
+            opcode::POP => {
+                state.pop()?;
+            }
+            opcode::MLOAD => {
+                let offset = state.pop_usize()?;
+                state.memory.resize(offset, 32)?;
+                let word = state.memory.load_word(offset);
+                state.push(word)?;
+            }
+            opcode::MSTORE => {
+                let offset = state.pop_usize()?;
+                let value = state.pop()?;
+                state.memory.resize(offset, 32)?;
+                state.memory.store_word(offset, &value);
+            }
+            opcode::MSTORE8 => {
+                let offset = state.pop_usize()?;
+                let value = state.pop()?;
+                state.memory.resize(offset, 1)?;
+                state.memory.store_byte(offset, &value, 31);
+            }
+            opcode::SLOAD => {
+                let slot = state.pop()?;
+                let value = state.sload(slot);
+                state.push(value)?;
+            }
+            opcode::SSTORE => {
+                let slot = state.pop()?;
+                let value = state.pop()?;
+                state.storage.push((slot, value));
+            }
+            opcode::TLOAD | opcode::TSTORE if !SpecId::enabled(env.cfg.spec_id, SpecId::CANCUN) => {
+                return Err(InstructionResult::NotActivated);
+            }
+            opcode::TLOAD => {
+                let slot = state.pop()?;
+                let value = state.tload(slot);
+                state.push(value)?;
+            }
+            opcode::TSTORE => {
+                let slot = state.pop()?;
+                let value = state.pop()?;
+                state.transient.push((slot, value));
+            }
+            opcode::JUMP => {
+                let dest = state.pop()?;
+                next = self.jump_dest(state.concretize(dest))?;
+            }
+            opcode::JUMPI => {
+                let dest = state.pop()?;
+                let condition = state.pop()?;
+                match condition.as_const() {
+                    Some(value) if value == U256::ZERO => (),
+                    Some(_) => next = self.jump_dest(state.concretize(dest))?,
+                    None => {
+                        if budget > 1 {
+                            fork = self.fork(state, &condition, dest.clone());
+                        }
+                        let taken = condition.eval(&state.model) != U256::ZERO;
+                        next = self.branch(state, &condition, dest, taken)?;
+                    }
+                }
+            }
+            opcode::PC => state.push_const(U256::from(state.pc))?,
+            opcode::MSIZE => state.push_const(U256::from(state.memory.bytes.len()))?,
+            opcode::GAS => state.push_const(U256::from(env.tx.gas_limit))?,
+            opcode::JUMPDEST => (),
+            opcode::MCOPY => {
+                let dst = state.pop()?;
+                let src = state.pop()?;
+                let len = state.pop()?;
+                let len = state.concretize(len);
+                if len != U256::ZERO {
+                    let len = usize::try_from(len).unwrap_or(usize::MAX);
+                    let dst = usize::try_from(state.concretize(dst)).unwrap_or(usize::MAX);
+                    let src = usize::try_from(state.concretize(src)).unwrap_or(usize::MAX);
+                    state.memory.resize(dst, len)?;
+                    state.memory.resize(src, len)?;
+                    state.memory.copy(dst, src, len);
+                }
+            }
+            opcode::PUSH0..=opcode::PUSH32 => {
+                let size = (op - opcode::PUSH0) as usize;
+                let mut word = [0u8; 32];
+                word[32 - size..].copy_from_slice(&code[state.pc + 1..state.pc + 1 + size]);
+                state.push_const(U256::from_be_bytes(word))?;
+                next += size;
+            }
+            opcode::DUP1..=opcode::DUP16 => {
+                let depth = (op - opcode::DUP1) as usize + 1;
+                let len = state.stack.len();
+                if depth > len {
+                    return Err(InstructionResult::StackUnderflow);
+                }
+                let value = state.stack[len - depth].clone();
+                state.push(value)?;
+            }
+            opcode::SWAP1..=opcode::SWAP16 => {
+                let depth = (op - opcode::SWAP1) as usize + 1;
+                let len = state.stack.len();
+                if depth >= len {
+                    return Err(InstructionResult::StackUnderflow);
+                }
+                state.stack.swap(len - 1, len - 1 - depth);
+            }
+            opcode::LOG0..=opcode::LOG4 => {
+                for _ in 0..2 + (op - opcode::LOG0) {
+                    state.pop()?;
+                }
+            }
+            opcode::CREATE | opcode::CREATE2 => return Err(InstructionResult::Stuck),
+            opcode::CALL | opcode::CALLCODE | opcode::DELEGATECALL | opcode::STATICCALL => {
+                let args = if matches!(op, opcode::CALL | opcode::CALLCODE) {
+                    7
+                } else {
+                    6
+                };
+                for _ in 0..args {
+                    state.pop()?;
+                }
+                state.push_const(U256::from(1))?;
+            }
+            opcode::RETURN | opcode::REVERT => {
+                state.pop_range()?;
+                return Err(if op == opcode::RETURN {
+                    InstructionResult::Return
+                } else {
+                    InstructionResult::Revert
+                });
+            }
+            opcode::INVALID => return Err(InstructionResult::InvalidFEOpcode),
+            opcode::SELFDESTRUCT => {
+                state.pop()?;
+                return Err(InstructionResult::SelfDestruct);
+            }
+            _ => return Err(InstructionResult::OpcodeNotFound),
+        }
+        state.pc = next;
+        Ok(fork)
+    }
+
+    fn jump_dest(&self, dest: U256) -> Result<usize, InstructionResult> {
+        usize::try_from(dest)
+            .ok()
+            .filter(|dest| self.code.jump_map().is_valid(*dest))
+            .ok_or(InstructionResult::InvalidJump)
+    }
+
+    /// Follow the branch of a `JUMPI`, returns the next pc.
+    fn branch(
+        &self,
+        state: &mut State,
+        condition: &Arc<Expr>,
+        dest: Arc<Expr>,
+        taken: bool,
+    ) -> Result<usize, InstructionResult> {
+        state.constraints.push(Constraint {
+            pc: state.pc,
+            expr: condition.clone(),
+            taken,
+        });
+        if taken {
+            self.jump_dest(state.concretize(dest))
+        } else {
+            Ok(state.pc + 1)
+        }
+    }
+
+    /// Side of a `JUMPI` not taken by the model of `state`, if the solver finds a model for it.
+    fn fork(&self, state: &State, condition: &Arc<Expr>, dest: Arc<Expr>) -> Option<State> {
+        let taken = condition.eval(&state.model) == U256::ZERO;
+        let mut constraints = state.constraints.clone();
+        constraints.push(Constraint {
+            pc: state.pc,
+            expr: condition.clone(),
+            taken,
+        });
+        let model = self.config.solver.solve(&constraints, &state.model)?;
+        let mut fork = state.clone();
+        fork.model = model;
+        match self.branch(&mut fork, condition, dest, taken) {
+            Ok(next) => fork.pc = next,
+            Err(result) => fork.halted = Some(result),
+        }
+        Some(fork)
+    }
+}
+
+fn address_word(address: B160) -> U256 {
+    U256::from_be_bytes(B256::from(address).0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+    use crate::primitives::ShanghaiSpec;
+    use crate::{Contract, DummyHost, Interpreter};
+    use alloc::boxed::Box;
+
+    #[test]
+    fn paths_replay_concretely() {
+        let code = assemble(
+            "PUSH1 4 CALLDATASIZE LT PUSH @fail JUMPI
+             PUSH1 0 CALLDATALOAD PUSH1 0xe0 SHR PUSH4 0x12345678 EQ PUSH @guarded JUMPI
+             fail: JUMPDEST PUSH1 0 DUP1 REVERT
+             guarded: JUMPDEST CALLVALUE PUSH @fail JUMPI
+             PUSH1 4 CALLDATALOAD PUSH1 7 ADD PUSH1 0xff AND PUSH1 49 EQ ISZERO PUSH @fail JUMPI
+             PUSH1 1 PUSH1 0 SSTORE STOP",
+        )
+        .unwrap();
+        let env = Env::default();
+        let executor = SymbolicExecutor::new(
+            Bytecode::new_raw(code.clone()),
+            B160::zero(),
+            &env,
+            SymbolicConfig::default(),
+        );
+        let paths = executor.explore();
+        assert_eq!(paths.len(), 5);
+        let stop = paths
+            .iter()
+            .find(|path| path.result == InstructionResult::Stop)
+            .unwrap();
+        assert_eq!(stop.constraints.len(), 4);
+        assert_eq!(stop.storage.len(), 1);
+
+        for path in &paths {
+            let contract = Contract::new(
+                path.calldata(),
+                Bytecode::new_raw(code.clone()),
+                B160::zero(),
+                B160::zero(),
+                path.model.callvalue,
+                SpecId::SHANGHAI,
+            );
+            let mut interpreter = Interpreter::new(Box::new(contract), 100_000, false);
+            let mut host = DummyHost::new(env.clone());
+            let result = interpreter.run::<_, ShanghaiSpec>(&mut host);
+            assert_eq!(result, path.result, "{path}");
+        }
+    }
+
+    #[test]
+    fn copies_read_source_and_size_in_order() {
+        // calldata[4..6] to memory 0, code[1..4] to memory 0x20, distinct sources and sizes
+        let code = assemble(
+            "PUSH1 2 PUSH1 4 PUSH1 0 CALLDATACOPY
+             PUSH1 0 MLOAD PUSH1 0 SSTORE
+             PUSH1 3 PUSH1 1 PUSH1 0x20 CODECOPY
+             PUSH1 0x20 MLOAD PUSH1 1 SSTORE STOP",
+        )
+        .unwrap();
+        let env = Env::default();
+        let executor = SymbolicExecutor::new(
+            Bytecode::new_raw(code.clone()),
+            B160::zero(),
+            &env,
+            SymbolicConfig::default(),
+        );
+        let seed = Model {
+            calldata: (1..=8).collect(),
+            ..Default::default()
+        };
+        let paths = executor.explore_from(seed);
+        let [path] = &paths[..] else {
+            panic!("{paths:?}");
+        };
+        assert_eq!(path.result, InstructionResult::Stop);
+        let stored: Vec<_> = path
+            .storage
+            .iter()
+            .map(|(slot, value)| (slot.eval(&path.model), value.eval(&path.model)))
+            .collect();
+        let word = |bytes: &[u8]| {
+            let mut word = [0; 32];
+            word[..bytes.len()].copy_from_slice(bytes);
+            U256::from_be_bytes(word)
+        };
+        let expected = vec![
+            (U256::ZERO, word(&[5, 6])),
+            (U256::from(1), word(&code[1..4])),
+        ];
+        assert_eq!(stored, expected);
+
+        let contract = Contract::new(
+            path.calldata(),
+            Bytecode::new_raw(code),
+            B160::zero(),
+            B160::zero(),
+            U256::ZERO,
+            SpecId::SHANGHAI,
+        );
+        let mut interpreter = Interpreter::new(Box::new(contract), 100_000, false);
+        let mut host = DummyHost::new(env.clone());
+        interpreter.run::<_, ShanghaiSpec>(&mut host);
+        for (slot, value) in expected {
+            assert_eq!(host.storage.get(&slot), Some(&value));
+        }
+    }
+}
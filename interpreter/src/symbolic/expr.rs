@@ -0,0 +1,253 @@
+//! Symbolic words and their evaluation under a model.
+
+use crate::instructions::i256::{i256_cmp, i256_div, i256_mod, i256_sign, two_compl, Sign};
+use crate::opcode::{self, OPCODE_JUMPMAP};
+use crate::primitives::{keccak256, Bytes, U256};
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::cmp::Ordering;
+use core::fmt;
+
+/// Transaction inputs that are kept symbolic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Var {
+    /// Calldata word read by `CALLDATALOAD` at a constant offset.
+    CallData(usize),
+    CallDataSize,
+    CallValue,
+    /// Value of the storage slot before the transaction.
+    Storage(Arc<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Const(U256),
+    Var(Var),
+    /// Pure opcode applied to its operands, the first operand is the top of the stack.
+    Op(u8, Vec<Arc<Expr>>),
+    /// `KECCAK256` of the first `len` bytes of the concatenated words.
+    Keccak(Vec<Arc<Expr>>, usize),
+}
+
+/// Concrete assignment of the symbolic inputs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Model {
+    pub calldata: Vec<u8>,
+    pub callvalue: U256,
+    /// Storage before the transaction, missing slots are zero.
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl Model {
+    pub fn calldata(&self) -> Bytes {
+        Bytes::copy_from_slice(&self.calldata)
+    }
+
+    /// Word at `offset`, zero padded past the end of calldata.
+    pub fn calldata_word(&self, offset: usize) -> U256 {
+        let mut word = [0u8; 32];
+        if offset < self.calldata.len() {
+            let end = self.calldata.len().min(offset + 32);
+            word[..end - offset].copy_from_slice(&self.calldata[offset..end]);
+        }
+        U256::from_be_bytes(word)
+    }
+
+    /// Write the word at `offset`, extending calldata when needed.
+    pub fn set_calldata_word(&mut self, offset: usize, value: U256) {
+        if self.calldata.len() < offset + 32 {
+            self.calldata.resize(offset + 32, 0);
+        }
+        self.calldata[offset..offset + 32].copy_from_slice(&value.to_be_bytes::<32>());
+    }
+}
+
+impl Expr {
+    pub fn constant(value: U256) -> Arc<Self> {
+        Arc::new(Self::Const(value))
+    }
+
+    pub fn var(var: Var) -> Arc<Self> {
+        Arc::new(Self::Var(var))
+    }
+
+    /// Apply a pure opcode, folding it when every operand is constant.
+    pub fn op(opcode: u8, args: Vec<Arc<Expr>>) -> Arc<Self> {
+        let values: Option<Vec<U256>> = args.iter().map(|arg| arg.as_const()).collect();
+        match values {
+            Some(values) => Self::constant(apply(opcode, &values)),
+            None => Arc::new(Self::Op(opcode, args)),
+        }
+    }
+
+    pub fn keccak(words: Vec<Arc<Expr>>, len: usize) -> Arc<Self> {
+        let values: Option<Vec<U256>> = words.iter().map(|word| word.as_const()).collect();
+        match values {
+            Some(values) => Self::constant(keccak_words(&values, len)),
+            None => Arc::new(Self::Keccak(words, len)),
+        }
+    }
+
+    pub fn as_const(&self) -> Option<U256> {
+        match self {
+            Self::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn eval(&self, model: &Model) -> U256 {
+        match self {
+            Self::Const(value) => *value,
+            Self::Var(Var::CallData(offset)) => model.calldata_word(*offset),
+            Self::Var(Var::CallDataSize) => U256::from(model.calldata.len()),
+            Self::Var(Var::CallValue) => model.callvalue,
+            Self::Var(Var::Storage(slot)) => model
+                .storage
+                .get(&slot.eval(model))
+                .copied()
+                .unwrap_or_default(),
+            Self::Op(opcode, args) => {
+                let values: Vec<U256> = args.iter().map(|arg| arg.eval(model)).collect();
+                apply(*opcode, &values)
+            }
+            Self::Keccak(words, len) => {
+                let values: Vec<U256> = words.iter().map(|word| word.eval(model)).collect();
+                keccak_words(&values, *len)
+            }
+        }
+    }
+
+    /// Opcode produces only zero or one.
+    pub fn is_boolean(&self) -> bool {
+        matches!(
+            self,
+            Self::Op(
+                opcode::LT | opcode::GT | opcode::SLT | opcode::SGT | opcode::EQ | opcode::ISZERO,
+                _
+            )
+        )
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Const(value) => write_word(f, *value),
+            Self::Var(Var::CallData(offset)) => write!(f, "calldata[{offset}]"),
+            Self::Var(Var::CallDataSize) => f.write_str("calldatasize"),
+            Self::Var(Var::CallValue) => f.write_str("callvalue"),
+            Self::Var(Var::Storage(slot)) => write!(f, "storage[{slot}]"),
+            Self::Op(opcode, args) => {
+                f.write_str(OPCODE_JUMPMAP[*opcode as usize].unwrap_or("UNKNOWN"))?;
+                write_args(f, args)
+            }
+            Self::Keccak(words, len) => {
+                write!(f, "KECCAK256[{len}]")?;
+                write_args(f, words)
+            }
+        }
+    }
+}
+
+/// Hex without leading zeros.
+pub(crate) fn write_word(f: &mut fmt::Formatter<'_>, value: U256) -> fmt::Result {
+    let bytes = value.to_be_bytes::<32>();
+    let first = bytes.iter().position(|b| *b != 0).unwrap_or(31);
+    write!(f, "0x{:x}", bytes[first])?;
+    bytes[first + 1..]
+        .iter()
+        .try_for_each(|b| write!(f, "{b:02x}"))
+}
+
+fn write_args(f: &mut fmt::Formatter<'_>, args: &[Arc<Expr>]) -> fmt::Result {
+    f.write_str("(")?;
+    for (index, arg) in args.iter().enumerate() {
+        if index > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{arg}")?;
+    }
+    f.write_str(")")
+}
+
+/// Number of operands of a pure opcode, `None` if the opcode is not pure.
+pub fn arity(opcode: u8) -> Option<usize> {
+    match opcode {
+        opcode::ISZERO | opcode::NOT => Some(1),
+        opcode::ADDMOD | opcode::MULMOD => Some(3),
+        opcode::ADD..=opcode::SIGNEXTEND | opcode::LT..=opcode::SAR => Some(2),
+        _ => None,
+    }
+}
+
+/// Concrete semantics of pure opcodes, same as the interpreter instructions.
+pub fn apply(opcode: u8, args: &[U256]) -> U256 {
+    let a = args[0];
+    let b = args.get(1).copied().unwrap_or_default();
+    let bool_word = |value: bool| U256::from(value as u8);
+    let shift = |value: U256| usize::try_from(value).unwrap_or(usize::MAX);
+    match opcode {
+        opcode::ADD => a.wrapping_add(b),
+        opcode::MUL => a.wrapping_mul(b),
+        opcode::SUB => a.wrapping_sub(b),
+        opcode::DIV => a.checked_div(b).unwrap_or_default(),
+        opcode::SDIV => i256_div(a, b),
+        opcode::MOD => a.checked_rem(b).unwrap_or_default(),
+        opcode::SMOD if b == U256::ZERO => U256::ZERO,
+        opcode::SMOD => i256_mod(a, b),
+        opcode::ADDMOD => a.add_mod(b, args[2]),
+        opcode::MULMOD => a.mul_mod(b, args[2]),
+        opcode::EXP => a.pow(b),
+        opcode::SIGNEXTEND if a < U256::from(32) => {
+            let bit_index = (8 * a.as_limbs()[0] + 7) as usize;
+            let mask = (U256::from(1) << bit_index) - U256::from(1);
+            if b.bit(bit_index) {
+                b | !mask
+            } else {
+                b & mask
+            }
+        }
+        opcode::SIGNEXTEND => b,
+        opcode::LT => bool_word(a < b),
+        opcode::GT => bool_word(a > b),
+        opcode::SLT => bool_word(i256_cmp(a, b) == Ordering::Less),
+        opcode::SGT => bool_word(i256_cmp(a, b) == Ordering::Greater),
+        opcode::EQ => bool_word(a == b),
+        opcode::ISZERO => bool_word(a == U256::ZERO),
+        opcode::AND => a & b,
+        opcode::OR => a | b,
+        opcode::XOR => a ^ b,
+        opcode::NOT => !a,
+        opcode::BYTE if a < U256::from(32) => (b << (8 * shift(a))) >> (8 * 31),
+        opcode::BYTE => U256::ZERO,
+        opcode::SHL => b << shift(a),
+        opcode::SHR => b >> shift(a),
+        opcode::SAR => {
+            let mut value = b;
+            let sign = i256_sign::<true>(&mut value);
+            if value == U256::ZERO || a >= U256::from(256) {
+                match sign {
+                    Sign::Plus | Sign::Zero => U256::ZERO,
+                    Sign::Minus => two_compl(U256::from(1)),
+                }
+            } else {
+                let shift = shift(a);
+                match sign {
+                    Sign::Plus | Sign::Zero => value >> shift,
+                    Sign::Minus => two_compl(
+                        ((value.wrapping_sub(U256::from(1))) >> shift).wrapping_add(U256::from(1)),
+                    ),
+                }
+            }
+        }
+        _ => unreachable!("opcode {opcode:#x} is not pure"),
+    }
+}
+
+fn keccak_words(words: &[U256], len: usize) -> U256 {
+    let mut bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word| word.to_be_bytes::<32>())
+        .collect();
+    bytes.resize(len, 0);
+    U256::from_be_bytes(keccak256(&bytes).0)
+}
@@ -0,0 +1,343 @@
+//! Small solver for path constraints.
+//!
+//! The solver does local search over a concrete model: it picks the first violated constraint
+//! and inverts the expression down to one of its inputs, keeping the other operands at their
+//! current values. Invertible operations cover linear arithmetic modulo 2^256, comparisons and
+//! the bit-vector operations used by ABI decoding (masks, shifts, bytes). It is incomplete:
+//! `None` means no model was found, not that the constraints are unsatisfiable.
+
+use super::expr::{Expr, Model, Var};
+use crate::opcode;
+use crate::primitives::U256;
+use alloc::{sync::Arc, vec::Vec};
+use core::fmt;
+
+/// Calldata is never grown past this size by the solver.
+pub const MAX_CALLDATA_SIZE: usize = 1 << 16;
+
+/// Branch condition taken by a path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    /// Pc of the `JUMPI`, or of the instruction that needed a concrete operand.
+    pub pc: usize,
+    pub expr: Arc<Expr>,
+    /// Condition is non-zero.
+    pub taken: bool,
+}
+
+impl Constraint {
+    pub fn holds(&self, model: &Model) -> bool {
+        (self.expr.eval(model) != U256::ZERO) == self.taken
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let relation = if self.taken { "!=" } else { "==" };
+        write!(f, "{:04x}: {} {relation} 0", self.pc, self.expr)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Solver {
+    /// Max number of repairs before giving up.
+    pub max_rounds: usize,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self { max_rounds: 64 }
+    }
+}
+
+impl Solver {
+    /// Find a model of the constraints, starting the search from `seed`.
+    pub fn solve(&self, constraints: &[Constraint], seed: &Model) -> Option<Model> {
+        let mut model = seed.clone();
+        for _ in 0..self.max_rounds {
+            let Some(violated) = constraints.iter().find(|c| !c.holds(&model)) else {
+                return Some(model);
+            };
+            let target = if violated.taken {
+                nonzero_target(&violated.expr, &model)
+            } else {
+                U256::ZERO
+            };
+            if !repair(&violated.expr, target, &mut model) {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+/// Non-zero value the expression is steered to.
+fn nonzero_target(expr: &Expr, model: &Model) -> U256 {
+    if let Expr::Op(opcode::AND, args) = expr {
+        let mask = args[0].eval(model) & args[1].eval(model);
+        if mask != U256::ZERO {
+            return mask;
+        }
+        for arg in args {
+            if let Some(mask) = arg.as_const() {
+                return mask & mask.wrapping_neg();
+            }
+        }
+    }
+    U256::from(1)
+}
+
+/// Change the model so that `expr` evaluates to `target`.
+/// The model is left untouched when no change was found.
+fn repair(expr: &Expr, target: U256, model: &mut Model) -> bool {
+    let backup = model.clone();
+    if repair_inner(expr, target, model) && expr.eval(model) == target {
+        return true;
+    }
+    *model = backup;
+    false
+}
+
+fn repair_inner(expr: &Expr, target: U256, model: &mut Model) -> bool {
+    let (op, args) = match expr {
+        Expr::Const(value) => return *value == target,
+        Expr::Var(var) => return assign(var, target, model),
+        Expr::Keccak(..) => return false,
+        Expr::Op(op, args) => (*op, args),
+    };
+    let values: Vec<U256> = args.iter().map(|arg| arg.eval(model)).collect();
+    let one = U256::from(1);
+    // Either operand of a binary operation can be solved for, the other one is kept.
+    let either = |model: &mut Model, left: Option<U256>, right: Option<U256>| {
+        left.is_some_and(|left| repair(&args[0], left, model))
+            || right.is_some_and(|right| repair(&args[1], right, model))
+    };
+    let (a, b) = (values[0], values.get(1).copied().unwrap_or_default());
+    match op {
+        opcode::ISZERO if target == U256::ZERO => {
+            let nonzero = nonzero_target(&args[0], model);
+            repair(&args[0], nonzero, model)
+        }
+        opcode::ISZERO => target == one && repair(&args[0], U256::ZERO, model),
+        opcode::NOT => repair(&args[0], !target, model),
+        _ if expr.is_boolean() && target > one => false,
+        opcode::EQ if target == one => either(model, Some(b), Some(a)),
+        opcode::EQ => either(model, Some(b.wrapping_add(one)), Some(a.wrapping_add(one))),
+        opcode::LT | opcode::GT | opcode::SLT | opcode::SGT => {
+            let signed = matches!(op, opcode::SLT | opcode::SGT);
+            // normalize to `lower < upper` or `lower >= upper`
+            let less = target == one;
+            let flip = |value: U256| {
+                if signed {
+                    value ^ (one << 255)
+                } else {
+                    value
+                }
+            };
+            let (a, b) = (flip(a), flip(b));
+            let lower_is_a = matches!(op, opcode::LT | opcode::SLT);
+            let (lower, upper) = if lower_is_a { (a, b) } else { (b, a) };
+            let (lower_target, upper_target) = if less {
+                (
+                    (upper != U256::ZERO).then(|| upper - one),
+                    (lower != U256::MAX).then(|| lower + one),
+                )
+            } else {
+                (Some(upper), Some(lower))
+            };
+            let lower_target = lower_target.map(flip);
+            let upper_target = upper_target.map(flip);
+            if lower_is_a {
+                either(model, lower_target, upper_target)
+            } else {
+                either(model, upper_target, lower_target)
+            }
+        }
+        opcode::ADD => either(
+            model,
+            Some(target.wrapping_sub(b)),
+            Some(target.wrapping_sub(a)),
+        ),
+        opcode::SUB => either(
+            model,
+            Some(target.wrapping_add(b)),
+            Some(a.wrapping_sub(target)),
+        ),
+        opcode::XOR => either(model, Some(target ^ b), Some(target ^ a)),
+        opcode::MUL => either(model, divide(target, b), divide(target, a)),
+        opcode::DIV => {
+            b != U256::ZERO
+                && target
+                    .checked_mul(b)
+                    .is_some_and(|a| repair(&args[0], a, model))
+        }
+        opcode::AND => either(
+            model,
+            (target & !b == U256::ZERO).then(|| (a & !b) | target),
+            (target & !a == U256::ZERO).then(|| (b & !a) | target),
+        ),
+        opcode::OR => either(
+            model,
+            (b & !target == U256::ZERO).then(|| target & !b),
+            (a & !target == U256::ZERO).then(|| target & !a),
+        ),
+        opcode::SHR => {
+            let Ok(shift) = usize::try_from(a) else {
+                return target == U256::ZERO;
+            };
+            if shift >= 256 {
+                return target == U256::ZERO;
+            }
+            let low = (one << shift) - one;
+            (target << shift) >> shift == target
+                && repair(&args[1], (target << shift) | (b & low), model)
+        }
+        opcode::SHL => {
+            let Ok(shift) = usize::try_from(a) else {
+                return target == U256::ZERO;
+            };
+            if shift >= 256 {
+                return target == U256::ZERO;
+            }
+            let high = !(U256::MAX >> shift);
+            (target >> shift) << shift == target
+                && repair(&args[1], (target >> shift) | (b & high), model)
+        }
+        opcode::BYTE => {
+            let Ok(index) = usize::try_from(a) else {
+                return false;
+            };
+            if index >= 32 || target > U256::from(0xff) {
+                return false;
+            }
+            let shift = 8 * (31 - index);
+            let value = (b & !(U256::from(0xff) << shift)) | (target << shift);
+            repair(&args[1], value, model)
+        }
+        opcode::SIGNEXTEND => repair(&args[1], target, model),
+        _ => false,
+    }
+}
+
+/// Some `x` with `x * by == target`.
+fn divide(target: U256, by: U256) -> Option<U256> {
+    if by == U256::ZERO {
+        return (target == U256::ZERO).then_some(U256::ZERO);
+    }
+    if by.bit(0) {
+        return Some(target.wrapping_mul(inverse(by)));
+    }
+    (target % by == U256::ZERO).then(|| target / by)
+}
+
+/// Inverse of an odd number modulo 2^256, by Newton iteration.
+fn inverse(odd: U256) -> U256 {
+    // correct to 3 bits, every step doubles the number of correct bits.
+    let mut inverse = odd;
+    for _ in 0..7 {
+        inverse = inverse.wrapping_mul(U256::from(2).wrapping_sub(odd.wrapping_mul(inverse)));
+    }
+    inverse
+}
+
+fn assign(var: &Var, target: U256, model: &mut Model) -> bool {
+    match var {
+        Var::CallData(offset) => model.set_calldata_word(*offset, target),
+        Var::CallDataSize => match usize::try_from(target) {
+            Ok(size) if size <= MAX_CALLDATA_SIZE => model.calldata.resize(size, 0),
+            _ => return false,
+        },
+        Var::CallValue => model.callvalue = target,
+        Var::Storage(slot) => {
+            let slot = slot.eval(model);
+            model.storage.insert(slot, target);
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn abi_checks() {
+        let calldata = |offset| Expr::var(Var::CallData(offset));
+        let constant = |value: u64| Expr::constant(U256::from(value));
+        let selector = Expr::op(opcode::SHR, vec![constant(0xe0), calldata(0)]);
+        let argument = calldata(4);
+        let constraints = [
+            // calldatasize < 4 not taken
+            Constraint {
+                pc: 0,
+                expr: Expr::op(opcode::LT, vec![Expr::var(Var::CallDataSize), constant(4)]),
+                taken: false,
+            },
+            Constraint {
+                pc: 1,
+                expr: Expr::op(opcode::EQ, vec![constant(0x27e235e3), selector]),
+                taken: true,
+            },
+            // calldatasize - 4 slt 32 not taken
+            Constraint {
+                pc: 2,
+                expr: Expr::op(
+                    opcode::SLT,
+                    vec![
+                        Expr::op(opcode::SUB, vec![Expr::var(Var::CallDataSize), constant(4)]),
+                        constant(32),
+                    ],
+                ),
+                taken: false,
+            },
+            // 3 * argument + 5 == 0x20 (mod 2^256)
+            Constraint {
+                pc: 3,
+                expr: Expr::op(
+                    opcode::EQ,
+                    vec![
+                        Expr::op(
+                            opcode::ADD,
+                            vec![
+                                Expr::op(opcode::MUL, vec![constant(3), argument.clone()]),
+                                constant(5),
+                            ],
+                        ),
+                        constant(0x20),
+                    ],
+                ),
+                taken: true,
+            },
+            Constraint {
+                pc: 4,
+                expr: Expr::op(opcode::ISZERO, vec![Expr::var(Var::CallValue)]),
+                taken: true,
+            },
+        ];
+        let model = Solver::default()
+            .solve(&constraints, &Model::default())
+            .unwrap();
+        assert!(constraints.iter().all(|c| c.holds(&model)));
+        assert_eq!(model.calldata[..4], [0x27, 0xe2, 0x35, 0xe3]);
+        assert!(model.calldata.len() >= 36);
+        assert_eq!(argument.eval(&model), U256::from(9));
+
+        let contradiction = [
+            Constraint {
+                pc: 0,
+                expr: Expr::var(Var::CallValue),
+                taken: true,
+            },
+            Constraint {
+                pc: 1,
+                expr: Expr::op(opcode::ISZERO, vec![Expr::var(Var::CallValue)]),
+                taken: true,
+            },
+        ];
+        assert_eq!(
+            Solver::default().solve(&contradiction, &Model::default()),
+            None
+        );
+    }
+}
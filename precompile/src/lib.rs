@@ -21,7 +21,7 @@ pub type B256 = [u8; 32];
 /// libraries for no_std flag
 #[macro_use]
 extern crate alloc;
-use alloc::vec::Vec;
+use alloc::{sync::Arc, vec::Vec};
 use core::fmt;
 
 pub fn calc_linear_cost_u32(len: usize, base: u64, word: u64) -> u64 {
@@ -67,6 +67,7 @@ impl Default for Precompiles {
 pub enum Precompile {
     Standard(StandardPrecompileFn),
     Custom(CustomPrecompileFn),
+    Stateful(Arc<dyn StatefulPrecompile>),
 }
 
 impl fmt::Debug for Precompile {
@@ -74,6 +75,7 @@ impl fmt::Debug for Precompile {
         match self {
             Precompile::Standard(_) => f.write_str("Standard"),
             Precompile::Custom(_) => f.write_str("Custom"),
+            Precompile::Stateful(_) => f.write_str("Stateful"),
         }
     }
 }
@@ -205,6 +207,33 @@ impl Precompiles {
         }
     }
 
+    /// Register a precompile at `address`, replacing the one already there.
+    pub fn insert(&mut self, address: B160, precompile: Precompile) -> Option<Precompile> {
+        self.fun.insert(address, precompile)
+    }
+
+    /// Register a closure or a [`StatefulPrecompile`] at `address`.
+    pub fn insert_stateful<P: StatefulPrecompile + 'static>(
+        &mut self,
+        address: B160,
+        precompile: P,
+    ) -> Option<Precompile> {
+        self.insert(address, Precompile::Stateful(Arc::new(precompile)))
+    }
+
+    pub fn remove(&mut self, address: &B160) -> Option<Precompile> {
+        self.fun.remove(address)
+    }
+
+    /// Number of precompiles at consecutive addresses starting from `0x01`. Those are warm at the
+    /// start of a transaction and can't be created over, other registered addresses are treated
+    /// as accounts for both.
+    pub fn consecutive_len(&self) -> usize {
+        (1..)
+            .take_while(|index| self.contains(&u64_to_b160(*index)))
+            .count()
+    }
+
     pub fn addresses(&self) -> impl IntoIterator<Item = &B160> {
         self.fun.keys()
     }
@@ -1,4 +1,4 @@
-use crate::{keccak256, B256, KECCAK_EMPTY};
+use crate::{keccak256, SpecId, B256, KECCAK_EMPTY};
 use alloc::{sync::Arc, vec, vec::Vec};
 use bitvec::prelude::{bitvec, Lsb0};
 use bitvec::vec::BitVec;
@@ -26,12 +26,45 @@ impl JumpMap {
     }
 }
 
+/// Static gas of every gas block, indexed by the pc where the block starts.
+///
+/// Opcode gas differs between hardforks so blocks are tagged with the spec they were summed for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GasBlocks {
+    pub spec_id: SpecId,
+    pub gas: Arc<Vec<u32>>,
+}
+
+impl Default for GasBlocks {
+    fn default() -> Self {
+        Self {
+            spec_id: SpecId::LATEST,
+            gas: Arc::new(Vec::new()),
+        }
+    }
+}
+
+impl GasBlocks {
+    /// Static gas of the block starting at `pc`, zero if no block starts there.
+    #[inline(always)]
+    pub fn gas(&self, pc: usize) -> u64 {
+        self.gas.get(pc).copied().unwrap_or_default() as u64
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BytecodeState {
     Raw,
-    Checked { len: usize },
-    Analysed { len: usize, jump_map: JumpMap },
+    Checked {
+        len: usize,
+    },
+    Analysed {
+        len: usize,
+        jump_map: JumpMap,
+        gas_blocks: GasBlocks,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -58,6 +91,7 @@ impl Bytecode {
             state: BytecodeState::Analysed {
                 len: 0,
                 jump_map: JumpMap(Arc::new(bitvec![u8, Lsb0; 0])),
+                gas_blocks: GasBlocks::default(),
             },
         }
     }
@@ -77,6 +77,7 @@ pub enum CreateScheme {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CfgEnv {
     pub chain_id: U256,
     pub spec_id: SpecId,
@@ -111,6 +112,20 @@ pub struct CfgEnv {
     /// Disables base fee checks for EIP-1559 transactions.
     /// This is useful for testing method calls with zero gas price.
     pub disable_base_fee: bool,
+    /// Handle calls to the Foundry cheatcode address (`0x7109709ECfa91a80626fF3989D68f67F5b1DD12D`)
+    /// so test scenarios can be written in Solidity.
+    /// By default, it is set to `false`.
+    pub enable_cheatcodes: bool,
+    /// Run the nested calls and creates of a transaction in place instead of returning a stuck
+    /// execution result at the first one.
+    /// By default, it is set to `false`.
+    pub run_nested_frames: bool,
+    /// Record the interpreter state before every instruction, see `EVMImpl::take_trace`.
+    /// By default, it is set to `false`.
+    pub trace_steps: bool,
+    /// Record a light event before every instruction, see `EVMImpl::take_events`.
+    /// By default, it is set to `false`.
+    pub trace_events: bool,
 }
 
 impl CfgEnv {
@@ -187,6 +202,10 @@ impl Default for CfgEnv {
             disable_eip3607: false,
             disable_gas_refund: false,
             disable_base_fee: false,
+            enable_cheatcodes: false,
+            run_nested_frames: false,
+            trace_steps: false,
+            trace_events: false,
         }
     }
 }
@@ -10,6 +10,7 @@ pub mod precompile;
 pub mod result;
 pub mod specification;
 pub mod state;
+pub mod trie;
 pub mod utilities;
 
 extern crate alloc;
@@ -38,4 +39,5 @@ pub use ruint::aliases::U256;
 pub use ruint::uint;
 pub use specification::*;
 pub use state::*;
+pub use trie::*;
 pub use utilities::*;
@@ -1,3 +1,4 @@
+use crate::{Bytecode, Bytes, Env, B160, B256, U256};
 use alloc::vec::Vec;
 
 /// A precompile operation result.
@@ -6,6 +7,64 @@ pub type PrecompileResult = Result<(u64, Vec<u8>), PrecompileError>;
 pub type StandardPrecompileFn = fn(&[u8], u64) -> PrecompileResult;
 pub type CustomPrecompileFn = fn(&[u8], u64) -> PrecompileResult;
 
+/// Call frame and state seen by a [`StatefulPrecompile`].
+///
+/// State changes are journaled like the ones done by contracts: they are reverted together with
+/// the precompile frame when it fails or runs out of gas, and with any parent frame that reverts.
+/// Methods return `None` on database error, the call then fails with `FatalExternalError`.
+pub trait PrecompileContext {
+    /// Environment of the transaction, it can be changed to move the block or the transaction.
+    fn env(&mut self) -> &mut Env;
+    /// Address of the executing account, the caller's own address for `DELEGATECALL` and
+    /// `CALLCODE`. Logs are emitted from it.
+    fn address(&self) -> B160;
+    fn caller(&self) -> B160;
+    /// Apparent value of the call.
+    fn value(&self) -> U256;
+    /// Call depth of the precompile frame.
+    fn depth(&self) -> u64;
+    /// Precompile is called in a static context. Writes are not refused by the context, a
+    /// precompile that changes state should check this itself.
+    fn is_static(&self) -> bool;
+
+    fn balance(&mut self, address: B160) -> Option<U256>;
+    /// Move balance between two accounts, returns `false` if `from` does not have enough.
+    fn transfer(&mut self, from: B160, to: B160, value: U256) -> Option<bool>;
+    fn code(&mut self, address: B160) -> Option<Bytecode>;
+    fn set_code(&mut self, address: B160, code: Bytecode) -> Option<()>;
+    fn sload(&mut self, address: B160, index: U256) -> Option<U256>;
+    fn sstore(&mut self, address: B160, index: U256, value: U256) -> Option<()>;
+    /// Emit a log from [`Self::address`].
+    fn log(&mut self, topics: Vec<B256>, data: Bytes);
+}
+
+/// Precompile that can hold state and access the call context.
+///
+/// Precompiles are shared between clones of `Precompiles`, so state kept in the precompile itself
+/// needs interior mutability. State kept in the storage of the precompile address is journaled.
+pub trait StatefulPrecompile: Send + Sync {
+    fn call(
+        &self,
+        input: &[u8],
+        gas_limit: u64,
+        context: &mut dyn PrecompileContext,
+    ) -> PrecompileResult;
+}
+
+impl<F> StatefulPrecompile for F
+where
+    F: Fn(&[u8], u64, &mut dyn PrecompileContext) -> PrecompileResult + Send + Sync,
+{
+    fn call(
+        &self,
+        input: &[u8],
+        gas_limit: u64,
+        context: &mut dyn PrecompileContext,
+    ) -> PrecompileResult {
+        self(input, gas_limit, context)
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PrecompileError {
     /// out of gas is the main error. Other are just here for completness
@@ -0,0 +1,362 @@
+//! Merkle-Patricia trie roots of account and storage contents.
+//!
+//! Only roots are computed, the trie nodes themselves are not kept. Keys are hashed with keccak
+//! before insertion, as in the state and storage tries of Ethereum.
+
+use crate::{keccak256, AccountInfo, HashMap, HashSet, State, B160, B256, U256};
+use alloc::{collections::BTreeMap, vec::Vec};
+use hex_literal::hex;
+use rlp::RlpStream;
+
+/// Root of the empty trie, `keccak256(rlp(""))`.
+pub const EMPTY_ROOT_HASH: B256 = B256(hex!(
+    "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+));
+
+/// Root of the trie mapping `keccak256(key)` to `value` for every entry.
+pub fn sec_trie_root<K, V>(entries: impl IntoIterator<Item = (K, V)>) -> B256
+where
+    K: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    let mut leaves: Vec<(B256, V)> = entries
+        .into_iter()
+        .map(|(key, value)| (keccak256(key.as_ref()), value))
+        .collect();
+    leaves.sort_unstable_by_key(|(key, _)| *key);
+    leaves.dedup_by(|a, b| a.0 == b.0);
+    hashed_trie_root(leaves.iter().map(|(key, value)| (key, value.as_ref())))
+}
+
+/// Root of the storage trie, slots holding zero are not part of the trie.
+pub fn storage_root<'a>(storage: impl IntoIterator<Item = (&'a U256, &'a U256)>) -> B256 {
+    sec_trie_root(
+        storage
+            .into_iter()
+            .filter(|(_, value)| **value != U256::ZERO)
+            .map(|(slot, value)| (slot.to_be_bytes::<32>(), rlp::encode(value))),
+    )
+}
+
+/// RLP of the account as stored in the state trie: `[nonce, balance, storage_root, code_hash]`.
+pub fn trie_account_rlp(info: &AccountInfo, storage_root: B256) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&info.nonce);
+    stream.append(&info.balance);
+    stream.append(&storage_root.as_bytes());
+    stream.append(&info.code_hash.as_bytes());
+    stream.out().to_vec()
+}
+
+/// State root of the accounts in `state`, using the present value of every slot.
+///
+/// Self destructed and empty accounts are left out (EIP-161). Note that a journaled state only
+/// holds the accounts and slots loaded so far.
+pub fn state_root(state: &State) -> B256 {
+    sec_trie_root(
+        state
+            .iter()
+            .filter(|(_, account)| !account.is_selfdestructed() && !account.is_empty())
+            .map(|(address, account)| {
+                let storage = account
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (*slot, value.present_value()))
+                    .collect::<Vec<_>>();
+                let storage_root = storage_root(storage.iter().map(|(slot, value)| (slot, value)));
+                (address, trie_account_rlp(&account.info, storage_root))
+            }),
+    )
+}
+
+/// State root maintained across small changes of the state.
+///
+/// Hashed keys and encoded values of accounts and slots are kept between calls to
+/// [`StateRootCache::root`], so only the storage roots of the accounts that changed since the
+/// last call are recomputed. This is meant for hashing many slightly different states, like the
+/// states along a rollout.
+#[derive(Clone, Debug, Default)]
+pub struct StateRootCache {
+    accounts: HashMap<B160, CachedAccount>,
+    /// Encoded accounts, by hashed address.
+    leaves: BTreeMap<B256, Vec<u8>>,
+    dirty: HashSet<B160>,
+    root: Option<B256>,
+}
+
+#[derive(Clone, Debug)]
+struct CachedAccount {
+    hashed_address: B256,
+    info: AccountInfo,
+    /// Encoded non zero slots, by hashed slot.
+    storage: BTreeMap<B256, Vec<u8>>,
+    storage_root: B256,
+}
+
+impl CachedAccount {
+    fn new(address: B160) -> Self {
+        Self {
+            hashed_address: keccak256(address.as_bytes()),
+            info: AccountInfo::default(),
+            storage: BTreeMap::new(),
+            storage_root: EMPTY_ROOT_HASH,
+        }
+    }
+}
+
+impl StateRootCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the nonce, balance and code hash of the account, adding it if missing.
+    pub fn set_account(&mut self, address: B160, info: &AccountInfo) {
+        let account = self
+            .accounts
+            .entry(address)
+            .or_insert_with(|| CachedAccount::new(address));
+        if self.leaves.contains_key(&account.hashed_address)
+            && account.info.nonce == info.nonce
+            && account.info.balance == info.balance
+            && account.info.code_hash == info.code_hash
+        {
+            return;
+        }
+        account.info.nonce = info.nonce;
+        account.info.balance = info.balance;
+        account.info.code_hash = info.code_hash;
+        self.dirty.insert(address);
+        self.root = None;
+    }
+
+    /// Sets a storage slot of the account, which must have been added with
+    /// [`StateRootCache::set_account`].
+    pub fn set_storage(&mut self, address: B160, slot: U256, value: U256) {
+        let Some(account) = self.accounts.get_mut(&address) else {
+            return;
+        };
+        let hashed_slot = keccak256(&slot.to_be_bytes::<32>());
+        let changed = if value == U256::ZERO {
+            account.storage.remove(&hashed_slot).is_some()
+        } else {
+            let encoded = rlp::encode(&value).to_vec();
+            account.storage.insert(hashed_slot, encoded.clone()) != Some(encoded)
+        };
+        if changed {
+            self.dirty.insert(address);
+            self.root = None;
+        }
+    }
+
+    /// Removes the account and its storage.
+    pub fn remove_account(&mut self, address: B160) {
+        if let Some(account) = self.accounts.remove(&address) {
+            self.leaves.remove(&account.hashed_address);
+            self.dirty.remove(&address);
+            self.root = None;
+        }
+    }
+
+    /// Applies the accounts of `state`, as [`state_root`] would see them.
+    pub fn commit(&mut self, state: &State) {
+        for (address, account) in state {
+            if account.is_selfdestructed() || account.is_empty() {
+                self.remove_account(*address);
+                continue;
+            }
+            if account.is_newly_created() {
+                if let Some(cached) = self.accounts.get_mut(address) {
+                    if !cached.storage.is_empty() {
+                        cached.storage.clear();
+                        self.dirty.insert(*address);
+                        self.root = None;
+                    }
+                }
+            }
+            self.set_account(*address, &account.info);
+            for (slot, value) in &account.storage {
+                self.set_storage(*address, *slot, value.present_value());
+            }
+        }
+    }
+
+    /// State root of the accounts set so far.
+    pub fn root(&mut self) -> B256 {
+        if let Some(root) = self.root {
+            return root;
+        }
+        for address in self.dirty.drain() {
+            let Some(account) = self.accounts.get_mut(&address) else {
+                continue;
+            };
+            account.storage_root =
+                hashed_trie_root(account.storage.iter().map(|(key, value)| (key, &value[..])));
+            self.leaves.insert(
+                account.hashed_address,
+                trie_account_rlp(&account.info, account.storage_root),
+            );
+        }
+        let root = hashed_trie_root(self.leaves.iter().map(|(key, value)| (key, &value[..])));
+        self.root = Some(root);
+        root
+    }
+}
+
+/// Root of the trie holding `leaves`, sorted by their distinct hashed keys.
+fn hashed_trie_root<'a>(leaves: impl Iterator<Item = (&'a B256, &'a [u8])>) -> B256 {
+    let leaves: Vec<_> = leaves.collect();
+    if leaves.is_empty() {
+        return EMPTY_ROOT_HASH;
+    }
+    keccak256(&encode_node(&leaves, 0))
+}
+
+/// Encodes the node holding `leaves` below their first `depth` nibbles.
+fn encode_node(leaves: &[(&B256, &[u8])], depth: usize) -> Vec<u8> {
+    if let [(key, value)] = leaves {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&compact_path(key, depth, 64, true));
+        stream.append(value);
+        return stream.out().to_vec();
+    }
+
+    // leaves are sorted, so the first and last ones share the shortest prefix
+    let (first, last) = (leaves[0].0, leaves[leaves.len() - 1].0);
+    let mut shared = depth;
+    while nibble(first, shared) == nibble(last, shared) {
+        shared += 1;
+    }
+    if shared > depth {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&compact_path(first, depth, shared, false));
+        append_child(&mut stream, encode_node(leaves, shared));
+        return stream.out().to_vec();
+    }
+
+    let mut stream = RlpStream::new_list(17);
+    let mut rest = leaves;
+    for branch in 0..16 {
+        let len = rest
+            .iter()
+            .take_while(|(key, _)| nibble(key, depth) == branch)
+            .count();
+        if len == 0 {
+            stream.append_empty_data();
+        } else {
+            append_child(&mut stream, encode_node(&rest[..len], depth + 1));
+        }
+        rest = &rest[len..];
+    }
+    stream.append_empty_data();
+    stream.out().to_vec()
+}
+
+/// Nodes shorter than a hash are inlined in their parent.
+fn append_child(stream: &mut RlpStream, encoded: Vec<u8>) {
+    if encoded.len() < 32 {
+        stream.append_raw(&encoded, 1);
+    } else {
+        stream.append(&keccak256(&encoded).as_bytes());
+    }
+}
+
+fn nibble(key: &B256, index: usize) -> u8 {
+    let byte = key.0[index / 2];
+    if index & 1 == 0 {
+        byte >> 4
+    } else {
+        byte & 0x0f
+    }
+}
+
+/// Hex-prefix encoding of the nibbles `start..end` of `key`.
+fn compact_path(key: &B256, start: usize, end: usize, is_leaf: bool) -> Vec<u8> {
+    let odd = (end - start) % 2 == 1;
+    let mut path = Vec::with_capacity((end - start) / 2 + 1);
+    let flag = if is_leaf { 0x20 } else { 0x00 };
+    let mut index = start;
+    if odd {
+        path.push(flag | 0x10 | nibble(key, index));
+        index += 1;
+    } else {
+        path.push(flag);
+    }
+    while index < end {
+        path.push(nibble(key, index) << 4 | nibble(key, index + 1));
+        index += 2;
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Account, Bytecode, StorageSlot};
+
+    #[test]
+    fn roots_match_reference_values() {
+        assert_eq!(
+            sec_trie_root(Vec::<(Vec<u8>, Vec<u8>)>::new()),
+            EMPTY_ROOT_HASH
+        );
+
+        // roots computed with an independent implementation
+        let sender = B160(hex!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b"));
+        let contract = B160(hex!("1000000000000000000000000000000000000000"));
+        let coinbase = B160(hex!("2adc25665018aa1fe0e6bc666dac8fc2697ff9ba"));
+        let code = Bytecode::new_raw(hex!("602a60005560006000a000").to_vec().into());
+        let gas = U256::from(43487);
+
+        let mut state = State::default();
+        let mut account = |address, info: AccountInfo, storage: &[(u64, u64)]| {
+            let mut account = Account::from(info);
+            for (slot, value) in storage {
+                let slot_value = StorageSlot::new(U256::from(*value));
+                account.storage.insert(U256::from(*slot), slot_value);
+            }
+            state.insert(address, account);
+        };
+        let ether = U256::from(1_000_000_000_000_000_000u64);
+        let sender_balance = ether - gas * U256::from(10);
+        account(
+            sender,
+            AccountInfo::new(sender_balance, 1, Bytecode::new()),
+            &[],
+        );
+        account(contract, AccountInfo::new(U256::ZERO, 0, code), &[(0, 42)]);
+        account(
+            coinbase,
+            AccountInfo::new(gas * U256::from(3), 0, Bytecode::new()),
+            &[],
+        );
+        account(B160::from_low_u64_be(7), AccountInfo::default(), &[]);
+
+        let expected = B256(hex!(
+            "49154f81b4f4b6413d9f330cd7425d692e31b21a4b69528a566317c8054012e0"
+        ));
+        assert_eq!(state_root(&state), expected);
+
+        let mut cache = StateRootCache::new();
+        cache.commit(&state);
+        assert_eq!(cache.root(), expected);
+
+        // rolling a change back restores the root
+        let mut changed = state.clone();
+        changed
+            .get_mut(&contract)
+            .unwrap()
+            .storage
+            .get_mut(&U256::ZERO)
+            .unwrap()
+            .present_value = U256::from(43);
+        cache.commit(&changed);
+        assert_eq!(cache.root(), state_root(&changed));
+        assert_ne!(cache.root(), expected);
+        cache.commit(&state);
+        assert_eq!(cache.root(), expected);
+
+        cache.remove_account(coinbase);
+        state.remove(&coinbase);
+        assert_eq!(cache.root(), state_root(&state));
+    }
+}
@@ -0,0 +1,495 @@
+//! Foundry compatible cheatcodes.
+//!
+//! When [`CfgEnv::enable_cheatcodes`](crate::primitives::CfgEnv) is set, calls to
+//! [`CHEATCODE_ADDRESS`] are handled by the EVM instead of running code, so test scenarios can
+//! be written in Solidity against the usual `Vm` interface:
+//!
+//! * `deal(address,uint256)`, `store(address,bytes32,bytes32)`, `load(address,bytes32)` and
+//!   `etch(address,bytes)` read and write accounts. Writes are journaled in the frame of the
+//!   caller.
+//! * `warp(uint256)` and `roll(uint256)` set the block timestamp and number.
+//! * `prank(address)` sets `msg.sender` of the next call or create made by the caller.
+//! * `expectRevert()`, `expectRevert(bytes4)` and `expectRevert(bytes)` make the next call or
+//!   create of the caller succeed only if it reverts (with the given selector or data), and fail
+//!   otherwise.
+//! * `snapshot()` and `revertTo(uint256)` save and restore the whole state and environment.
+//!   Snapshots are dropped at the end of the transaction, as the state is then handed to the
+//!   database.
+
+use crate::db::Database;
+use crate::evm_impl::{CallResult, EVMData};
+use crate::interpreter::{return_ok, CallInputs, CreateInputs, Gas, InstructionResult};
+use crate::journaled_state::TransientStorage;
+use crate::primitives::{hex_literal::hex, Bytecode, Bytes, Env, Log, State, B160, U256};
+use alloc::vec::Vec;
+
+/// Address of the cheatcode contract, same as in Foundry.
+pub const CHEATCODE_ADDRESS: B160 = B160(hex!("7109709ecfa91a80626ff3989d68f67f5b1dd12d"));
+
+const DEAL: [u8; 4] = hex!("c88a5e6d");
+const PRANK: [u8; 4] = hex!("ca669fa7");
+const WARP: [u8; 4] = hex!("e5d6bf02");
+const ROLL: [u8; 4] = hex!("1f7b4f30");
+const STORE: [u8; 4] = hex!("70ca10bb");
+const LOAD: [u8; 4] = hex!("667f9d70");
+const ETCH: [u8; 4] = hex!("b4d6c782");
+const EXPECT_REVERT: [u8; 4] = hex!("f4844814");
+const EXPECT_REVERT_BYTES: [u8; 4] = hex!("f28dceb3");
+const EXPECT_REVERT_SELECTOR: [u8; 4] = hex!("c31eb0e0");
+const SNAPSHOT: [u8; 4] = hex!("9711715a");
+const REVERT_TO: [u8; 4] = hex!("44d7f0a4");
+/// Selector of `Error(string)`, used for revert messages.
+const ERROR: [u8; 4] = hex!("08c379a0");
+
+/// Cheatcode state carried between calls.
+#[derive(Debug, Clone, Default)]
+pub struct Cheatcodes {
+    prank: Option<Prank>,
+    expected_revert: Option<ExpectedRevert>,
+    snapshots: Vec<Snapshot>,
+}
+
+/// Next frame entered by `caller` from `depth` runs with `new_caller` as its caller.
+#[derive(Debug, Clone)]
+struct Prank {
+    caller: B160,
+    depth: u64,
+    new_caller: B160,
+}
+
+/// Next frame entered by `caller` from `depth` has to revert.
+#[derive(Debug, Clone)]
+struct ExpectedRevert {
+    caller: B160,
+    depth: u64,
+    data: RevertData,
+    /// Expected frame was entered, it is checked when it returns to `depth`.
+    entered: bool,
+}
+
+#[derive(Debug, Clone)]
+enum RevertData {
+    Any,
+    Selector([u8; 4]),
+    /// Exact revert data, or the message of an `Error(string)`.
+    Exact(Bytes),
+}
+
+#[derive(Debug, Clone)]
+struct Snapshot {
+    state: State,
+    transient_storage: TransientStorage,
+    logs: Vec<Log>,
+    env: Env,
+}
+
+impl Cheatcodes {
+    /// Handle a call to [`CHEATCODE_ADDRESS`]. Cheatcodes use no gas.
+    pub(crate) fn call<DB: Database>(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        inputs: &CallInputs,
+    ) -> CallResult {
+        let output = self.apply(data, inputs.context.caller, &inputs.input);
+        let (result, return_value) = match output {
+            _ if data.error.is_some() => (InstructionResult::FatalExternalError, Bytes::new()),
+            Ok(output) => (InstructionResult::Return, output),
+            Err(message) => (InstructionResult::Revert, error_message(message)),
+        };
+        CallResult {
+            result,
+            gas: Gas::new(inputs.gas_limit),
+            return_value,
+        }
+    }
+
+    /// Inputs of a call about to be entered from `depth`, if a prank changes them.
+    pub(crate) fn enter_call(&mut self, depth: u64, inputs: &CallInputs) -> Option<CallInputs> {
+        let new_caller = self.enter(depth, inputs.context.caller)?;
+        let mut inputs = inputs.clone();
+        if inputs.transfer.source == inputs.context.caller {
+            inputs.transfer.source = new_caller;
+        }
+        inputs.context.caller = new_caller;
+        Some(inputs)
+    }
+
+    /// Inputs of a create about to be entered from `depth`, if a prank changes them.
+    pub(crate) fn enter_create(
+        &mut self,
+        depth: u64,
+        inputs: &CreateInputs,
+    ) -> Option<CreateInputs> {
+        let new_caller = self.enter(depth, inputs.caller)?;
+        let mut inputs = inputs.clone();
+        inputs.caller = new_caller;
+        Some(inputs)
+    }
+
+    fn enter(&mut self, depth: u64, caller: B160) -> Option<B160> {
+        if let Some(expected) = &mut self.expected_revert {
+            if !expected.entered && expected.depth == depth && expected.caller == caller {
+                expected.entered = true;
+            }
+        }
+        match &self.prank {
+            Some(prank) if prank.depth == depth && prank.caller == caller => {
+                self.prank.take().map(|prank| prank.new_caller)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check a frame that returned to `depth` against the expected revert. Returns `true` if the
+    /// frame reverted as expected and its result was turned into a success.
+    pub(crate) fn exit(
+        &mut self,
+        depth: u64,
+        result: &mut InstructionResult,
+        output: &mut Bytes,
+    ) -> bool {
+        match &self.expected_revert {
+            Some(expected) if expected.entered && expected.depth == depth => (),
+            _ => return false,
+        }
+        let expected = self.expected_revert.take().unwrap();
+        let failure = if matches!(*result, return_ok!()) {
+            "call did not revert as expected"
+        } else if !expected.data.matches(output) {
+            "call reverted with unexpected data"
+        } else {
+            *result = InstructionResult::Return;
+            *output = Bytes::new();
+            return true;
+        };
+        *result = InstructionResult::Revert;
+        *output = error_message(failure);
+        false
+    }
+
+    fn apply<DB: Database>(
+        &mut self,
+        data: &mut EVMData<'_, DB>,
+        caller: B160,
+        input: &[u8],
+    ) -> Result<Bytes, &'static str> {
+        if input.len() < 4 {
+            return Err("cheatcode selector is missing");
+        }
+        let (selector, args) = input.split_at(4);
+        let args = Args(args);
+        let depth = data.journaled_state.depth();
+        match [selector[0], selector[1], selector[2], selector[3]] {
+            DEAL => {
+                let address = args.address(0)?;
+                load_code(data, address)?;
+                data.journaled_state.set_balance(address, args.word(1)?);
+            }
+            PRANK => {
+                self.prank = Some(Prank {
+                    caller,
+                    depth,
+                    new_caller: args.address(0)?,
+                });
+            }
+            WARP => data.env.block.timestamp = args.word(0)?,
+            ROLL => data.env.block.number = args.word(0)?,
+            STORE => {
+                let address = args.address(0)?;
+                load_code(data, address)?;
+                data.journaled_state
+                    .sstore(address, args.word(1)?, args.word(2)?, data.db)
+                    .map_err(|e| data.error = Some(e))
+                    .map_err(|_| "database error")?;
+            }
+            LOAD => {
+                let address = args.address(0)?;
+                load_code(data, address)?;
+                let (value, _) = data
+                    .journaled_state
+                    .sload(address, args.word(1)?, data.db)
+                    .map_err(|e| data.error = Some(e))
+                    .map_err(|_| "database error")?;
+                return Ok(value.to_be_bytes_vec().into());
+            }
+            ETCH => {
+                let address = args.address(0)?;
+                let code = Bytes::copy_from_slice(args.bytes(1)?);
+                load_code(data, address)?;
+                data.journaled_state
+                    .set_code(address, Bytecode::new_raw(code));
+            }
+            EXPECT_REVERT => self.expect_revert(caller, depth, RevertData::Any)?,
+            EXPECT_REVERT_SELECTOR => {
+                let word = args.word(0)?.to_be_bytes::<32>();
+                let selector = [word[0], word[1], word[2], word[3]];
+                self.expect_revert(caller, depth, RevertData::Selector(selector))?
+            }
+            EXPECT_REVERT_BYTES => {
+                let data = Bytes::copy_from_slice(args.bytes(0)?);
+                self.expect_revert(caller, depth, RevertData::Exact(data))?
+            }
+            SNAPSHOT => {
+                let journal = &data.journaled_state;
+                self.snapshots.push(Snapshot {
+                    state: journal.state.clone(),
+                    transient_storage: journal.transient_storage.clone(),
+                    logs: journal.logs.clone(),
+                    env: data.env.clone(),
+                });
+                return Ok(word(U256::from(self.snapshots.len() - 1)));
+            }
+            REVERT_TO => {
+                let snapshot = usize::try_from(args.word(0)?)
+                    .ok()
+                    .and_then(|id| self.snapshots.get(id));
+                let Some(snapshot) = snapshot else {
+                    return Ok(word(U256::ZERO));
+                };
+                let journal = &mut data.journaled_state;
+                journal.state = snapshot.state.clone();
+                journal.transient_storage = snapshot.transient_storage.clone();
+                journal.logs = snapshot.logs.clone();
+                // entries of the open frames refer to the replaced state, reverting one of those
+                // frames later keeps the snapshot.
+                journal.journal.iter_mut().for_each(Vec::clear);
+                *data.env = snapshot.env.clone();
+                return Ok(word(U256::from(1)));
+            }
+            _ => return Err("unknown cheatcode"),
+        }
+        Ok(Bytes::new())
+    }
+
+    fn expect_revert(
+        &mut self,
+        caller: B160,
+        depth: u64,
+        data: RevertData,
+    ) -> Result<(), &'static str> {
+        if self.expected_revert.is_some() {
+            return Err("a revert is already expected");
+        }
+        self.expected_revert = Some(ExpectedRevert {
+            caller,
+            depth,
+            data,
+            entered: false,
+        });
+        Ok(())
+    }
+}
+
+impl RevertData {
+    fn matches(&self, output: &[u8]) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Selector(selector) => output.starts_with(selector),
+            Self::Exact(data) => output == &data[..] || decode_error(output) == Some(&data[..]),
+        }
+    }
+}
+
+/// ABI encoded arguments of a cheatcode.
+struct Args<'a>(&'a [u8]);
+
+impl<'a> Args<'a> {
+    fn word(&self, index: usize) -> Result<U256, &'static str> {
+        self.word_at(index * 32)
+    }
+
+    fn word_at(&self, offset: usize) -> Result<U256, &'static str> {
+        let bytes = self
+            .0
+            .get(offset..offset.saturating_add(32))
+            .ok_or("cheatcode arguments are too short")?;
+        Ok(U256::from_be_bytes::<32>(bytes.try_into().unwrap()))
+    }
+
+    fn address(&self, index: usize) -> Result<B160, &'static str> {
+        Ok(B160::from_slice(
+            &self.word(index)?.to_be_bytes::<32>()[12..],
+        ))
+    }
+
+    /// Dynamic `bytes` argument.
+    fn bytes(&self, index: usize) -> Result<&'a [u8], &'static str> {
+        let invalid = "cheatcode bytes argument is invalid";
+        let offset = usize::try_from(self.word(index)?).map_err(|_| invalid)?;
+        let len = usize::try_from(self.word_at(offset)?).map_err(|_| invalid)?;
+        let start = offset + 32;
+        self.0.get(start..start.saturating_add(len)).ok_or(invalid)
+    }
+}
+
+fn load_code<DB: Database>(data: &mut EVMData<'_, DB>, address: B160) -> Result<(), &'static str> {
+    data.journaled_state
+        .load_code(address, data.db)
+        .map(|_| ())
+        .map_err(|e| data.error = Some(e))
+        .map_err(|_| "database error")
+}
+
+fn word(value: U256) -> Bytes {
+    value.to_be_bytes_vec().into()
+}
+
+/// Revert data of `Error(message)`.
+fn error_message(message: &str) -> Bytes {
+    let mut data = ERROR.to_vec();
+    data.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    data.extend_from_slice(&U256::from(message.len()).to_be_bytes::<32>());
+    data.extend_from_slice(message.as_bytes());
+    data.resize(data.len() + (32 - message.len() % 32) % 32, 0);
+    data.into()
+}
+
+/// Message of an `Error(string)` revert.
+fn decode_error(output: &[u8]) -> Option<&[u8]> {
+    Args(output.strip_prefix(&ERROR)?).bytes(0).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::{asm::assemble, CallContext, CallScheme, Transfer};
+    use crate::primitives::{AccountInfo, LatestSpec, TransactTo, B256};
+    use crate::{EVMImpl, InMemoryDB};
+
+    type Evm<'a> = EVMImpl<'a, LatestSpec, InMemoryDB>;
+
+    fn call_inputs(caller: B160, contract: B160, input: Bytes) -> CallInputs {
+        CallInputs {
+            contract,
+            transfer: Transfer {
+                source: caller,
+                target: contract,
+                value: U256::ZERO,
+            },
+            input,
+            gas_limit: 1_000_000,
+            context: CallContext {
+                caller,
+                address: contract,
+                code_address: contract,
+                apparent_value: U256::ZERO,
+                scheme: CallScheme::Call,
+            },
+            is_static: false,
+        }
+    }
+
+    fn cheat(selector: [u8; 4], args: &[U256]) -> Bytes {
+        let mut input = selector.to_vec();
+        args.iter()
+            .for_each(|arg| input.extend_from_slice(&arg.to_be_bytes::<32>()));
+        input.into()
+    }
+
+    fn address_word(address: B160) -> U256 {
+        U256::from_be_bytes(B256::from(address).0)
+    }
+
+    #[test]
+    fn cheatcodes() {
+        let test = B160::from(0x7e57);
+        let alice = B160::from(0xa11ce);
+        let echo = B160::from(0xec40);
+        let mut db = InMemoryDB::default();
+        let mut env = Env::default();
+        env.cfg.enable_cheatcodes = true;
+        env.tx.transact_to = TransactTo::Call(test);
+        let mut evm = Evm::new(&mut db, &mut env, Default::default());
+        let vm = |evm: &mut Evm<'_>, input: Bytes| {
+            let ret = evm.call_nested(&call_inputs(test, CHEATCODE_ADDRESS, input));
+            assert_eq!(ret.result, InstructionResult::Return);
+            ret.return_value
+        };
+
+        // etch a contract returning its caller.
+        let code = assemble("CALLER PUSH0 MSTORE PUSH1 32 PUSH0 RETURN").unwrap();
+        let mut etch = cheat(
+            ETCH,
+            &[address_word(echo), U256::from(64), U256::from(code.len())],
+        );
+        let mut padded = code.to_vec();
+        padded.resize(32, 0);
+        etch = [&etch[..], &padded].concat().into();
+        vm(&mut evm, etch);
+        let caller = |evm: &mut Evm<'_>| evm.call_nested(&call_inputs(test, echo, Bytes::new()));
+        assert_eq!(caller(&mut evm).return_value[12..], test[..]);
+        vm(&mut evm, cheat(PRANK, &[address_word(alice)]));
+        assert_eq!(caller(&mut evm).return_value[12..], alice[..]);
+        assert_eq!(caller(&mut evm).return_value[12..], test[..]);
+
+        // deal, store and load, and a snapshot restoring them.
+        let id = vm(&mut evm, cheat(SNAPSHOT, &[]));
+        vm(
+            &mut evm,
+            cheat(DEAL, &[address_word(alice), U256::from(1000)]),
+        );
+        let slot = [address_word(echo), U256::from(1), U256::from(7)];
+        vm(&mut evm, cheat(STORE, &slot));
+        assert_eq!(
+            vm(&mut evm, cheat(LOAD, &slot[..2])),
+            U256::from(7).to_be_bytes_vec()
+        );
+        assert_eq!(
+            evm.data.journaled_state.account(alice).info.balance,
+            U256::from(1000)
+        );
+        let id = U256::from_be_bytes::<32>(id[..].try_into().unwrap());
+        assert_eq!(vm(&mut evm, cheat(REVERT_TO, &[id])), word(U256::from(1)));
+        assert_eq!(vm(&mut evm, cheat(LOAD, &slot[..2])), word(U256::ZERO));
+        assert_eq!(
+            vm(&mut evm, cheat(REVERT_TO, &[U256::from(9)])),
+            word(U256::ZERO)
+        );
+
+        // expected revert is turned into success, an unexpected success into a revert.
+        let reverter = B160::from(0xdead);
+        let code = assemble("PUSH4 0xDEADBEEF PUSH1 0xE0 SHL PUSH0 MSTORE PUSH1 4 PUSH0 REVERT");
+        let code = code.unwrap();
+        evm.data.db.insert_account_info(
+            reverter,
+            AccountInfo::new(U256::ZERO, 0, Bytecode::new_raw(code)),
+        );
+        let revert =
+            |evm: &mut Evm<'_>| evm.call_nested(&call_inputs(test, reverter, Bytes::new()));
+        assert_eq!(revert(&mut evm).result, InstructionResult::Revert);
+        let selector = U256::from(0xdeadbeefu32) << 224;
+        vm(&mut evm, cheat(EXPECT_REVERT_SELECTOR, &[selector]));
+        assert_eq!(revert(&mut evm).result, InstructionResult::Return);
+        vm(
+            &mut evm,
+            cheat(EXPECT_REVERT_SELECTOR, &[U256::from(1) << 224]),
+        );
+        let ret = revert(&mut evm);
+        assert_eq!(ret.result, InstructionResult::Revert);
+        assert_eq!(
+            decode_error(&ret.return_value),
+            Some(&b"call reverted with unexpected data"[..])
+        );
+        vm(&mut evm, cheat(EXPECT_REVERT, &[]));
+        assert_eq!(caller(&mut evm).result, InstructionResult::Revert);
+
+        // warp from a contract, the cheatcode call is a nested call of a stuck frame.
+        let warp = B160::from(0x3a49);
+        let code = format!(
+            "PUSH4 0xE5D6BF02 PUSH1 0xE0 SHL PUSH0 MSTORE PUSH2 1234 PUSH1 4 MSTORE
+             PUSH0 PUSH0 PUSH1 36 PUSH0 PUSH0 PUSH20 0x{} GAS CALL POP
+             TIMESTAMP PUSH0 MSTORE PUSH1 32 PUSH0 RETURN",
+            crate::primitives::hex::encode_upper(CHEATCODE_ADDRESS)
+        );
+        let code = assemble(&code).unwrap();
+        evm.data.db.insert_account_info(
+            warp,
+            AccountInfo::new(U256::ZERO, 0, Bytecode::new_raw(code)),
+        );
+        let depth = evm.data.journaled_state.depth();
+        let ret = evm.call_nested(&call_inputs(test, warp, Bytes::new()));
+        assert_eq!(ret.result, InstructionResult::Return);
+        assert_eq!(ret.return_value, word(U256::from(1234)));
+        assert_eq!(evm.data.journaled_state.depth(), depth);
+    }
+}
@@ -1,7 +1,7 @@
 use super::{DatabaseCommit, DatabaseRef};
 use crate::primitives::{
-    hash_map::Entry, keccak256, Account, AccountInfo, Bytecode, HashMap, Log, B160, B256,
-    KECCAK_EMPTY, U256,
+    hash_map::Entry, keccak256, sec_trie_root, storage_root, trie_account_rlp, Account,
+    AccountInfo, Bytecode, HashMap, Log, B160, B256, KECCAK_EMPTY, U256,
 };
 use crate::Database;
 use alloc::vec::Vec;
@@ -119,6 +119,27 @@ impl<ExtDB: DatabaseRef> CacheDB<ExtDB> {
         account.storage = storage.into_iter().collect();
         Ok(())
     }
+
+    /// Returns the state root of the cached accounts.
+    ///
+    /// Not existing and empty accounts are left out (EIP-161). Accounts and slots that were never
+    /// loaded from the underlying database are not covered.
+    pub fn state_root(&self) -> B256 {
+        sec_trie_root(
+            self.accounts
+                .iter()
+                .filter(|(_, account)| {
+                    !matches!(account.account_state, AccountState::NotExisting)
+                        && !account.info.is_empty()
+                })
+                .map(|(address, account)| {
+                    (
+                        address,
+                        trie_account_rlp(&account.info, account.storage_root()),
+                    )
+                }),
+        )
+    }
 }
 
 impl<ExtDB: DatabaseRef> DatabaseCommit for CacheDB<ExtDB> {
@@ -300,6 +321,12 @@ impl DbAccount {
             ..Default::default()
         }
     }
+
+    /// Root of the storage trie of the account.
+    pub fn storage_root(&self) -> B256 {
+        storage_root(&self.storage)
+    }
+
     pub fn info(&self) -> Option<AccountInfo> {
         if matches!(self.account_state, AccountState::NotExisting) {
             None
@@ -435,7 +462,9 @@ impl Database for BenchmarkDB {
 #[cfg(test)]
 mod tests {
     use super::{CacheDB, EmptyDB};
-    use crate::primitives::{db::Database, AccountInfo, U256};
+    use crate::primitives::{
+        db::Database, state_root, Account, AccountInfo, State, StorageSlot, EMPTY_ROOT_HASH, U256,
+    };
 
     #[test]
     pub fn test_insert_account_storage() {
@@ -482,4 +511,31 @@ mod tests {
         assert_eq!(new_state.storage(account, key0), Ok(U256::ZERO));
         assert_eq!(new_state.storage(account, key1), Ok(value1));
     }
+
+    #[test]
+    pub fn test_state_root() {
+        let account = 42.into();
+        let mut db = CacheDB::new(EmptyDB::default());
+        assert_eq!(db.state_root(), EMPTY_ROOT_HASH);
+
+        // loading a missing account does not add it to the state
+        let _ = db.basic(7.into());
+        assert_eq!(db.state_root(), EMPTY_ROOT_HASH);
+
+        let info = AccountInfo {
+            nonce: 1,
+            ..Default::default()
+        };
+        db.insert_account_info(account, info.clone());
+        let _ = db.insert_account_storage(account, U256::from(1), U256::from(2));
+        let _ = db.insert_account_storage(account, U256::from(3), U256::ZERO);
+
+        let mut state = State::default();
+        let mut expected = Account::from(db.accounts[&account].info.clone());
+        expected
+            .storage
+            .insert(U256::from(1), StorageSlot::new(U256::from(2)));
+        state.insert(account, expected);
+        assert_eq!(db.state_root(), state_root(&state));
+    }
 }
@@ -1,9 +1,11 @@
 use crate::interpreter::{
     analysis::to_analysed, gas, instruction_result::SuccessOrHalt, return_ok, return_revert,
     CallContext, CallInputs, CallScheme, Contract, CreateInputs, CreateScheme, Gas, Host,
-    InstructionResult, Interpreter, SelfDestructResult, Transfer, CALL_STACK_LIMIT,
+    InstructionResult, Interpreter, SelfDestructResult, StuckReason, Transfer, CALL_STACK_LIMIT,
 };
-use crate::journaled_state::{is_precompile, JournalCheckpoint};
+use crate::cheatcodes::{Cheatcodes, CHEATCODE_ADDRESS};
+use crate::journaled_state::JournalCheckpoint;
+use crate::tracer::{StepEvent, TraceStep};
 use crate::primitives::{
     create2_address, create_address, keccak256, Account, AnalysisKind, Bytecode, Bytes, EVMError,
     Env, HashMap, InvalidTransaction, Log,
@@ -15,10 +17,10 @@ use crate::result::*;
 use crate::{db::Database, journaled_state::JournaledState, precompile};
 use alloc::boxed::Box;
 use alloc::vec::Vec;
-use core::{cmp::min, marker::PhantomData};
+use core::{cmp::min, marker::PhantomData, mem};
 use revm_interpreter::gas::initial_tx_gas;
 use revm_interpreter::MAX_CODE_SIZE;
-use revm_precompile::{Precompile, Precompiles};
+use revm_precompile::{Precompile, PrecompileContext, Precompiles};
 
 #[derive(Debug)]
 pub struct EVMData<'a, DB: Database> {
@@ -31,6 +33,14 @@ pub struct EVMData<'a, DB: Database> {
 pub struct EVMImpl<'a, GSPEC: Spec, DB: Database> {
     pub data: EVMData<'a, DB>,
     precompiles: Precompiles,
+    /// Checkpoints of stuck frames, the last one belongs to the frame resumed next.
+    suspended: Vec<JournalCheckpoint>,
+    /// Set when [`CfgEnv::enable_cheatcodes`](crate::primitives::CfgEnv) is.
+    cheatcodes: Option<Cheatcodes>,
+    /// Set when [`CfgEnv::trace_steps`](crate::primitives::CfgEnv) is.
+    tracer: Option<Vec<TraceStep>>,
+    /// Set when [`CfgEnv::trace_events`](crate::primitives::CfgEnv) is.
+    events: Option<Vec<StepEvent>>,
     _phantomdata: PhantomData<GSPEC>,
 }
 
@@ -57,7 +67,7 @@ pub struct CreateResult {
     pub return_value: Bytes,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 
 pub struct CallResult {
     pub result: InstructionResult,
@@ -148,7 +158,7 @@ impl<'a, GSPEC: Spec, DB: Database> Transact<DB::Error>
                 // Nonce is already checked
                 caller_account.info.nonce =
                     caller_account.info.nonce.checked_add(1).unwrap_or(u64::MAX);
-                let (call_result, maybe_interpreter) = self.call(&mut CallInputs {
+                let inputs = CallInputs {
                     contract: address,
                     transfer: Transfer {
                         source: tx_caller,
@@ -165,20 +175,30 @@ impl<'a, GSPEC: Spec, DB: Database> Transact<DB::Error>
                         scheme: CallScheme::Call,
                     },
                     is_static: false,
-                }, interpreter);
+                };
+                let (call_result, maybe_interpreter) = if self.data.env.cfg.run_nested_frames {
+                    (self.call_nested(&inputs), None)
+                } else {
+                    self.call(&inputs, interpreter)
+                };
                 match maybe_interpreter {
                     None => (call_result.result, call_result.gas, Output::Call(call_result)),
                     Some(interpreter) => return Ok(ResultAndState{ result: ExecutionResult::Stuck{interpreter}, state: HashMap::new() }),
                 }
             }
             TransactTo::Create(scheme) => {
-                let (create_result, maybe_interpreter) = self.create(&mut CreateInputs {
+                let inputs = CreateInputs {
                     caller: tx_caller,
                     scheme,
                     value: tx_value,
                     init_code: tx_data,
                     gas_limit: transact_gas_limit,
-                }, interpreter);
+                };
+                let (create_result, maybe_interpreter) = if self.data.env.cfg.run_nested_frames {
+                    (self.create_nested(&inputs), None)
+                } else {
+                    self.create(&inputs, interpreter)
+                };
                 match maybe_interpreter {
                     None => (create_result.result, create_result.gas, Output::Create(create_result)),
                     Some(interpreter) => return Ok(ResultAndState{ result: ExecutionResult::Stuck{interpreter}, state: HashMap::new() }),
@@ -241,10 +261,13 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
         precompiles: Precompiles,
     ) -> Self {
         let journaled_state = if GSPEC::enabled(SpecId::SPURIOUS_DRAGON) {
-            JournaledState::new(precompiles.len())
+            JournaledState::new(precompiles.consecutive_len())
         } else {
-            JournaledState::new_legacy(precompiles.len())
+            JournaledState::new_legacy(precompiles.consecutive_len())
         };
+        let cheatcodes = env.cfg.enable_cheatcodes.then(Cheatcodes::default);
+        let tracer = env.cfg.trace_steps.then(Vec::new);
+        let events = env.cfg.trace_events.then(Vec::new);
         Self {
             data: EVMData {
                 env,
@@ -253,10 +276,47 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
                 error: None,
             },
             precompiles,
+            suspended: Vec::new(),
+            cheatcodes,
+            tracer,
+            events,
             _phantomdata: PhantomData {},
         }
     }
 
+    /// Checkpoints of the stuck frames, the last one belongs to the frame resumed next.
+    pub fn suspended(&self) -> &[JournalCheckpoint] {
+        &self.suspended
+    }
+
+    /// Replaces the checkpoints of the stuck frames, e.g. when restoring a snapshot of the
+    /// journaled state taken together with them.
+    pub fn set_suspended(&mut self, suspended: Vec<JournalCheckpoint>) {
+        self.suspended = suspended;
+    }
+
+    /// Steps recorded since the last call, empty unless
+    /// [`CfgEnv::trace_steps`](crate::primitives::CfgEnv) is set.
+    pub fn take_trace(&mut self) -> Vec<TraceStep> {
+        self.tracer.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Events recorded since the last call, empty unless
+    /// [`CfgEnv::trace_events`](crate::primitives::CfgEnv) is set.
+    pub fn take_events(&mut self) -> Vec<StepEvent> {
+        self.events.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Runs `f` without recording its steps, e.g. calls on the side of the traced execution.
+    pub fn untraced<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let tracer = self.tracer.take();
+        let events = self.events.take();
+        let result = f(self);
+        self.tracer = tracer;
+        self.events = events;
+        result
+    }
+
     pub fn finalize<SPEC: Spec>(&mut self, gas: &Gas) -> (HashMap<B160, Account>, Vec<Log>, u64, u64) {
         let caller = self.data.env.tx.caller;
         let coinbase = self.data.env.block.coinbase;
@@ -311,6 +371,9 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
             (0, 0)
         };
         let (new_state, logs) = self.data.journaled_state.finalize();
+        if let Some(cheatcodes) = &mut self.cheatcodes {
+            *cheatcodes = Cheatcodes::default();
+        }
         (new_state, logs, gas_used, gas_refunded)
     }
 
@@ -406,6 +469,7 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
             created_address,
             inputs.caller,
             inputs.value,
+            GSPEC::SPEC_ID,
         ));
 
         Ok(PreparedCreate {
@@ -417,30 +481,68 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
     }
 
     /// EVM create opcode for both initial crate and CREATE and CREATE2 opcodes.
+    ///
+    /// Stuck frames are suspended and resumed like in [`Self::call`].
     pub fn create(&mut self, inputs: &CreateInputs, interpreter: Option<Box<Interpreter>>) -> (CreateResult, Option<Box<Interpreter>>) {
-        let res = self.prepare_create(inputs);
-
-        let prepared_create = match res {
-            Ok(o) => o,
-            Err(e) => return (e, None),
+        let Some(cheatcodes) = &mut self.cheatcodes else {
+            return self.create_frame(inputs, interpreter);
         };
+        let mut pranked = None;
+        if interpreter.is_none() {
+            pranked = cheatcodes.enter_create(self.data.journaled_state.depth(), inputs);
+        }
+        let (mut ret, interpreter) = self.create_frame(pranked.as_ref().unwrap_or(inputs), interpreter);
+        if interpreter.is_none() {
+            let depth = self.data.journaled_state.depth();
+            if let Some(cheatcodes) = &mut self.cheatcodes {
+                if cheatcodes.exit(depth, &mut ret.result, &mut ret.return_value) {
+                    ret.created_address = None;
+                }
+            }
+        }
+        (ret, interpreter)
+    }
 
-        // Create new interpreter and execute initcode
-        let (exit_reason, mut interpreter) = if let Some(mut interpreter) = interpreter {
-            (interpreter.run::<Self, GSPEC>(self), interpreter)
-        } else {
-            self.run_interpreter(prepared_create.contract, prepared_create.gas.limit(), false)
+    fn create_frame(&mut self, inputs: &CreateInputs, interpreter: Option<Box<Interpreter>>) -> (CreateResult, Option<Box<Interpreter>>) {
+        let (created_address, checkpoint, exit_reason, mut interpreter) = match interpreter {
+            // Resumed frame was already prepared before it got stuck.
+            Some(mut interpreter) => {
+                let checkpoint = self.suspended.pop().expect("resumed create is suspended");
+                interpreter.instruction_result = InstructionResult::Continue;
+                let exit_reason = self.run_frame(&mut interpreter);
+                (interpreter.contract.address, checkpoint, exit_reason, interpreter)
+            }
+            None => {
+                let prepared_create = match self.prepare_create(inputs) {
+                    Ok(o) => o,
+                    Err(e) => return (e, None),
+                };
+                // Create new interpreter and execute initcode
+                let (exit_reason, interpreter) = self.run_interpreter(
+                    prepared_create.contract,
+                    prepared_create.gas.limit(),
+                    false,
+                );
+                (
+                    prepared_create.created_address,
+                    prepared_create.checkpoint,
+                    exit_reason,
+                    interpreter,
+                )
+            }
         };
 
         // Host error if present on execution
         match exit_reason {
-            InstructionResult::Stuck =>
+            InstructionResult::Stuck => {
+                self.suspended.push(checkpoint);
                 (CreateResult {
                     result: InstructionResult::Stuck,
-                    created_address: Some(prepared_create.created_address),
+                    created_address: Some(created_address),
                     gas: interpreter.gas,
                     return_value: interpreter.return_value()
-                }, Some(interpreter)),
+                }, Some(interpreter))
+            }
             return_ok!() => ({
                 // if ok, check contract creation limit and calculate gas deduction on output len.
                 let mut bytes = interpreter.return_value();
@@ -449,10 +551,10 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
                 if GSPEC::enabled(LONDON) && !bytes.is_empty() && bytes.first() == Some(&0xEF) {
                     self.data
                         .journaled_state
-                        .checkpoint_revert(prepared_create.checkpoint);
+                        .checkpoint_revert(checkpoint);
                     return (CreateResult {
                         result: InstructionResult::CreateContractStartingWithEF,
-                        created_address: Some(prepared_create.created_address),
+                        created_address: Some(created_address),
                         gas: interpreter.gas,
                         return_value: bytes,
                     }, None);
@@ -471,10 +573,10 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
                 {
                     self.data
                         .journaled_state
-                        .checkpoint_revert(prepared_create.checkpoint);
+                        .checkpoint_revert(checkpoint);
                     return (CreateResult {
                         result: InstructionResult::CreateContractSizeLimit,
-                        created_address: Some(prepared_create.created_address),
+                        created_address: Some(created_address),
                         gas: interpreter.gas,
                         return_value: bytes,
                     }, None);
@@ -489,10 +591,10 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
                         if GSPEC::enabled(HOMESTEAD) {
                             self.data
                                 .journaled_state
-                                .checkpoint_revert(prepared_create.checkpoint);
+                                .checkpoint_revert(checkpoint);
                             return (CreateResult {
                                 result: InstructionResult::OutOfGas,
-                                created_address: Some(prepared_create.created_address),
+                                created_address: Some(created_address),
                                 gas: interpreter.gas,
                                 return_value: bytes,
                             }, None);
@@ -507,14 +609,16 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
                 let bytecode = match self.data.env.cfg.perf_analyse_created_bytecodes {
                     AnalysisKind::Raw => Bytecode::new_raw(bytes.clone()),
                     AnalysisKind::Check => Bytecode::new_raw(bytes.clone()).to_checked(),
-                    AnalysisKind::Analyse => to_analysed(Bytecode::new_raw(bytes.clone())),
+                    AnalysisKind::Analyse => {
+                        to_analysed(Bytecode::new_raw(bytes.clone()), GSPEC::SPEC_ID)
+                    }
                 };
                 self.data
                     .journaled_state
-                    .set_code(prepared_create.created_address, bytecode);
+                    .set_code(created_address, bytecode);
                 CreateResult {
                     result: InstructionResult::Return,
-                    created_address: Some(prepared_create.created_address),
+                    created_address: Some(created_address),
                     gas: interpreter.gas,
                     return_value: bytes,
                 }
@@ -522,10 +626,10 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
             _ => {
                 self.data
                     .journaled_state
-                    .checkpoint_revert(prepared_create.checkpoint);
+                    .checkpoint_revert(checkpoint);
                 (CreateResult {
                     result: exit_reason,
-                    created_address: Some(prepared_create.created_address),
+                    created_address: Some(created_address),
                     gas: interpreter.gas,
                     return_value: interpreter.return_value(),
                 }, None)
@@ -553,11 +657,29 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
         #[cfg(not(feature = "memory_limit"))]
         let mut interpreter = Box::new(Interpreter::new(contract, gas_limit, is_static));
 
-        let exit_reason = interpreter.run::<Self, GSPEC>(self);
+        let exit_reason = self.run_frame(&mut interpreter);
 
         (exit_reason, interpreter)
     }
 
+    /// Runs the interpreter until it exits or gets stuck, recording its steps when tracing.
+    fn run_frame(&mut self, interpreter: &mut Interpreter) -> InstructionResult {
+        if self.tracer.is_none() && self.events.is_none() {
+            return interpreter.run::<Self, GSPEC>(self);
+        }
+        while interpreter.instruction_result == InstructionResult::Continue {
+            let depth = self.data.journaled_state.depth();
+            if let Some(tracer) = &mut self.tracer {
+                tracer.push(TraceStep::new(interpreter, depth));
+            }
+            if let Some(events) = &mut self.events {
+                events.push(StepEvent::new(interpreter, depth));
+            }
+            interpreter.step::<Self, GSPEC>(self);
+        }
+        interpreter.instruction_result
+    }
+
     /// Call precompile contract
     fn call_precompile(&mut self, inputs: &CallInputs, mut gas: Gas) -> CallResult {
         let input_data = inputs.input.clone();
@@ -570,6 +692,21 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
         let out = match precompile {
             Precompile::Standard(fun) => fun(&input_data, gas.limit()),
             Precompile::Custom(fun) => fun(&input_data, gas.limit()),
+            Precompile::Stateful(precompile) => {
+                let mut context = PrecompileFrame {
+                    data: &mut self.data,
+                    inputs,
+                };
+                let out = precompile.call(&input_data, gas.limit(), &mut context);
+                if self.data.error.is_some() {
+                    return CallResult {
+                        result: InstructionResult::FatalExternalError,
+                        gas,
+                        return_value: Bytes::new(),
+                    };
+                }
+                out
+            }
         };
         match out {
             Ok((gas_used, data)) => {
@@ -650,6 +787,7 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
             inputs.input.clone(),
             bytecode,
             &inputs.context,
+            GSPEC::SPEC_ID,
         ));
 
         Ok(PreparedCall {
@@ -660,56 +798,218 @@ impl<'a, GSPEC: Spec, DB: Database> EVMImpl<'a, GSPEC, DB> {
     }
 
     /// Main contract call of the EVM.
+    ///
+    /// A frame that gets stuck keeps its journal checkpoint until it is resumed by passing its
+    /// interpreter back. Stuck frames are resumed in reverse order.
     pub fn call(&mut self, inputs: &CallInputs, interpreter: Option<Box<Interpreter>>) -> (CallResult, Option<Box<Interpreter>>) {
-        let res = self.prepare_call(inputs);
-
-        let prepared_call = match res {
-            Ok(o) => o,
-            Err(e) => return (e, None),
+        let Some(cheatcodes) = &mut self.cheatcodes else {
+            return self.call_frame(inputs, interpreter);
         };
+        let mut pranked = None;
+        if interpreter.is_none() {
+            if inputs.contract == CHEATCODE_ADDRESS {
+                return (cheatcodes.call(&mut self.data, inputs), None);
+            }
+            pranked = cheatcodes.enter_call(self.data.journaled_state.depth(), inputs);
+        }
+        let (mut ret, interpreter) = self.call_frame(pranked.as_ref().unwrap_or(inputs), interpreter);
+        if interpreter.is_none() {
+            let depth = self.data.journaled_state.depth();
+            if let Some(cheatcodes) = &mut self.cheatcodes {
+                cheatcodes.exit(depth, &mut ret.result, &mut ret.return_value);
+            }
+        }
+        (ret, interpreter)
+    }
 
-        let ret = if is_precompile(inputs.contract, self.precompiles.len()) {
-            (self.call_precompile(inputs, prepared_call.gas), None)
-        } else if !prepared_call.contract.bytecode.is_empty() {
-            // Create interpreter and execute subcall
-            let (exit_reason, interpreter) = interpreter
-                .map(|mut x| (x.run::<Self, GSPEC>(self), x))
-                .unwrap_or_else(|| self.run_interpreter(
+    fn call_frame(&mut self, inputs: &CallInputs, interpreter: Option<Box<Interpreter>>) -> (CallResult, Option<Box<Interpreter>>) {
+        let (checkpoint, exit_reason, interpreter) = match interpreter {
+            // Resumed frame was already prepared before it got stuck.
+            Some(mut interpreter) => {
+                let checkpoint = self.suspended.pop().expect("resumed call is suspended");
+                interpreter.instruction_result = InstructionResult::Continue;
+                let exit_reason = self.run_frame(&mut interpreter);
+                (checkpoint, exit_reason, interpreter)
+            }
+            None => {
+                let prepared_call = match self.prepare_call(inputs) {
+                    Ok(o) => o,
+                    Err(e) => return (e, None),
+                };
+                if self.precompiles.contains(&inputs.contract) {
+                    let ret = self.call_precompile(inputs, prepared_call.gas);
+                    return (self.end_call(prepared_call.checkpoint, ret), None);
+                }
+                if prepared_call.contract.bytecode.is_empty() {
+                    let ret = CallResult {
+                        result: InstructionResult::Stop,
+                        gas: prepared_call.gas,
+                        return_value: Bytes::new(),
+                    };
+                    return (self.end_call(prepared_call.checkpoint, ret), None);
+                }
+                // Create interpreter and execute subcall
+                let (exit_reason, interpreter) = self.run_interpreter(
                     prepared_call.contract,
                     prepared_call.gas.limit(),
                     inputs.is_static,
-                ));
-            if matches!(exit_reason, InstructionResult::Stuck) {
-                return (CallResult {
-                    result: exit_reason,
-                    gas: interpreter.gas,
-                    return_value: interpreter.return_value(),
-                }, Some(interpreter))
-            } else {
-                (CallResult {
-                    result: exit_reason,
-                    gas: interpreter.gas,
-                    return_value: interpreter.return_value(),
-                }, None)
+                );
+                (prepared_call.checkpoint, exit_reason, interpreter)
             }
-        } else {
-            (CallResult {
-                result: InstructionResult::Stop,
-                gas: prepared_call.gas,
-                return_value: Bytes::new(),
-            }, None)
         };
 
-        // revert changes or not.
-        if matches!(ret.0.result, return_ok!()) {
+        let ret = CallResult {
+            result: exit_reason,
+            gas: interpreter.gas,
+            return_value: interpreter.return_value(),
+        };
+        if matches!(exit_reason, InstructionResult::Stuck) {
+            self.suspended.push(checkpoint);
+            return (ret, Some(interpreter));
+        }
+        (self.end_call(checkpoint, ret), None)
+    }
+
+    /// Run a call to its end, doing the nested calls and creates of stuck frames in place.
+    pub fn call_nested(&mut self, inputs: &CallInputs) -> CallResult {
+        let (mut ret, mut stuck) = self.call(inputs, None);
+        while let Some(mut interpreter) = stuck {
+            self.run_nested(&mut interpreter);
+            (ret, stuck) = self.call(inputs, Some(interpreter));
+        }
+        ret
+    }
+
+    /// Run a create to its end, like [`Self::call_nested`].
+    pub fn create_nested(&mut self, inputs: &CreateInputs) -> CreateResult {
+        let (mut ret, mut stuck) = self.create(inputs, None);
+        while let Some(mut interpreter) = stuck {
+            self.run_nested(&mut interpreter);
+            (ret, stuck) = self.create(inputs, Some(interpreter));
+        }
+        ret
+    }
+
+    /// Run the frame a stuck interpreter waits for and hand it the result.
+    fn run_nested(&mut self, interpreter: &mut Interpreter) {
+        interpreter.stuck_reason = match mem::replace(&mut interpreter.stuck_reason, StuckReason::Execute) {
+            StuckReason::Call(inputs, out_len, out_offset) => {
+                let ret = self.call_nested(&inputs);
+                StuckReason::CallReturn(ret.result, ret.gas, ret.return_value, out_len, out_offset)
+            }
+            StuckReason::Create(inputs) => {
+                let ret = self.create_nested(&inputs);
+                StuckReason::CreateReturn(ret.result, ret.created_address, ret.gas, ret.return_value)
+            }
+            reason => unreachable!("interpreter does not wait for a frame: {reason:?}"),
+        };
+    }
+
+    /// Commit or revert the changes of a finished call.
+    fn end_call(&mut self, checkpoint: JournalCheckpoint, ret: CallResult) -> CallResult {
+        if matches!(ret.result, return_ok!()) {
             self.data.journaled_state.checkpoint_commit();
         } else {
+            self.data.journaled_state.checkpoint_revert(checkpoint);
+        }
+        ret
+    }
+}
+
+/// Context given to a stateful precompile for the duration of its call.
+struct PrecompileFrame<'b, 'a, DB: Database> {
+    data: &'b mut EVMData<'a, DB>,
+    inputs: &'b CallInputs,
+}
+
+impl<'b, 'a, DB: Database> PrecompileFrame<'b, 'a, DB> {
+    fn load_account(&mut self, address: B160) -> Option<&mut Account> {
+        self.data
+            .journaled_state
+            .load_code(address, self.data.db)
+            .map(|(account, _)| account)
+            .map_err(|e| self.data.error = Some(e))
+            .ok()
+    }
+}
+
+impl<'b, 'a, DB: Database> PrecompileContext for PrecompileFrame<'b, 'a, DB> {
+    fn env(&mut self) -> &mut Env {
+        self.data.env
+    }
+
+    fn address(&self) -> B160 {
+        self.inputs.context.address
+    }
+
+    fn caller(&self) -> B160 {
+        self.inputs.context.caller
+    }
+
+    fn value(&self) -> U256 {
+        self.inputs.context.apparent_value
+    }
+
+    fn depth(&self) -> u64 {
+        self.data.journaled_state.depth()
+    }
+
+    fn is_static(&self) -> bool {
+        self.inputs.is_static
+    }
+
+    fn balance(&mut self, address: B160) -> Option<U256> {
+        Some(self.load_account(address)?.info.balance)
+    }
+
+    fn transfer(&mut self, from: B160, to: B160, value: U256) -> Option<bool> {
+        // load both accounts so database errors are not hidden by the transfer.
+        self.load_account(from)?;
+        self.load_account(to)?;
+        Some(
             self.data
                 .journaled_state
-                .checkpoint_revert(prepared_call.checkpoint);
-        }
+                .transfer(&from, &to, value, self.data.db)
+                .is_ok(),
+        )
+    }
 
-        ret
+    fn code(&mut self, address: B160) -> Option<Bytecode> {
+        self.load_account(address)?.info.code.clone()
+    }
+
+    fn set_code(&mut self, address: B160, code: Bytecode) -> Option<()> {
+        self.load_account(address)?;
+        self.data.journaled_state.set_code(address, code);
+        Some(())
+    }
+
+    fn sload(&mut self, address: B160, index: U256) -> Option<U256> {
+        self.load_account(address)?;
+        self.data
+            .journaled_state
+            .sload(address, index, self.data.db)
+            .map(|(value, _)| value)
+            .map_err(|e| self.data.error = Some(e))
+            .ok()
+    }
+
+    fn sstore(&mut self, address: B160, index: U256, value: U256) -> Option<()> {
+        self.load_account(address)?;
+        self.data
+            .journaled_state
+            .sstore(address, index, value, self.data.db)
+            .map(|_| ())
+            .map_err(|e| self.data.error = Some(e))
+            .ok()
+    }
+
+    fn log(&mut self, topics: Vec<B256>, data: Bytes) {
+        self.data.journaled_state.log(Log {
+            address: self.inputs.context.address,
+            topics,
+            data,
+        });
     }
 }
 
@@ -799,6 +1099,14 @@ impl<'a, GSPEC: Spec, DB: Database + 'a> Host
             .ok()
     }
 
+    fn tload(&mut self, address: B160, index: U256) -> U256 {
+        self.data.journaled_state.tload(address, index)
+    }
+
+    fn tstore(&mut self, address: B160, index: U256, value: U256) {
+        self.data.journaled_state.tstore(address, index, value)
+    }
+
     fn log(&mut self, address: B160, topics: Vec<B256>, data: Bytes) {
         let log = Log {
             address,
@@ -816,3 +1124,156 @@ impl<'a, GSPEC: Spec, DB: Database + 'a> Host
             .ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::InMemoryDB;
+    use crate::primitives::LatestSpec;
+    use crate::interpreter::opcode;
+    use crate::tracer::Operand;
+
+    #[test]
+    fn stateful_precompile_is_journaled() {
+        let hook = B160::from(0xc0de);
+        let mut precompiles = Precompiles::latest().clone();
+        precompiles.insert_stateful(
+            hook.0,
+            |input: &[u8], _gas_limit: u64, context: &mut dyn PrecompileContext| {
+                let address = context.address();
+                let calls = context.sload(address, U256::ZERO).unwrap() + U256::from(1);
+                context.sstore(address, U256::ZERO, calls).unwrap();
+                context.log(Vec::new(), Bytes::copy_from_slice(input));
+                if input.is_empty() {
+                    return Err(precompile::Error::OutOfGas);
+                }
+                Ok((100, calls.to_be_bytes_vec()))
+            },
+        );
+        assert_eq!(precompiles.consecutive_len(), Precompiles::latest().len());
+
+        let mut db = InMemoryDB::default();
+        let mut env = Env::default();
+        env.tx.transact_to = TransactTo::Call(hook);
+        env.tx.data = Bytes::from_static(b"hook");
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, precompiles.clone());
+        let ResultAndState { result, state } = evm.transact(None).unwrap();
+        let ExecutionResult::Success { logs, output, .. } = result else {
+            panic!("precompile failed: {result:?}");
+        };
+        assert_eq!(output.into_data(), U256::from(1).to_be_bytes_vec());
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].address, hook);
+        assert_eq!(
+            state[&hook].storage[&U256::ZERO].present_value,
+            U256::from(1)
+        );
+
+        // failed call reverts the storage write and the log.
+        env.tx.data = Bytes::new();
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, precompiles);
+        let ResultAndState { result, state } = evm.transact(None).unwrap();
+        assert!(matches!(result, ExecutionResult::Halt { .. }));
+        assert!(state[&hook].storage.is_empty());
+    }
+    #[test]
+    fn nested_frames_run_in_place() {
+        // create a child returning 42 and return what the child returns.
+        let parent = B160::from(0x9a4e);
+        let code = crate::interpreter::asm::assemble(
+            "PUSH16 0x67602a5f5260205ff35f5260086018f3 PUSH0 MSTORE
+             PUSH1 16 PUSH1 16 PUSH0 CREATE
+             PUSH1 32 PUSH0 PUSH0 PUSH0 PUSH0 DUP6 GAS CALL POP
+             PUSH1 32 PUSH0 RETURN",
+        )
+        .unwrap();
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            parent,
+            crate::primitives::AccountInfo::new(U256::ZERO, 0, Bytecode::new_raw(code)),
+        );
+        let mut env = Env::default();
+        env.tx.transact_to = TransactTo::Call(parent);
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, Precompiles::latest().clone());
+        let result = evm.transact(None).unwrap().result;
+        assert!(matches!(result, ExecutionResult::Stuck { .. }));
+
+        env.cfg.run_nested_frames = true;
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, Precompiles::latest().clone());
+        let ResultAndState { result, state } = evm.transact(None).unwrap();
+        let ExecutionResult::Success { output, .. } = result else {
+            panic!("call failed: {result:?}");
+        };
+        assert_eq!(output.into_data(), U256::from(42).to_be_bytes_vec());
+        let child = create_address(parent, 0);
+        assert_eq!(state[&child].info.nonce, 1);
+        assert_eq!(state[&parent].info.nonce, 1);
+    }
+
+    #[test]
+    fn trace_records_every_step() {
+        let contract = B160::from(0x7ace);
+        let code = crate::interpreter::asm::assemble("PUSH1 42 PUSH0 MSTORE PUSH1 32 PUSH0 RETURN").unwrap();
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            contract,
+            crate::primitives::AccountInfo::new(U256::ZERO, 0, Bytecode::new_raw(code)),
+        );
+        let mut env = Env::default();
+        env.tx.transact_to = TransactTo::Call(contract);
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, Precompiles::latest().clone());
+        evm.transact(None).unwrap();
+        assert!(evm.take_trace().is_empty());
+
+        env.cfg.trace_steps = true;
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, Precompiles::latest().clone());
+        evm.transact(None).unwrap();
+        let trace = evm.take_trace();
+        let pcs: Vec<_> = trace.iter().map(|step| step.pc).collect();
+        assert_eq!(pcs, [0, 2, 3, 4, 6, 7]);
+        assert_eq!(trace[2].stack, [U256::from(42), U256::ZERO]);
+        assert!(trace[2].memory.is_empty());
+        assert_eq!(trace[3].memory.len(), 32);
+        assert!(trace.iter().all(|step| step.address == contract && step.depth == 1 && !step.resumed));
+        assert!(evm.take_trace().is_empty());
+    }
+
+    #[test]
+    fn events_record_slots_and_preimages() {
+        let contract = B160::from(0x7ace);
+        let code = crate::interpreter::asm::assemble(
+            "PUSH1 7 PUSH1 32 MSTORE PUSH1 64 PUSH0 KECCAK256 PUSH1 1 SSTORE PUSH1 1 SLOAD STOP",
+        )
+        .unwrap();
+        let mut db = InMemoryDB::default();
+        db.insert_account_info(
+            contract,
+            crate::primitives::AccountInfo::new(U256::ZERO, 0, Bytecode::new_raw(code)),
+        );
+        let mut env = Env::default();
+        env.tx.transact_to = TransactTo::Call(contract);
+        env.tx.data = Bytes::from_static(&[0xd0, 0xe3, 0x0d, 0xb0, 0xff]);
+        env.cfg.trace_events = true;
+        let mut evm = EVMImpl::<LatestSpec, _>::new(&mut db, &mut env, Precompiles::latest().clone());
+        evm.transact(None).unwrap();
+        assert!(evm.take_trace().is_empty());
+        let events = evm.take_events();
+        let operands: Vec<_> = events
+            .iter()
+            .filter(|event| event.operand != Operand::None)
+            .map(|event| (event.opcode, event.operand))
+            .collect();
+        let mut preimage = [0; 64];
+        preimage[63] = 7;
+        assert_eq!(
+            operands,
+            [
+                (opcode::KECCAK256, Operand::Preimage(preimage)),
+                (opcode::SSTORE, Operand::Slot(U256::from(1))),
+                (opcode::SLOAD, Operand::Slot(U256::from(1))),
+            ]
+        );
+        assert!(events.iter().all(|event| event.selector == Some([0xd0, 0xe3, 0x0d, 0xb0]) && event.depth == 1));
+        assert!(evm.take_events().is_empty());
+    }
+}
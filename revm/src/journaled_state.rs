@@ -13,6 +13,8 @@ use revm_interpreter::primitives::SpecId::SPURIOUS_DRAGON;
 pub struct JournaledState {
     /// Current state.
     pub state: State,
+    /// EIP-1153 transient storage that is discarded after every transaction.
+    pub transient_storage: TransientStorage,
     /// logs
     pub logs: Vec<Log>,
     /// how deep are we in call stack.
@@ -28,6 +30,9 @@ pub struct JournaledState {
     pub num_of_precompiles: usize,
 }
 
+/// Transient storage keyed by account address and slot.
+pub type TransientStorage = HashMap<(B160, U256), U256>;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum JournalEntry {
@@ -53,6 +58,10 @@ pub enum JournalEntry {
     /// Action: Transfer balance
     /// Revert: Transfer balance back
     BalanceTransfer { from: B160, to: B160, balance: U256 },
+    /// Balance set directly, not moved from another account.
+    /// Action: Set balance
+    /// Revert: Set balance back
+    BalanceChange { address: B160, had_balance: U256 },
     /// Increment nonce
     /// Action: Increment nonce by one
     /// Revert: Decrement nonce by one
@@ -72,9 +81,17 @@ pub enum JournalEntry {
     /// Action: Account code changed
     /// Revert: Revert to previous bytecode.
     CodeChange { address: B160, had_code: Bytecode },
+    /// Transient storage changed
+    /// Action: Transient storage changed
+    /// Revert: Revert to previous value, zero removes the slot
+    TransientStorageChange {
+        address: B160,
+        key: U256,
+        had_value: U256,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 /// SubRoutine checkpoint that will help us to go back from this
 pub struct JournalCheckpoint {
     log_i: usize,
@@ -92,6 +109,7 @@ impl JournaledState {
     pub fn new(num_of_precompiles: usize) -> JournaledState {
         Self {
             state: HashMap::new(),
+            transient_storage: HashMap::new(),
             logs: Vec::new(),
             journal: vec![vec![]],
             depth: 0,
@@ -133,6 +151,7 @@ impl JournaledState {
     /// do cleanup and return modified state
     pub fn finalize(&mut self) -> (State, Vec<Log>) {
         let state = mem::take(&mut self.state);
+        self.transient_storage.clear();
 
         let logs = mem::take(&mut self.logs);
         self.journal = vec![vec![]];
@@ -155,18 +174,30 @@ impl JournaledState {
         let account = self.state.get_mut(&address).unwrap();
         Self::touch_account(self.journal.last_mut().unwrap(), &address, account);
 
+        let had_code = account.info.code.take().unwrap_or_default();
         self.journal
             .last_mut()
             .unwrap()
-            .push(JournalEntry::CodeChange {
-                address,
-                had_code: code.clone(),
-            });
+            .push(JournalEntry::CodeChange { address, had_code });
 
         account.info.code_hash = code.hash();
         account.info.code = Some(code);
     }
 
+    /// Set balance of an account, it is assumed that the account is hot.
+    pub fn set_balance(&mut self, address: B160, balance: U256) {
+        let account = self.state.get_mut(&address).unwrap();
+        Self::touch_account(self.journal.last_mut().unwrap(), &address, account);
+        let had_balance = mem::replace(&mut account.info.balance, balance);
+        self.journal
+            .last_mut()
+            .unwrap()
+            .push(JournalEntry::BalanceChange {
+                address,
+                had_balance,
+            });
+    }
+
     pub fn inc_nonce(&mut self, address: B160) -> Option<u64> {
         let account = self.state.get_mut(&address).unwrap();
         // Check if nonce is going to overflow.
@@ -329,6 +360,7 @@ impl JournaledState {
 
     fn journal_revert(
         state: &mut State,
+        transient_storage: &mut TransientStorage,
         journal_entries: Vec<JournalEntry>,
         is_spurious_dragon_enabled: bool,
     ) {
@@ -379,6 +411,12 @@ impl JournaledState {
                     let to = state.get_mut(&to).unwrap();
                     to.info.balance -= balance;
                 }
+                JournalEntry::BalanceChange {
+                    address,
+                    had_balance,
+                } => {
+                    state.get_mut(&address).unwrap().info.balance = had_balance;
+                }
                 JournalEntry::NonceChange { address } => {
                     state.get_mut(&address).unwrap().info.nonce -= 1;
                 }
@@ -399,6 +437,17 @@ impl JournaledState {
                     acc.info.code_hash = had_code.hash();
                     acc.info.code = Some(had_code);
                 }
+                JournalEntry::TransientStorageChange {
+                    address,
+                    key,
+                    had_value,
+                } => {
+                    if had_value == U256::ZERO {
+                        transient_storage.remove(&(address, key));
+                    } else {
+                        transient_storage.insert((address, key), had_value);
+                    }
+                }
             }
         }
     }
@@ -420,6 +469,7 @@ impl JournaledState {
     pub fn checkpoint_revert(&mut self, checkpoint: JournalCheckpoint) {
         let is_spurious_dragon_enabled = !self.is_before_spurious_dragon;
         let state = &mut self.state;
+        let transient_storage = &mut self.transient_storage;
         self.depth -= 1;
         // iterate over last N journals sets and revert our global state
         let leng = self.journal.len();
@@ -427,7 +477,14 @@ impl JournaledState {
             .iter_mut()
             .rev()
             .take(leng - checkpoint.journal_i)
-            .for_each(|cs| Self::journal_revert(state, mem::take(cs), is_spurious_dragon_enabled));
+            .for_each(|cs| {
+                Self::journal_revert(
+                    state,
+                    transient_storage,
+                    mem::take(cs),
+                    is_spurious_dragon_enabled,
+                )
+            });
 
         self.logs.truncate(checkpoint.log_i);
         self.journal.truncate(checkpoint.journal_i);
@@ -658,6 +715,35 @@ impl JournaledState {
         Ok((slot.original_value, present, new, is_cold))
     }
 
+    /// Read transient storage, slots that were not written are zero.
+    pub fn tload(&mut self, address: B160, key: U256) -> U256 {
+        self.transient_storage
+            .get(&(address, key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Write transient storage. Zero values are removed so the map only holds live slots.
+    pub fn tstore(&mut self, address: B160, key: U256, new: U256) {
+        let had_value = if new == U256::ZERO {
+            self.transient_storage.remove(&(address, key))
+        } else {
+            self.transient_storage.insert((address, key), new)
+        }
+        .unwrap_or_default();
+
+        if had_value != new {
+            self.journal
+                .last_mut()
+                .unwrap()
+                .push(JournalEntry::TransientStorageChange {
+                    address,
+                    key,
+                    had_value,
+                });
+        }
+    }
+
     /// push log into subroutine
     pub fn log(&mut self, log: Log) {
         self.logs.push(log);
@@ -721,4 +807,30 @@ mod test {
             "0x000..3 is precompile"
         );
     }
+
+    #[test]
+    fn transient_storage_reverts_with_frames() {
+        let address = B160([7; 20]);
+        let (one, two) = (U256::from(1), U256::from(2));
+        let mut journal = JournaledState::new(0);
+
+        journal.tstore(address, U256::ZERO, one);
+        let checkpoint = journal.checkpoint();
+        journal.tstore(address, U256::ZERO, two);
+        journal.tstore(address, one, two);
+        assert_eq!(journal.tload(address, one), two);
+        journal.checkpoint_revert(checkpoint);
+        assert_eq!(journal.tload(address, U256::ZERO), one);
+        assert_eq!(journal.tload(address, one), U256::ZERO);
+
+        journal.checkpoint();
+        journal.tstore(address, U256::ZERO, U256::ZERO);
+        journal.checkpoint_commit();
+        assert_eq!(journal.tload(address, U256::ZERO), U256::ZERO);
+
+        journal.tstore(address, one, one);
+        journal.finalize();
+        assert_eq!(journal.tload(address, one), U256::ZERO);
+        assert!(journal.transient_storage.is_empty());
+    }
 }
@@ -1,10 +1,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+mod cheatcodes;
 pub mod db;
 mod evm;
 mod evm_impl;
 mod result;
 mod journaled_state;
+mod tracer;
 
 #[cfg(all(feature = "with-serde", not(feature = "serde")))]
 compile_error!("`with-serde` feature has been renamed to `serde`.");
@@ -12,11 +14,13 @@ compile_error!("`with-serde` feature has been renamed to `serde`.");
 pub(crate) const USE_GAS: bool = !cfg!(feature = "no_gas_measuring");
 pub type DummyStateDB = InMemoryDB;
 
+pub use cheatcodes::{Cheatcodes, CHEATCODE_ADDRESS};
 pub use db::{Database, DatabaseCommit, InMemoryDB};
 pub use evm::{evm_inner, new, EVM};
 pub use result::{ResultAndState, ExecutionResult};
 pub use evm_impl::{EVMData, EVMImpl, Transact, CallResult, CreateResult};
-pub use journaled_state::{JournalEntry, JournaledState};
+pub use journaled_state::{JournalCheckpoint, JournalEntry, JournaledState, TransientStorage};
+pub use tracer::{Operand, StepEvent, TraceStep};
 
 extern crate alloc;
 
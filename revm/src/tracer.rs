@@ -0,0 +1,110 @@
+//! Interpreter state recorded before every instruction, for debuggers and replay tools.
+//!
+//! Recording is enabled with [`CfgEnv::trace_steps`](crate::primitives::CfgEnv) and the steps
+//! are taken out of the [`EVMImpl`](crate::EVMImpl) with `take_trace`. Watchers that run on
+//! every move of a search record the lighter [`StepEvent`] instead, enabled with
+//! [`CfgEnv::trace_events`](crate::primitives::CfgEnv) and taken with `take_events`.
+
+use crate::interpreter::{opcode, Interpreter, StuckReason};
+use crate::primitives::{Bytes, B160, U256};
+use alloc::vec::Vec;
+
+/// Interpreter state right before an instruction runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Call depth of the frame.
+    pub depth: u64,
+    /// Address of the executing contract.
+    pub address: B160,
+    /// Call data of the frame.
+    pub input: Bytes,
+    pub pc: usize,
+    pub opcode: u8,
+    pub gas_remaining: u64,
+    /// Bottom of the stack first.
+    pub stack: Vec<U256>,
+    pub memory: Bytes,
+    pub return_data: Bytes,
+    /// The instruction picks up the result of the call or create the frame got stuck at,
+    /// instead of running for the first time.
+    pub resumed: bool,
+}
+
+impl TraceStep {
+    pub fn new(interpreter: &Interpreter, depth: u64) -> Self {
+        Self {
+            depth,
+            address: interpreter.contract.address,
+            input: interpreter.contract.input.clone(),
+            pc: interpreter.program_counter(),
+            opcode: interpreter.current_opcode(),
+            gas_remaining: interpreter.gas.remaining(),
+            stack: interpreter.stack.data().clone(),
+            memory: Bytes::copy_from_slice(interpreter.memory.data()),
+            return_data: interpreter.return_data_buffer.clone(),
+            resumed: !matches!(interpreter.stuck_reason, StuckReason::Execute),
+        }
+    }
+}
+
+/// Where an instruction runs and the storage slot or hash preimage it reads, without copies
+/// of the stack and memory of the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepEvent {
+    /// Call depth of the frame.
+    pub depth: u64,
+    /// Address of the executing contract.
+    pub address: B160,
+    /// First four bytes of the call data of the frame.
+    pub selector: Option<[u8; 4]>,
+    pub pc: usize,
+    pub opcode: u8,
+    /// See [`TraceStep::resumed`].
+    pub resumed: bool,
+    pub operand: Operand,
+}
+
+/// What an event records of the operands of its instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    None,
+    /// Slot of an `SLOAD` or `SSTORE`.
+    Slot(U256),
+    /// Preimage of a `KECCAK256` of 64 bytes in memory, e.g. of an entry of a mapping.
+    Preimage([u8; 64]),
+}
+
+impl StepEvent {
+    pub fn new(interpreter: &Interpreter, depth: u64) -> Self {
+        let opcode = interpreter.current_opcode();
+        let top = |n: usize| interpreter.stack.peek(n).ok();
+        let operand = match opcode {
+            opcode::SLOAD | opcode::SSTORE => top(0).map_or(Operand::None, Operand::Slot),
+            opcode::KECCAK256 if top(1) == Some(U256::from(64)) => top(0)
+                .and_then(|offset| usize::try_from(offset).ok())
+                .and_then(|offset| {
+                    interpreter
+                        .memory
+                        .data()
+                        .get(offset..offset.checked_add(64)?)
+                })
+                .map_or(Operand::None, |preimage| {
+                    Operand::Preimage(preimage.try_into().unwrap())
+                }),
+            _ => Operand::None,
+        };
+        Self {
+            depth,
+            address: interpreter.contract.address,
+            selector: interpreter
+                .contract
+                .input
+                .get(..4)
+                .map(|selector| selector.try_into().unwrap()),
+            pc: interpreter.program_counter(),
+            opcode,
+            resumed: !matches!(interpreter.stuck_reason, StuckReason::Execute),
+            operand,
+        }
+    }
+}
@@ -0,0 +1,45 @@
+mod statetest;
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Nested frames recurse on the native stack, deep call chains need more than the default.
+const STACK_SIZE: usize = 64 * 1024 * 1024;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let command = args.next();
+    let paths: Vec<PathBuf> = args.map(PathBuf::from).collect();
+    if command.as_deref() != Some("statetest") || paths.is_empty() {
+        eprintln!("usage: revme statetest <path>...");
+        return ExitCode::FAILURE;
+    }
+
+    let summary = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let tests: Vec<PathBuf> = paths
+                .iter()
+                .flat_map(|path| statetest::find_all_json_tests(path))
+                .collect();
+            statetest::run(&tests)
+        })
+        .expect("failed to spawn the test thread")
+        .join()
+        .expect("test thread panicked");
+
+    for error in &summary.errors {
+        eprintln!("{error}");
+    }
+    println!(
+        "passed: {}, skipped: {}, failed: {}",
+        summary.passed,
+        summary.skipped,
+        summary.errors.len()
+    );
+    if summary.errors.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
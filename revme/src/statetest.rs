@@ -0,0 +1,10 @@
+//! Runner for the GeneralStateTests of ethereum/tests.
+//!
+//! Nested frames are run in place (see `CfgEnv::run_nested_frames`), so a transaction executes to
+//! completion and the resulting state root and logs hash are compared against the fixture.
+
+mod merkle_trie;
+mod models;
+mod runner;
+
+pub use runner::{find_all_json_tests, run};
@@ -0,0 +1,41 @@
+//! State root and logs hash of an execution, as committed to by the fixtures.
+
+use revm::db::{AccountState, DbAccount};
+use revm::primitives::{keccak256, sec_trie_root, trie_account_rlp, Log, B160, B256};
+use rlp::RlpStream;
+
+/// Keccak of the RLP list of `[address, topics, data]` of every log.
+pub fn log_rlp_hash(logs: &[Log]) -> B256 {
+    let mut stream = RlpStream::new();
+    stream.begin_unbounded_list();
+    for log in logs {
+        stream.begin_list(3);
+        stream.append(&log.address.as_bytes());
+        stream.begin_list(log.topics.len());
+        for topic in &log.topics {
+            stream.append(&topic.as_bytes());
+        }
+        stream.append(&log.data.as_ref());
+    }
+    stream.finalize_unbounded_list();
+    keccak256(&stream.out())
+}
+
+pub fn state_merkle_trie_root<'a>(
+    accounts: impl IntoIterator<Item = (&'a B160, &'a DbAccount)>,
+) -> B256 {
+    sec_trie_root(accounts.into_iter().map(|(address, account)| {
+        (
+            address,
+            trie_account_rlp(&account.info, account.storage_root()),
+        )
+    }))
+}
+
+/// Accounts present in the state of the spec, empty accounts are removed since EIP-161.
+pub fn is_in_state(account: &DbAccount, is_legacy: bool) -> bool {
+    if is_legacy {
+        return !matches!(account.account_state, AccountState::NotExisting);
+    }
+    !account.info.is_empty() || matches!(account.account_state, AccountState::None)
+}
@@ -0,0 +1,156 @@
+//! Format of the GeneralStateTests fixtures of ethereum/tests.
+
+use revm::primitives::{Bytes, SpecId, B160, B256, U256};
+use serde::{de, Deserialize, Deserializer};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct TestSuite(pub BTreeMap<String, TestUnit>);
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct TestUnit {
+    pub env: Env,
+    pub pre: HashMap<B160, AccountInfo>,
+    pub post: BTreeMap<SpecName, Vec<Test>>,
+    pub transaction: TransactionParts,
+}
+
+/// Expected outcome of the transaction built from `indexes`.
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Test {
+    pub indexes: TxPartIndices,
+    /// Post state root.
+    pub hash: B256,
+    /// Hash of the RLP of the logs.
+    pub logs: B256,
+    pub expect_exception: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct TxPartIndices {
+    pub data: usize,
+    pub gas: usize,
+    pub value: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct AccountInfo {
+    pub balance: U256,
+    #[serde(with = "revm::primitives::utilities::serde_hex_bytes")]
+    pub code: Bytes,
+    #[serde(deserialize_with = "deserialize_u64")]
+    pub nonce: u64,
+    pub storage: HashMap<U256, U256>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Env {
+    pub current_coinbase: B160,
+    #[serde(default)]
+    pub current_difficulty: U256,
+    pub current_gas_limit: U256,
+    pub current_number: U256,
+    pub current_timestamp: U256,
+    pub current_base_fee: Option<U256>,
+    pub current_random: Option<B256>,
+}
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionParts {
+    #[serde(deserialize_with = "deserialize_bytes_vec")]
+    pub data: Vec<Bytes>,
+    pub access_lists: Option<Vec<Option<AccessList>>>,
+    pub gas_limit: Vec<U256>,
+    pub gas_price: Option<U256>,
+    pub max_fee_per_gas: Option<U256>,
+    pub max_priority_fee_per_gas: Option<U256>,
+    pub nonce: U256,
+    pub secret_key: B256,
+    /// Newer fixtures give the sender, older ones only its secret key.
+    pub sender: Option<B160>,
+    /// Empty for contract creation.
+    #[serde(deserialize_with = "deserialize_maybe_empty")]
+    pub to: Option<B160>,
+    pub value: Vec<U256>,
+}
+
+pub type AccessList = Vec<AccessListItem>;
+
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessListItem {
+    pub address: B160,
+    pub storage_keys: Vec<B256>,
+}
+
+/// Fork names used in fixtures. Transition forks are not supported and end up as `Unknown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+pub enum SpecName {
+    Frontier,
+    Homestead,
+    #[serde(alias = "EIP150")]
+    TangerineWhistle,
+    #[serde(alias = "EIP158")]
+    SpuriousDragon,
+    Byzantium,
+    Constantinople,
+    #[serde(alias = "ConstantinopleFix")]
+    Petersburg,
+    Istanbul,
+    Berlin,
+    London,
+    #[serde(alias = "Paris")]
+    Merge,
+    Shanghai,
+    Cancun,
+    #[serde(other)]
+    Unknown,
+}
+
+impl SpecName {
+    pub fn to_spec_id(self) -> Option<SpecId> {
+        Some(match self {
+            Self::Frontier => SpecId::FRONTIER,
+            Self::Homestead => SpecId::HOMESTEAD,
+            Self::TangerineWhistle => SpecId::TANGERINE,
+            Self::SpuriousDragon => SpecId::SPURIOUS_DRAGON,
+            Self::Byzantium => SpecId::BYZANTIUM,
+            Self::Constantinople => SpecId::CONSTANTINOPLE,
+            Self::Petersburg => SpecId::PETERSBURG,
+            Self::Istanbul => SpecId::ISTANBUL,
+            Self::Berlin => SpecId::BERLIN,
+            Self::London => SpecId::LONDON,
+            Self::Merge => SpecId::MERGE,
+            Self::Shanghai => SpecId::SHANGHAI,
+            Self::Cancun => SpecId::CANCUN,
+            Self::Unknown => return None,
+        })
+    }
+}
+
+fn deserialize_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    let value = U256::deserialize(deserializer)?;
+    u64::try_from(value).map_err(de::Error::custom)
+}
+
+fn deserialize_bytes_vec<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Bytes>, D::Error> {
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(with = "revm::primitives::utilities::serde_hex_bytes")] Bytes);
+    let data = Vec::<Wrapper>::deserialize(deserializer)?;
+    Ok(data.into_iter().map(|Wrapper(bytes)| bytes).collect())
+}
+
+fn deserialize_maybe_empty<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<B160>, D::Error> {
+    let string = String::deserialize(deserializer)?;
+    if string.is_empty() {
+        return Ok(None);
+    }
+    string.parse().map(Some).map_err(de::Error::custom)
+}
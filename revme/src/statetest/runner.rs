@@ -0,0 +1,339 @@
+use super::merkle_trie::{is_in_state, log_rlp_hash, state_merkle_trie_root};
+use super::models::{SpecName, TestSuite, TestUnit};
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{
+    keccak256, AccountInfo, Bytecode, Env, SpecId, TransactTo, B160, B256, U256,
+};
+use revm::ExecutionResult;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// Fixtures that exercise values this runner does not model (e.g. nonces and values above the
+/// ranges of the env types).
+const SKIPPED: &[&str] = &["ValueOverflow.json", "CreateTransactionHighNonce.json"];
+
+#[derive(Debug)]
+pub enum TestError {
+    Io(PathBuf, std::io::Error),
+    Decode(PathBuf, serde_json::Error),
+    UnknownSender(String),
+    UnexpectedException {
+        name: String,
+        spec: SpecName,
+        index: usize,
+        expected: Option<String>,
+        got: Option<String>,
+    },
+    LogsMismatch {
+        name: String,
+        spec: SpecName,
+        index: usize,
+        expected: B256,
+        got: B256,
+    },
+    StateRootMismatch {
+        name: String,
+        spec: SpecName,
+        index: usize,
+        expected: B256,
+        got: B256,
+    },
+}
+
+impl fmt::Display for TestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::Decode(path, err) => write!(f, "{}: {err}", path.display()),
+            Self::UnknownSender(name) => write!(f, "{name}: unknown private key"),
+            Self::UnexpectedException {
+                name,
+                spec,
+                index,
+                expected,
+                got,
+            } => write!(
+                f,
+                "{name} {spec:?} #{index}: expected exception {expected:?}, got {got:?}"
+            ),
+            Self::LogsMismatch {
+                name,
+                spec,
+                index,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "{name} {spec:?} #{index}: logs hash {got:?}, expected {expected:?}"
+                )
+            }
+            Self::StateRootMismatch {
+                name,
+                spec,
+                index,
+                expected,
+                got,
+            } => {
+                write!(
+                    f,
+                    "{name} {spec:?} #{index}: state root {got:?}, expected {expected:?}"
+                )
+            }
+        }
+    }
+}
+
+/// Outcome of a run over a set of fixtures.
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub skipped: usize,
+    pub errors: Vec<TestError>,
+}
+
+/// All json fixtures under `path`, which may itself be a single fixture.
+pub fn find_all_json_tests(path: &Path) -> Vec<PathBuf> {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry: &DirEntry| entry.file_name().to_string_lossy().ends_with(".json"))
+        .map(DirEntry::into_path)
+        .collect()
+}
+
+pub fn run(paths: &[PathBuf]) -> Summary {
+    let mut summary = Summary::default();
+    for path in paths {
+        if path
+            .file_name()
+            .is_some_and(|name| SKIPPED.contains(&name.to_string_lossy().as_ref()))
+        {
+            summary.skipped += 1;
+            continue;
+        }
+        let suite = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(err) => {
+                summary.errors.push(TestError::Io(path.clone(), err));
+                continue;
+            }
+        };
+        match serde_json::from_str::<TestSuite>(&suite) {
+            Ok(suite) => {
+                for (name, unit) in suite.0 {
+                    execute_test_unit(&name, &unit, &mut summary);
+                }
+            }
+            Err(err) => summary.errors.push(TestError::Decode(path.clone(), err)),
+        }
+    }
+    summary
+}
+
+pub fn execute_test_unit(name: &str, unit: &TestUnit, summary: &mut Summary) {
+    let caller = match unit.transaction.sender {
+        Some(sender) => sender,
+        None => match recover_address(&unit.transaction.secret_key) {
+            Some(caller) => caller,
+            None => {
+                summary
+                    .errors
+                    .push(TestError::UnknownSender(name.to_string()));
+                return;
+            }
+        },
+    };
+
+    let mut pre = CacheDB::new(EmptyDB::default());
+    for (address, info) in &unit.pre {
+        let code = Bytecode::new_raw(info.code.clone());
+        pre.insert_account_info(*address, AccountInfo::new(info.balance, info.nonce, code));
+        for (slot, value) in &info.storage {
+            pre.insert_account_storage(*address, *slot, *value).unwrap();
+        }
+    }
+
+    let mut env = Env::default();
+    env.cfg.chain_id = U256::from(1);
+    env.cfg.run_nested_frames = true;
+    env.block.number = unit.env.current_number;
+    env.block.coinbase = unit.env.current_coinbase;
+    env.block.timestamp = unit.env.current_timestamp;
+    env.block.gas_limit = unit.env.current_gas_limit;
+    env.block.basefee = unit.env.current_base_fee.unwrap_or_default();
+    env.block.difficulty = unit.env.current_difficulty;
+    env.block.prevrandao = unit.env.current_random;
+
+    let tx = &unit.transaction;
+    env.tx.caller = caller;
+    env.tx.gas_price = tx.gas_price.or(tx.max_fee_per_gas).unwrap_or_default();
+    env.tx.gas_priority_fee = tx.max_priority_fee_per_gas;
+    env.tx.transact_to = match tx.to {
+        Some(to) => TransactTo::Call(to),
+        None => TransactTo::create(),
+    };
+    env.tx.nonce = u64::try_from(tx.nonce).ok();
+
+    for (spec, tests) in &unit.post {
+        let Some(spec_id) = spec.to_spec_id() else {
+            summary.skipped += tests.len();
+            continue;
+        };
+        env.cfg.spec_id = spec_id;
+
+        for (index, test) in tests.iter().enumerate() {
+            env.tx.gas_limit = tx.gas_limit[test.indexes.gas].saturating_to();
+            env.tx.data = tx.data[test.indexes.data].clone();
+            env.tx.value = tx.value[test.indexes.value];
+            env.tx.access_list = tx
+                .access_lists
+                .as_ref()
+                .and_then(|lists| lists.get(test.indexes.data))
+                .and_then(Option::as_ref)
+                .into_iter()
+                .flatten()
+                .map(|item| {
+                    let slots = item
+                        .storage_keys
+                        .iter()
+                        .map(|key| U256::from_be_bytes(key.0))
+                        .collect();
+                    (item.address, slots)
+                })
+                .collect();
+
+            let mut evm = revm::new();
+            evm.env = env.clone();
+            evm.database(pre.clone());
+            let result = evm.transact_commit();
+            let db = evm.take_db();
+
+            let logs = match &result {
+                Ok(ExecutionResult::Success { logs, .. }) => logs.as_slice(),
+                _ => &[],
+            };
+            let exception = result.as_ref().err().map(ToString::to_string);
+            if test.expect_exception.is_some() != exception.is_some() {
+                summary.errors.push(TestError::UnexpectedException {
+                    name: name.to_string(),
+                    spec: *spec,
+                    index,
+                    expected: test.expect_exception.clone(),
+                    got: exception,
+                });
+                continue;
+            }
+
+            let logs_hash = log_rlp_hash(logs);
+            if logs_hash != test.logs {
+                summary.errors.push(TestError::LogsMismatch {
+                    name: name.to_string(),
+                    spec: *spec,
+                    index,
+                    expected: test.logs,
+                    got: logs_hash,
+                });
+                continue;
+            }
+
+            let is_legacy = !SpecId::enabled(spec_id, SpecId::SPURIOUS_DRAGON);
+            let state_root = state_merkle_trie_root(
+                db.accounts
+                    .iter()
+                    .filter(|(_, account)| is_in_state(account, is_legacy)),
+            );
+            if state_root != test.hash {
+                summary.errors.push(TestError::StateRootMismatch {
+                    name: name.to_string(),
+                    spec: *spec,
+                    index,
+                    expected: test.hash,
+                    got: state_root,
+                });
+                continue;
+            }
+            summary.passed += 1;
+        }
+    }
+}
+
+/// Address of the account controlled by `secret_key`.
+fn recover_address(secret_key: &B256) -> Option<B160> {
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::from_slice(secret_key.as_bytes()).ok()?;
+    let public_key = PublicKey::from_secret_key(&secp, &secret_key).serialize_uncompressed();
+    Some(B160::from_slice(&keccak256(&public_key[1..])[12..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Roots computed independently of `triehash`.
+    const FIXTURE: &str = r#"{
+        "callSstoreAndLog": {
+            "env": {
+                "currentCoinbase": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+                "currentDifficulty": "0x00",
+                "currentGasLimit": "0x05f5e100",
+                "currentNumber": "0x01",
+                "currentTimestamp": "0x03e8",
+                "currentBaseFee": "0x07",
+                "currentRandom": "0x0000000000000000000000000000000000000000000000000000000000020000"
+            },
+            "pre": {
+                "0xa94f5374fce5edbc8e2a8697c15331677e6ebf0b": {
+                    "balance": "0x0de0b6b3a7640000", "code": "0x", "nonce": "0x00", "storage": {}
+                },
+                "0x1000000000000000000000000000000000000000": {
+                    "balance": "0x00",
+                    "code": "0x600060006000600060007320000000000000000000000000000000000000005af15060006000a000",
+                    "nonce": "0x00",
+                    "storage": {}
+                },
+                "0x2000000000000000000000000000000000000000": {
+                    "balance": "0x00", "code": "0x602a60005500", "nonce": "0x00", "storage": {}
+                }
+            },
+            "post": {
+                "Shanghai": [
+                    {
+                        "indexes": { "data": 0, "gas": 0, "value": 0 },
+                        "hash": "0x80e70d2b846e2228bef8f87689133629730f627ce4fee1bba548eb2675afc7e6",
+                        "logs": "0x13b52f9db0672b6060dd2f45b55e3355ec69e16cb3aa8f49ca88e294299c9b52"
+                    },
+                    {
+                        "indexes": { "data": 0, "gas": 1, "value": 0 },
+                        "hash": "0xb8a69812164b75809b7a33d92805ef66d17aaae61747e7abc7c2edc8df6e458a",
+                        "logs": "0x1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+                        "expectException": "TR_IntrinsicGas"
+                    }
+                ],
+                "Shanghai+3855": []
+            },
+            "transaction": {
+                "data": ["0x"],
+                "gasLimit": ["0x0186a0", "0x4e20"],
+                "gasPrice": "0x0a",
+                "nonce": "0x00",
+                "secretKey": "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8",
+                "to": "0x1000000000000000000000000000000000000000",
+                "value": ["0x00"]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn fixture_state_root_and_logs() {
+        let suite: TestSuite = serde_json::from_str(FIXTURE).unwrap();
+        let mut summary = Summary::default();
+        for (name, unit) in &suite.0 {
+            execute_test_unit(name, unit, &mut summary);
+        }
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(summary.passed, 2);
+    }
+}